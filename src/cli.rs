@@ -6,23 +6,166 @@ pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 /// This includes the architecture the code is for, whether it is 32- or 64-bits etc.
 #[derive(clap::Parser)]
 #[clap(author, version, about, long_about = None)]
+#[clap(subcommand_negates_reqs = true)]
 pub struct Arguments {
     #[clap(short, long)]
     pub version: bool,
 
-    /// The path to the ELF file
-    #[clap(parse(from_os_str))]
-    pub path: PathBuf,
+    #[clap(subcommand)]
+    pub command: Option<Command>,
 
-    /// Print the ELF header
+    /// The path to the ELF file or static archive
+    #[clap(parse(from_os_str), required = true)]
+    pub path: Option<PathBuf>,
+
+    /// Print the symbol index of an archive
+    #[clap(short = 'c', long = "archive-index")]
+    pub archive_index: bool,
+
+    /// Print the program headers
     #[clap(short, long = "program-header")]
     pub program_header: bool,
 
-    /// Print the program headers
+    /// Print the section headers
     #[clap(short, long = "section-header")]
     pub section_header: bool,
 
-    /// Print the section headers
+    /// Print the ELF header
     #[clap(short, long = "header")]
     pub header: bool,
+
+    /// Print the entries of the symbol tables
+    #[clap(long)]
+    pub symbols: bool,
+
+    /// Print the entries of the dynamic section
+    #[clap(long)]
+    pub dynamic: bool,
+
+    /// Print the entries of the relocation sections
+    #[clap(long)]
+    pub relocations: bool,
+
+    /// Print the notes
+    #[clap(long)]
+    pub notes: bool,
+
+    /// Print the symbol versioning sections
+    #[clap(short = 'V', long = "version-info")]
+    pub version_info: bool,
+
+    /// Print a histogram of the bucket list lengths of the hash tables
+    #[clap(short = 'I', long)]
+    pub histogram: bool,
+
+    /// Look up a dynamic symbol through the hash tables
+    #[clap(long, value_name = "NAME")]
+    pub lookup: Option<String>,
+
+    /// Print the threads, registers, signal and mapped files of a core dump
+    #[clap(long)]
+    pub core: bool,
+
+    /// Print the section groups
+    #[clap(short = 'g', long = "section-groups")]
+    pub section_groups: bool,
+
+    /// Keep unknown values and print violations as warnings instead of aborting
+    #[clap(long)]
+    pub lenient: bool,
+}
+
+#[derive(clap::Subcommand)]
+pub enum Command {
+    /// Modify the program interpreter and the dynamic section of an executable or shared object
+    Edit(EditArguments),
+    /// Remove debug information, the symbol table or other sections. Without options the debug
+    /// sections and the symbol table are removed.
+    Strip(StripArguments),
+    /// Translate a virtual address to a file offset and print the segment and the section
+    /// containing it
+    Addr(AddrArguments),
+}
+
+#[derive(clap::Args)]
+pub struct EditArguments {
+    /// The path to the ELF file, which is modified in place unless an output path is given
+    #[clap(parse(from_os_str))]
+    pub path: PathBuf,
+
+    /// Write the modified file to this path
+    #[clap(short, long, parse(from_os_str))]
+    pub output: Option<PathBuf>,
+
+    /// Set the path of the program interpreter
+    #[clap(long, value_name = "PATH")]
+    pub set_interpreter: Option<String>,
+
+    /// Set the library search path (DT_RUNPATH, or DT_RPATH if only that is present)
+    #[clap(long, value_name = "PATHS")]
+    pub set_rpath: Option<String>,
+
+    /// Set the shared object name (DT_SONAME)
+    #[clap(long, value_name = "NAME")]
+    pub set_soname: Option<String>,
+
+    /// Add a needed library (DT_NEEDED), may be given multiple times
+    #[clap(long, value_name = "LIBRARY", multiple_occurrences = true)]
+    pub add_needed: Vec<String>,
+
+    /// Remove a needed library (DT_NEEDED), may be given multiple times
+    #[clap(long, value_name = "LIBRARY", multiple_occurrences = true)]
+    pub remove_needed: Vec<String>,
+}
+
+#[derive(clap::Args)]
+pub struct StripArguments {
+    /// The path to the ELF file, which is modified in place unless an output path is given
+    #[clap(parse(from_os_str))]
+    pub path: PathBuf,
+
+    /// Write the stripped file to this path
+    #[clap(short, long, parse(from_os_str))]
+    pub output: Option<PathBuf>,
+
+    /// Remove the debug sections
+    #[clap(short = 'g', long)]
+    pub strip_debug: bool,
+
+    /// Remove the debug sections and the symbol table
+    #[clap(short, long)]
+    pub strip_all: bool,
+
+    /// Remove the section with this name, may be given multiple times
+    #[clap(short = 'R', long, value_name = "NAME", multiple_occurrences = true)]
+    pub remove_section: Vec<String>,
+
+    /// Write the removed sections to this file and link it with a .gnu_debuglink section
+    #[clap(long, parse(from_os_str), value_name = "PATH")]
+    pub debug_file: Option<PathBuf>,
+}
+
+#[derive(clap::Args)]
+pub struct AddrArguments {
+    /// The path to the ELF file
+    #[clap(parse(from_os_str))]
+    pub path: PathBuf,
+
+    /// The virtual address, hexadecimal with a 0x prefix or decimal
+    #[clap(parse(try_from_str = parse_number))]
+    pub address: u64,
+
+    /// Treat the address as a file offset and translate it to a virtual address
+    #[clap(long)]
+    pub offset: bool,
+}
+
+fn parse_number(value: &str) -> Result<u64, std::num::ParseIntError> {
+    match value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => value.parse(),
+    }
 }
@@ -0,0 +1,367 @@
+use std::fmt::{Display, Formatter};
+
+use super::*;
+
+/// The processor specific `flags` of the ELF header, decoded for the architectures which
+/// define them.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum ArchFlags {
+    Arm(ArmFlags),
+    Mips(MipsFlags),
+    RiscV(RiscVFlags),
+    PowerPC64(PowerPC64Flags),
+    /// Architectures without decoded flags
+    Other(u32),
+}
+
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum ArmFloatAbi {
+    Unspecified,
+    Soft,
+    Hard,
+}
+
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct ArmFlags {
+    /// The EABI version, 0 for files not conforming to the ARM EABI
+    pub eabi_version: u8,
+    pub float_abi: ArmFloatAbi,
+    /// BE8 byte order, big endian data with little endian code
+    pub be8: bool,
+}
+
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum MipsIsa {
+    Mips1,
+    Mips2,
+    Mips3,
+    Mips4,
+    Mips5,
+    Mips32,
+    Mips64,
+    Mips32R2,
+    Mips64R2,
+    Mips32R6,
+    Mips64R6,
+    Unknown(u8),
+}
+
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum MipsAbi {
+    Unspecified,
+    O32,
+    O64,
+    N32,
+    Eabi32,
+    Eabi64,
+    Unknown(u16),
+}
+
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct MipsFlags {
+    pub isa: MipsIsa,
+    pub abi: MipsAbi,
+    pub noreorder: bool,
+    pub pic: bool,
+    pub cpic: bool,
+    /// IEEE 754-2008 NaN encoding
+    pub nan2008: bool,
+    /// 64-bit floating point registers in 32-bit code
+    pub fp64: bool,
+}
+
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum RiscVFloatAbi {
+    Soft,
+    Single,
+    Double,
+    Quad,
+}
+
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct RiscVFlags {
+    /// Compressed instructions are used
+    pub rvc: bool,
+    pub float_abi: RiscVFloatAbi,
+    /// The reduced register set of RV32E/RV64E is used
+    pub rve: bool,
+    /// Total store ordering is required
+    pub tso: bool,
+}
+
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum PowerPC64Abi {
+    Unspecified,
+    /// The original ABI with function descriptors
+    ElfV1,
+    ElfV2,
+    Unknown(u8),
+}
+
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct PowerPC64Flags {
+    pub abi: PowerPC64Abi,
+}
+
+impl ArchFlags {
+    pub fn decode(arch: Arch, flags: u32) -> ArchFlags {
+        match arch {
+            Arch::Arm => ArchFlags::Arm(ArmFlags::decode(flags)),
+            Arch::Mips => ArchFlags::Mips(MipsFlags::decode(flags)),
+            Arch::RiscV => ArchFlags::RiscV(RiscVFlags::decode(flags)),
+            Arch::PowerPC64 => ArchFlags::PowerPC64(PowerPC64Flags::decode(flags)),
+            _ => ArchFlags::Other(flags),
+        }
+    }
+}
+
+impl ArmFlags {
+    pub const fn decode(flags: u32) -> ArmFlags {
+        let float_abi = if flags & 0x400 != 0 {
+            ArmFloatAbi::Hard
+        } else if flags & 0x200 != 0 {
+            ArmFloatAbi::Soft
+        } else {
+            ArmFloatAbi::Unspecified
+        };
+        ArmFlags {
+            eabi_version: (flags >> 24) as u8,
+            float_abi,
+            be8: flags & 0x00800000 != 0,
+        }
+    }
+}
+
+impl MipsFlags {
+    pub const fn decode(flags: u32) -> MipsFlags {
+        let isa = match (flags >> 28) as u8 {
+            0x0 => MipsIsa::Mips1,
+            0x1 => MipsIsa::Mips2,
+            0x2 => MipsIsa::Mips3,
+            0x3 => MipsIsa::Mips4,
+            0x4 => MipsIsa::Mips5,
+            0x5 => MipsIsa::Mips32,
+            0x6 => MipsIsa::Mips64,
+            0x7 => MipsIsa::Mips32R2,
+            0x8 => MipsIsa::Mips64R2,
+            0x9 => MipsIsa::Mips32R6,
+            0xA => MipsIsa::Mips64R6,
+            isa => MipsIsa::Unknown(isa),
+        };
+        let abi = match (flags & 0xF000) as u16 {
+            // n32 is marked by the ABI2 flag instead of an ABI value
+            0x0000 if flags & 0x20 != 0 => MipsAbi::N32,
+            0x0000 => MipsAbi::Unspecified,
+            0x1000 => MipsAbi::O32,
+            0x2000 => MipsAbi::O64,
+            0x3000 => MipsAbi::Eabi32,
+            0x4000 => MipsAbi::Eabi64,
+            abi => MipsAbi::Unknown(abi),
+        };
+        MipsFlags {
+            isa,
+            abi,
+            noreorder: flags & 0x1 != 0,
+            pic: flags & 0x2 != 0,
+            cpic: flags & 0x4 != 0,
+            nan2008: flags & 0x400 != 0,
+            fp64: flags & 0x200 != 0,
+        }
+    }
+}
+
+impl RiscVFlags {
+    pub const fn decode(flags: u32) -> RiscVFlags {
+        let float_abi = match flags & 0x6 {
+            0x0 => RiscVFloatAbi::Soft,
+            0x2 => RiscVFloatAbi::Single,
+            0x4 => RiscVFloatAbi::Double,
+            _ => RiscVFloatAbi::Quad,
+        };
+        RiscVFlags {
+            rvc: flags & 0x1 != 0,
+            float_abi,
+            rve: flags & 0x8 != 0,
+            tso: flags & 0x10 != 0,
+        }
+    }
+}
+
+impl PowerPC64Flags {
+    pub const fn decode(flags: u32) -> PowerPC64Flags {
+        let abi = match (flags & 0x3) as u8 {
+            0 => PowerPC64Abi::Unspecified,
+            1 => PowerPC64Abi::ElfV1,
+            2 => PowerPC64Abi::ElfV2,
+            abi => PowerPC64Abi::Unknown(abi),
+        };
+        PowerPC64Flags { abi }
+    }
+}
+
+/// Writes the parts like GNU readelf does, e.g. `Version5 EABI, hard-float ABI`
+fn write_parts(f: &mut Formatter<'_>, parts: &[String]) -> std::fmt::Result {
+    write!(f, "{}", parts.join(", "))
+}
+
+impl Display for ArchFlags {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ArchFlags::Arm(flags) => flags.fmt(f),
+            ArchFlags::Mips(flags) => flags.fmt(f),
+            ArchFlags::RiscV(flags) => flags.fmt(f),
+            ArchFlags::PowerPC64(flags) => flags.fmt(f),
+            ArchFlags::Other(_) => Ok(()),
+        }
+    }
+}
+
+impl Display for ArmFlags {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut parts = Vec::new();
+        match self.eabi_version {
+            0 => parts.push("GNU EABI".to_string()),
+            version => parts.push(format!("Version{} EABI", version)),
+        }
+        if self.be8 {
+            parts.push("BE8".to_string());
+        }
+        match self.float_abi {
+            ArmFloatAbi::Unspecified => {}
+            ArmFloatAbi::Soft => parts.push("soft-float ABI".to_string()),
+            ArmFloatAbi::Hard => parts.push("hard-float ABI".to_string()),
+        }
+        write_parts(f, &parts)
+    }
+}
+
+impl Display for MipsFlags {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut parts = Vec::new();
+        for (set, name) in [
+            (self.noreorder, "noreorder"),
+            (self.pic, "pic"),
+            (self.cpic, "cpic"),
+            (self.nan2008, "nan2008"),
+            (self.fp64, "fp64"),
+        ]
+        .iter()
+        {
+            if *set {
+                parts.push(name.to_string());
+            }
+        }
+        match self.abi {
+            MipsAbi::Unspecified => {}
+            MipsAbi::O32 => parts.push("o32".to_string()),
+            MipsAbi::O64 => parts.push("o64".to_string()),
+            MipsAbi::N32 => parts.push("abi2".to_string()),
+            MipsAbi::Eabi32 => parts.push("eabi32".to_string()),
+            MipsAbi::Eabi64 => parts.push("eabi64".to_string()),
+            MipsAbi::Unknown(abi) => parts.push(format!("unknown ABI {:#x}", abi)),
+        }
+        parts.push(match self.isa {
+            MipsIsa::Mips1 => "mips1".to_string(),
+            MipsIsa::Mips2 => "mips2".to_string(),
+            MipsIsa::Mips3 => "mips3".to_string(),
+            MipsIsa::Mips4 => "mips4".to_string(),
+            MipsIsa::Mips5 => "mips5".to_string(),
+            MipsIsa::Mips32 => "mips32".to_string(),
+            MipsIsa::Mips64 => "mips64".to_string(),
+            MipsIsa::Mips32R2 => "mips32r2".to_string(),
+            MipsIsa::Mips64R2 => "mips64r2".to_string(),
+            MipsIsa::Mips32R6 => "mips32r6".to_string(),
+            MipsIsa::Mips64R6 => "mips64r6".to_string(),
+            MipsIsa::Unknown(isa) => format!("unknown ISA {:#x}", isa),
+        });
+        write_parts(f, &parts)
+    }
+}
+
+impl Display for RiscVFlags {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut parts = Vec::new();
+        if self.rvc {
+            parts.push("RVC".to_string());
+        }
+        if self.rve {
+            parts.push("RVE".to_string());
+        }
+        parts.push(
+            match self.float_abi {
+                RiscVFloatAbi::Soft => "soft-float ABI",
+                RiscVFloatAbi::Single => "single-float ABI",
+                RiscVFloatAbi::Double => "double-float ABI",
+                RiscVFloatAbi::Quad => "quad-float ABI",
+            }
+            .to_string(),
+        );
+        if self.tso {
+            parts.push("TSO".to_string());
+        }
+        write_parts(f, &parts)
+    }
+}
+
+impl Display for PowerPC64Flags {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.abi {
+            PowerPC64Abi::Unspecified => Ok(()),
+            PowerPC64Abi::ElfV1 => write!(f, "abiv1"),
+            PowerPC64Abi::ElfV2 => write!(f, "abiv2"),
+            PowerPC64Abi::Unknown(abi) => write!(f, "abiv{}", abi),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_arm_flags() {
+        let flags = ArchFlags::decode(Arch::Arm, 0x05000400);
+        assert_eq!(
+            flags,
+            ArchFlags::Arm(ArmFlags {
+                eabi_version: 5,
+                float_abi: ArmFloatAbi::Hard,
+                be8: false,
+            })
+        );
+        assert_eq!(flags.to_string(), "Version5 EABI, hard-float ABI");
+    }
+
+    #[test]
+    fn test_mips_flags() {
+        let flags = ArchFlags::decode(Arch::Mips, 0x70001007);
+        assert_eq!(
+            flags,
+            ArchFlags::Mips(MipsFlags {
+                isa: MipsIsa::Mips32R2,
+                abi: MipsAbi::O32,
+                noreorder: true,
+                pic: true,
+                cpic: true,
+                nan2008: false,
+                fp64: false,
+            })
+        );
+        assert_eq!(flags.to_string(), "noreorder, pic, cpic, o32, mips32r2");
+        assert_eq!(MipsFlags::decode(0x80000027).abi, MipsAbi::N32);
+    }
+
+    #[test]
+    fn test_riscv_and_ppc64_flags() {
+        let test_data = [
+            (Arch::RiscV, 0x5, "RVC, double-float ABI"),
+            (Arch::RiscV, 0x8, "RVE, soft-float ABI"),
+            (Arch::PowerPC64, 0x2, "abiv2"),
+            (Arch::PowerPC64, 0x1, "abiv1"),
+            (Arch::X86_64, 0x0, ""),
+        ];
+        for (arch, flags, expected) in test_data.iter() {
+            assert_eq!(ArchFlags::decode(*arch, *flags).to_string(), *expected);
+        }
+    }
+}
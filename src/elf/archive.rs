@@ -0,0 +1,538 @@
+use std::convert::TryFrom;
+
+use super::*;
+
+/// The magic bytes at the start of a static archive (`.a` file)
+pub const ARCHIVE_MAGIC: &[u8] = b"!<arch>\n";
+
+const MEMBER_HEADER_SIZE: usize = 60;
+const MEMBER_HEADER_END: &[u8] = b"`\n";
+const BSD_NAME_PREFIX: &str = "#1/";
+
+/// A static archive in the common `ar` format, as produced by GNU and BSD `ar`.
+///
+/// Like [`ElfFile`] the archive borrows the complete file contents. The symbol index and the
+/// long name table are located up front, the members are parsed on demand.
+#[derive(Debug, Clone)]
+pub struct Archive<'data> {
+    data: &'data [u8],
+    symbols: Vec<ArchiveSymbol<'data>>,
+    names: &'data [u8],
+}
+
+/// A member of an [`Archive`].
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct ArchiveMember<'data> {
+    name: &'data str,
+    offset: usize,
+    date: u64,
+    uid: u32,
+    gid: u32,
+    mode: u32,
+    data: &'data [u8],
+}
+
+/// An entry of the symbol index of an [`Archive`], mapping a symbol to the member defining it.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct ArchiveSymbol<'data> {
+    pub name: &'data str,
+    /// The offset of the header of the defining member in the archive
+    pub member_offset: u64,
+}
+
+/// Iterates over the regular members of an [`Archive`], skipping the symbol index and the long
+/// name table.
+#[derive(Debug, Clone)]
+pub struct ArchiveMembers<'data> {
+    data: &'data [u8],
+    names: &'data [u8],
+    offset: usize,
+}
+
+/// The raw fields of a member header, before the name is resolved.
+struct RawMember<'data> {
+    name: &'data str,
+    date: u64,
+    uid: u32,
+    gid: u32,
+    mode: u32,
+    data: &'data [u8],
+    next: usize,
+}
+
+impl<'data> Archive<'data> {
+    /// Whether `data` starts with the archive magic.
+    pub fn is_archive(data: &[u8]) -> bool {
+        data.starts_with(ARCHIVE_MAGIC)
+    }
+
+    pub fn parse(data: &'data [u8]) -> Result<Archive<'data>> {
+        if !Archive::is_archive(data) {
+            return Err(ParseError::NoArchive);
+        }
+        let mut archive = Archive {
+            data,
+            symbols: Vec::new(),
+            names: &[],
+        };
+        // the symbol index and the long name table precede the regular members
+        let mut offset = ARCHIVE_MAGIC.len();
+        while offset < data.len() {
+            let member = RawMember::parse(data, offset)?;
+            match member.name {
+                "/" => archive.symbols = parse_gnu_symbols(member.data, 4)?,
+                "/SYM64/" => archive.symbols = parse_gnu_symbols(member.data, 8)?,
+                "//" => archive.names = member.data,
+                _ => match bsd_member_name(&member, offset)? {
+                    (name, data) if is_bsd_symbol_table(name) => {
+                        let width = if name.starts_with("__.SYMDEF_64") {
+                            8
+                        } else {
+                            4
+                        };
+                        archive.symbols = parse_bsd_symbols(data, width)?;
+                    }
+                    _ => break,
+                },
+            }
+            offset = member.next;
+        }
+        Ok(archive)
+    }
+
+    pub fn data(&self) -> &'data [u8] {
+        self.data
+    }
+
+    /// The entries of the symbol index, empty if the archive has none
+    pub fn symbols(&self) -> &[ArchiveSymbol<'data>] {
+        self.symbols.as_slice()
+    }
+
+    pub fn members(&self) -> ArchiveMembers<'data> {
+        ArchiveMembers {
+            data: self.data,
+            names: self.names,
+            offset: ARCHIVE_MAGIC.len(),
+        }
+    }
+
+    /// Returns the member whose header starts at `offset`, as referenced by the symbol index.
+    pub fn member_at(&self, offset: u64) -> Result<ArchiveMember<'data>> {
+        let offset = usize::try_from(offset)
+            .map_err(|_| ParseError::InvalidArchiveMemberHeader(usize::MAX))?;
+        if offset < ARCHIVE_MAGIC.len() {
+            return Err(ParseError::InvalidArchiveMemberHeader(offset));
+        }
+        ArchiveMember::parse(self.data, self.names, offset).map(|(member, _)| member)
+    }
+
+    /// Returns the member defining the symbol `name` according to the symbol index.
+    pub fn member_defining(&self, name: &str) -> Result<Option<ArchiveMember<'data>>> {
+        match self.symbols.iter().find(|symbol| symbol.name == name) {
+            None => Ok(None),
+            Some(symbol) => self.member_at(symbol.member_offset).map(Some),
+        }
+    }
+
+    /// Parses the metadata of every member. Members that are not ELF files yield an error, so
+    /// the caller decides whether to skip them.
+    pub fn metadata(
+        &self,
+    ) -> impl Iterator<Item = std::result::Result<(&'data str, Metadata), MetadataParseError>> + 'data
+    {
+        self.members().map(|member| {
+            let member = member.map_err(MetadataParseError::InvalidELF)?;
+            Ok((member.name(), Metadata::parse_bytes(member.data())?))
+        })
+    }
+}
+
+impl<'data> ArchiveMember<'data> {
+    fn parse(
+        data: &'data [u8],
+        names: &'data [u8],
+        offset: usize,
+    ) -> Result<(ArchiveMember<'data>, usize)> {
+        let raw = RawMember::parse(data, offset)?;
+        let (name, member_data) = if is_special(raw.name) {
+            (raw.name, raw.data)
+        } else if let Some(index) = raw.name.strip_prefix('/') {
+            // GNU long names are offsets into the `//` member, terminated by "/\n"
+            let index = index
+                .parse::<usize>()
+                .map_err(|_| ParseError::InvalidArchiveMemberName(offset))?;
+            let name = names
+                .get(index..)
+                .and_then(|names| names.split(|byte| *byte == b'\n').next())
+                .ok_or(ParseError::InvalidArchiveMemberName(offset))?;
+            let name = std::str::from_utf8(name).map_err(ParseError::InvalidString)?;
+            (name.strip_suffix('/').unwrap_or(name), raw.data)
+        } else {
+            bsd_member_name(&raw, offset)?
+        };
+        let member = ArchiveMember {
+            name,
+            offset,
+            date: raw.date,
+            uid: raw.uid,
+            gid: raw.gid,
+            mode: raw.mode,
+            data: member_data,
+        };
+        Ok((member, raw.next))
+    }
+
+    pub const fn name(&self) -> &'data str {
+        self.name
+    }
+
+    /// The offset of the member header in the archive
+    pub const fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// The modification time in seconds since the epoch
+    pub const fn date(&self) -> u64 {
+        self.date
+    }
+
+    pub const fn uid(&self) -> u32 {
+        self.uid
+    }
+
+    pub const fn gid(&self) -> u32 {
+        self.gid
+    }
+
+    /// The file mode, including the permission bits
+    pub const fn mode(&self) -> u32 {
+        self.mode
+    }
+
+    /// The contents of the member, without the header and the BSD long name
+    pub const fn data(&self) -> &'data [u8] {
+        self.data
+    }
+}
+
+impl<'data> ArchiveMembers<'data> {
+    fn parse_next(&mut self) -> Result<Option<ArchiveMember<'data>>> {
+        while self.offset < self.data.len() {
+            let (member, next) = ArchiveMember::parse(self.data, self.names, self.offset)?;
+            self.offset = next;
+            if !is_special(member.name) && !is_bsd_symbol_table(member.name) {
+                return Ok(Some(member));
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl<'data> Iterator for ArchiveMembers<'data> {
+    type Item = Result<ArchiveMember<'data>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let member = self.parse_next();
+        if member.is_err() {
+            // the following members can't be located without a valid member size
+            self.offset = self.data.len();
+        }
+        member.transpose()
+    }
+}
+
+impl<'data> RawMember<'data> {
+    fn parse(data: &'data [u8], offset: usize) -> Result<RawMember<'data>> {
+        let header = data
+            .get(offset..)
+            .and_then(|rest| rest.get(..MEMBER_HEADER_SIZE))
+            .ok_or_else(|| {
+                ParseError::InsufficientArchiveMemberLength(data.len().saturating_sub(offset))
+            })?;
+        if &header[58..60] != MEMBER_HEADER_END {
+            return Err(ParseError::InvalidArchiveMemberHeader(offset));
+        }
+        let field = |range: std::ops::Range<usize>| {
+            std::str::from_utf8(&header[range])
+                .map(str::trim_end)
+                .map_err(|_| ParseError::InvalidArchiveMemberHeader(offset))
+        };
+        let number = |range: std::ops::Range<usize>, radix: u32| {
+            let value = field(range)?;
+            if value.is_empty() {
+                return Ok(0);
+            }
+            u64::from_str_radix(value, radix)
+                .map_err(|_| ParseError::InvalidArchiveMemberHeader(offset))
+        };
+        let name = field(0..16)?;
+        // GNU terminates short names with a slash, the special and long names start with one
+        let name = if name.starts_with('/') {
+            name
+        } else {
+            name.strip_suffix('/').unwrap_or(name)
+        };
+        let size = usize::try_from(number(48..58, 10)?)
+            .map_err(|_| ParseError::InvalidArchiveMemberHeader(offset))?;
+        let start = offset + MEMBER_HEADER_SIZE;
+        let member_data = start
+            .checked_add(size)
+            .and_then(|end| data.get(start..end))
+            .ok_or(ParseError::InsufficientArchiveMemberLength(
+                data.len() - start,
+            ))?;
+        Ok(RawMember {
+            name,
+            date: number(16..28, 10)?,
+            uid: number(28..34, 10)? as u32,
+            gid: number(34..40, 10)? as u32,
+            mode: number(40..48, 8)? as u32,
+            data: member_data,
+            // the members are aligned to two bytes
+            next: (start + size).saturating_add(size & 1),
+        })
+    }
+}
+
+/// Resolves a BSD `#1/<length>` name, which is stored in front of the member contents.
+fn bsd_member_name<'data>(
+    member: &RawMember<'data>,
+    offset: usize,
+) -> Result<(&'data str, &'data [u8])> {
+    let length = match member.name.strip_prefix(BSD_NAME_PREFIX) {
+        None => return Ok((member.name, member.data)),
+        Some(length) => length
+            .parse::<usize>()
+            .map_err(|_| ParseError::InvalidArchiveMemberName(offset))?,
+    };
+    if length > member.data.len() {
+        return Err(ParseError::InvalidArchiveMemberName(offset));
+    }
+    let (name, data) = member.data.split_at(length);
+    let name_length = name.iter().position(|byte| *byte == 0).unwrap_or(length);
+    let name = std::str::from_utf8(&name[..name_length]).map_err(ParseError::InvalidString)?;
+    Ok((name, data))
+}
+
+/// Whether the member is the GNU symbol index or long name table.
+fn is_special(name: &str) -> bool {
+    matches!(name, "/" | "//" | "/SYM64/")
+}
+
+fn is_bsd_symbol_table(name: &str) -> bool {
+    name.starts_with("__.SYMDEF")
+}
+
+fn read_word(bytes: &[u8], width: usize, endianness: Endianness) -> Result<u64> {
+    match width {
+        4 => u32::from_bytes(bytes, endianness).map(u64::from),
+        _ => u64::from_bytes(bytes, endianness),
+    }
+}
+
+/// Parses the GNU symbol index: a big endian count, the member offsets and the NUL-terminated
+/// symbol names in the same order.
+fn parse_gnu_symbols(bytes: &[u8], width: usize) -> Result<Vec<ArchiveSymbol<'_>>> {
+    let check = |expected: usize| {
+        if bytes.len() < expected {
+            Err(ParseError::InsufficientArchiveSymbolTableLength(
+                bytes.len(),
+            ))
+        } else {
+            Ok(())
+        }
+    };
+    check(width)?;
+    let count = read_word(bytes, width, Endianness::Big)?;
+    let count = usize::try_from(count)
+        .map_err(|_| ParseError::InsufficientArchiveSymbolTableLength(bytes.len()))?;
+    let names_start = count
+        .checked_add(1)
+        .and_then(|entries| entries.checked_mul(width))
+        .ok_or(ParseError::InsufficientArchiveSymbolTableLength(
+            bytes.len(),
+        ))?;
+    check(names_start)?;
+    let mut names = &bytes[names_start..];
+    (0..count)
+        .map(|i| {
+            let member_offset = read_word(&bytes[(i + 1) * width..], width, Endianness::Big)?;
+            let name = parse_string(names, 0)?;
+            names = &names[name.len() + 1..];
+            Ok(ArchiveSymbol {
+                name,
+                member_offset,
+            })
+        })
+        .collect()
+}
+
+/// Parses the BSD `__.SYMDEF` symbol index: the size of the `ranlib` entries, the entries of
+/// name index and member offset, the size of the string table and the string table.
+fn parse_bsd_symbols(bytes: &[u8], width: usize) -> Result<Vec<ArchiveSymbol<'_>>> {
+    let endianness = Endianness::Little;
+    let insufficient = || ParseError::InsufficientArchiveSymbolTableLength(bytes.len());
+    if bytes.len() < width {
+        return Err(insufficient());
+    }
+    let entries_size =
+        usize::try_from(read_word(bytes, width, endianness)?).map_err(|_| insufficient())?;
+    let entries = bytes
+        .get(width..)
+        .and_then(|rest| rest.get(..entries_size))
+        .ok_or_else(insufficient)?;
+    let rest = &bytes[width + entries_size..];
+    if rest.len() < width {
+        return Err(insufficient());
+    }
+    let strings_size =
+        usize::try_from(read_word(rest, width, endianness)?).map_err(|_| insufficient())?;
+    let strings = rest
+        .get(width..)
+        .and_then(|rest| rest.get(..strings_size))
+        .ok_or_else(insufficient)?;
+    entries
+        .chunks_exact(2 * width)
+        .map(|entry| {
+            let name = read_word(entry, width, endianness)?;
+            let name = usize::try_from(name).map_err(|_| insufficient())?;
+            Ok(ArchiveSymbol {
+                name: parse_string(strings, name)?,
+                member_offset: read_word(&entry[width..], width, endianness)?,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::elf::testing::{Fixture, FixtureSection};
+
+    fn member(archive: &mut Vec<u8>, name: &str, data: &[u8]) -> usize {
+        let offset = archive.len();
+        let header = format!(
+            "{:<16}{:<12}{:<6}{:<6}{:<8}{:<10}`\n",
+            name,
+            1_650_000_000,
+            0,
+            0,
+            644,
+            data.len()
+        );
+        archive.extend_from_slice(header.as_bytes());
+        archive.extend_from_slice(data);
+        if data.len() % 2 == 1 {
+            archive.push(b'\n');
+        }
+        offset
+    }
+
+    fn object() -> Vec<u8> {
+        let mut fixture = Fixture::new(WordWidth::Width64, Endianness::Little);
+        fixture.file_type = 1;
+        fixture.section(FixtureSection::new(".text", 1, vec![0xC3]).flags(0x6));
+        fixture.build()
+    }
+
+    #[test]
+    fn test_gnu_archive() {
+        let long_name = "a_rather_long_object_name.o";
+        let mut archive = ARCHIVE_MAGIC.to_vec();
+        // the symbol index is patched once the member offsets are known
+        let mut index = Vec::new();
+        index.extend_from_slice(&2_u32.to_be_bytes());
+        index.extend_from_slice(&[0; 8]);
+        index.extend_from_slice(b"first\0second\0");
+        let index_offset = member(&mut archive, "/", &index);
+        member(&mut archive, "//", format!("{}/\n", long_name).as_bytes());
+        let short = member(&mut archive, "short.o/", &object());
+        let long = member(&mut archive, "/0", &object());
+        let symbols = index_offset + MEMBER_HEADER_SIZE + 4;
+        archive[symbols..symbols + 4].copy_from_slice(&(short as u32).to_be_bytes());
+        archive[symbols + 4..symbols + 8].copy_from_slice(&(long as u32).to_be_bytes());
+
+        let archive = Archive::parse(&archive).expect("valid archive");
+        assert_eq!(
+            archive.symbols(),
+            &[
+                ArchiveSymbol {
+                    name: "first",
+                    member_offset: short as u64
+                },
+                ArchiveSymbol {
+                    name: "second",
+                    member_offset: long as u64
+                }
+            ]
+        );
+        let members: Vec<_> = archive.members().map(Result::unwrap).collect();
+        assert_eq!(members.len(), 2);
+        assert_eq!(members[0].name(), "short.o");
+        assert_eq!(members[0].mode(), 0o644);
+        assert_eq!(members[1].name(), long_name);
+        assert_eq!(
+            archive
+                .member_defining("second")
+                .map(|m| m.map(|m| m.name())),
+            Ok(Some(long_name))
+        );
+
+        let metadata: Vec<_> = archive.metadata().map(|m| m.unwrap()).collect();
+        assert_eq!(metadata[1].0, long_name);
+        assert_eq!(metadata[1].1.header().file_type(), FileType::Relocatable);
+        assert_eq!(metadata[1].1.section_headers()[1].name(), ".text");
+    }
+
+    #[test]
+    fn test_bsd_archive() {
+        let mut archive = ARCHIVE_MAGIC.to_vec();
+        let mut symdef = Vec::new();
+        symdef.extend_from_slice(&8_u32.to_le_bytes());
+        symdef.extend_from_slice(&0_u32.to_le_bytes());
+        symdef.extend_from_slice(&0_u32.to_le_bytes());
+        symdef.extend_from_slice(&8_u32.to_le_bytes());
+        symdef.extend_from_slice(b"_main\0\0\0");
+        let mut name = b"__.SYMDEF SORTED".to_vec();
+        name.extend_from_slice(&symdef);
+        let symdef_offset = member(&mut archive, "#1/16", &name);
+        let mut data = b"main_object.o\0\0\0".to_vec();
+        data.extend_from_slice(&object());
+        let object_offset = member(&mut archive, "#1/16", &data);
+        let patch = symdef_offset + MEMBER_HEADER_SIZE + 16 + 8;
+        archive[patch..patch + 4].copy_from_slice(&(object_offset as u32).to_le_bytes());
+
+        let archive = Archive::parse(&archive).expect("valid archive");
+        assert_eq!(archive.symbols().len(), 1);
+        assert_eq!(archive.symbols()[0].name, "_main");
+        let members: Vec<_> = archive.members().map(Result::unwrap).collect();
+        assert_eq!(members.len(), 1);
+        assert_eq!(members[0].name(), "main_object.o");
+        assert_eq!(members[0].data(), object().as_slice());
+        assert_eq!(
+            archive.member_defining("_main"),
+            Ok(Some(members[0].clone()))
+        );
+    }
+
+    #[test]
+    fn test_invalid_archive() {
+        assert_eq!(
+            Archive::parse(b"\x7FELF").err(),
+            Some(ParseError::NoArchive)
+        );
+        let mut archive = ARCHIVE_MAGIC.to_vec();
+        member(&mut archive, "valid.o/", b"data");
+        member(&mut archive, "broken.o/", b"data");
+        archive.truncate(archive.len() - 2);
+        let archive = Archive::parse(&archive).expect("valid first member");
+        let members: Vec<_> = archive.members().collect();
+        assert_eq!(members.len(), 2);
+        assert_eq!(members[0].as_ref().map(|m| m.data()), Ok(&b"data"[..]));
+        assert_eq!(
+            members[1],
+            Err(ParseError::InsufficientArchiveMemberLength(2))
+        );
+    }
+}
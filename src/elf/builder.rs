@@ -0,0 +1,1087 @@
+use std::convert::TryFrom;
+
+use super::*;
+
+const DEFAULT_PAGE_SIZE: u64 = 0x1000;
+
+/// The index of a section added to an [`ElfBuilder`], which is also its index in the section
+/// header table of the built file.
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Hash)]
+pub struct SectionId(usize);
+
+/// A symbol added to an [`ElfBuilder`].
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Hash)]
+pub struct SymbolId(usize);
+
+/// A section to be added to an [`ElfBuilder`].
+#[derive(Debug, Clone)]
+pub struct BuilderSection {
+    name: String,
+    typ: SectionHeaderType,
+    flags: SectionHeaderFlags,
+    data: Vec<u8>,
+    size: u64,
+    address: Option<u64>,
+    align: u64,
+    entry_size: u64,
+    link: Option<SectionId>,
+    info: u32,
+}
+
+/// A symbol to be added to an [`ElfBuilder`].
+#[derive(Debug, Clone)]
+pub struct BuilderSymbol {
+    name: String,
+    value: u64,
+    size: u64,
+    typ: SymbolType,
+    binding: SymbolBinding,
+    visibility: SymbolVisibility,
+    section_index: SymbolSectionIndex,
+}
+
+#[derive(Debug, Clone)]
+struct BuilderRelocation {
+    target: SectionId,
+    offset: u64,
+    typ: u32,
+    symbol: Option<SymbolId>,
+    addend: i64,
+}
+
+#[derive(Debug, Clone)]
+struct BuilderSegment {
+    typ: ProgramHeaderSegmentType,
+    flags: u32,
+    sections: Vec<SectionId>,
+}
+
+/// The position of a section in the built file
+#[derive(Debug, Copy, Clone)]
+struct Placement {
+    offset: u64,
+    address: u64,
+}
+
+/// Generates new ELF files.
+///
+/// Sections are laid out in the order they are added, each at a file offset aligned to the
+/// section alignment. In executables and shared objects, allocated sections get consecutive
+/// virtual addresses after the base address unless they have a fixed one, and the first section
+/// of every `Load` segment starts on a new page. Symbols and relocations are written to the
+/// generated `.rel(a)<target>`, `.symtab` and `.strtab` sections, followed by `.shstrtab`.
+///
+/// ```
+/// use elfreader::elf::*;
+///
+/// let mut builder = ElfBuilder::new(
+///     WordWidth::Width64,
+///     Endianness::Little,
+///     FileType::Executable,
+///     Arch::X86_64,
+/// );
+/// // mov eax, 60; xor edi, edi; syscall
+/// let code = vec![0xB8, 0x3C, 0, 0, 0, 0x31, 0xFF, 0x0F, 0x05];
+/// let text = builder.add_section(
+///     BuilderSection::new(".text", SectionHeaderType::ProgramBits, code)
+///         .flags(SectionHeaderFlags::ALLOC | SectionHeaderFlags::EXEC)
+///         .align(16),
+/// );
+/// builder.add_segment(ProgramHeaderSegmentType::Load, 0x5, &[text]);
+/// builder.set_entry_point_to(text, 0);
+///
+/// let metadata = Metadata::parse_bytes(&builder.build().unwrap()).unwrap();
+/// assert_eq!(metadata.section_headers()[1].name(), ".text");
+/// ```
+#[derive(Debug, Clone)]
+pub struct ElfBuilder {
+    word_width: WordWidth,
+    endianness: Endianness,
+    file_type: FileType,
+    arch: Arch,
+    abi: Abi,
+    flags: u32,
+    entry_point: u64,
+    entry_section: Option<(SectionId, u64)>,
+    base_address: u64,
+    page_size: u64,
+    with_addends: bool,
+    sections: Vec<BuilderSection>,
+    symbols: Vec<BuilderSymbol>,
+    relocations: Vec<BuilderRelocation>,
+    segments: Vec<BuilderSegment>,
+}
+
+impl SectionId {
+    /// The index in the section header table
+    pub const fn index(&self) -> usize {
+        self.0
+    }
+}
+
+impl BuilderSection {
+    pub fn new(name: &str, typ: SectionHeaderType, data: Vec<u8>) -> BuilderSection {
+        BuilderSection {
+            name: name.to_string(),
+            typ,
+            flags: SectionHeaderFlags::empty(),
+            size: data.len() as u64,
+            data,
+            address: None,
+            align: 1,
+            entry_size: 0,
+            link: None,
+            info: 0,
+        }
+    }
+
+    /// A `NoData` section like `.bss`, which occupies memory but no space in the file
+    pub fn no_data(name: &str, size: u64) -> BuilderSection {
+        BuilderSection {
+            size,
+            ..BuilderSection::new(name, SectionHeaderType::NoData, Vec::new())
+        }
+    }
+
+    pub fn flags(mut self, flags: SectionHeaderFlags) -> BuilderSection {
+        self.flags = flags;
+        self
+    }
+
+    /// Places the section at a fixed virtual address instead of the next free one
+    pub fn address(mut self, address: u64) -> BuilderSection {
+        self.address = Some(address);
+        self
+    }
+
+    pub fn align(mut self, align: u64) -> BuilderSection {
+        self.align = align;
+        self
+    }
+
+    pub fn entry_size(mut self, entry_size: u64) -> BuilderSection {
+        self.entry_size = entry_size;
+        self
+    }
+
+    pub fn link(mut self, link: SectionId) -> BuilderSection {
+        self.link = Some(link);
+        self
+    }
+
+    pub fn info(mut self, info: u32) -> BuilderSection {
+        self.info = info;
+        self
+    }
+
+    fn has_data(&self) -> bool {
+        self.typ != SectionHeaderType::NoData
+    }
+}
+
+impl BuilderSymbol {
+    /// An undefined symbol with the default visibility
+    pub fn new(name: &str, typ: SymbolType, binding: SymbolBinding) -> BuilderSymbol {
+        BuilderSymbol {
+            name: name.to_string(),
+            value: 0,
+            size: 0,
+            typ,
+            binding,
+            visibility: SymbolVisibility::Default,
+            section_index: SymbolSectionIndex::Undefined,
+        }
+    }
+
+    /// Defines the symbol at `offset` in `section`. In executables and shared objects the
+    /// value becomes the virtual address of the symbol.
+    pub fn defined_in(mut self, section: SectionId, offset: u64, size: u64) -> BuilderSymbol {
+        self.section_index = SymbolSectionIndex::Index(section.0 as u32);
+        self.value = offset;
+        self.size = size;
+        self
+    }
+
+    /// Uses a reserved section index like `Absolute` or `Common` with a raw value
+    pub fn with_section_index(mut self, index: SymbolSectionIndex, value: u64) -> BuilderSymbol {
+        self.section_index = index;
+        self.value = value;
+        self
+    }
+
+    pub fn visibility(mut self, visibility: SymbolVisibility) -> BuilderSymbol {
+        self.visibility = visibility;
+        self
+    }
+}
+
+impl ElfBuilder {
+    pub fn new(
+        word_width: WordWidth,
+        endianness: Endianness,
+        file_type: FileType,
+        arch: Arch,
+    ) -> ElfBuilder {
+        let base_address = match (file_type, word_width) {
+            (FileType::Executable, WordWidth::Width64) => 0x400000,
+            (FileType::Executable, WordWidth::Width32) => 0x8048000,
+            _ => 0,
+        };
+        // the 32-bit ABIs of these architectures use relocations without addends
+        let with_addends = !matches!(
+            (word_width, arch),
+            (WordWidth::Width32, Arch::X86 | Arch::Arm | Arch::Mips)
+        );
+        ElfBuilder {
+            word_width,
+            endianness,
+            file_type,
+            arch,
+            abi: Abi::SysV,
+            flags: 0,
+            entry_point: 0,
+            entry_section: None,
+            base_address,
+            page_size: DEFAULT_PAGE_SIZE,
+            with_addends,
+            sections: Vec::new(),
+            symbols: Vec::new(),
+            relocations: Vec::new(),
+            segments: Vec::new(),
+        }
+    }
+
+    pub fn set_abi(&mut self, abi: Abi) -> &mut ElfBuilder {
+        self.abi = abi;
+        self
+    }
+
+    /// Sets the architecture specific flags of the header
+    pub fn set_flags(&mut self, flags: u32) -> &mut ElfBuilder {
+        self.flags = flags;
+        self
+    }
+
+    pub fn set_entry_point(&mut self, entry_point: u64) -> &mut ElfBuilder {
+        self.entry_point = entry_point;
+        self.entry_section = None;
+        self
+    }
+
+    /// Sets the entry point to `offset` in `section`, resolved once the section is placed
+    pub fn set_entry_point_to(&mut self, section: SectionId, offset: u64) -> &mut ElfBuilder {
+        self.entry_section = Some((section, offset));
+        self
+    }
+
+    /// The virtual address the file is mapped to, which defaults to 0 for shared objects
+    pub fn set_base_address(&mut self, base_address: u64) -> &mut ElfBuilder {
+        self.base_address = base_address;
+        self
+    }
+
+    /// The alignment of `Load` segments, a power of two
+    pub fn set_page_size(&mut self, page_size: u64) -> &mut ElfBuilder {
+        self.page_size = page_size;
+        self
+    }
+
+    /// Whether relocations are written to `RelocationWithAddends` sections
+    pub fn set_with_addends(&mut self, with_addends: bool) -> &mut ElfBuilder {
+        self.with_addends = with_addends;
+        self
+    }
+
+    pub fn add_section(&mut self, section: BuilderSection) -> SectionId {
+        self.sections.push(section);
+        SectionId(self.sections.len())
+    }
+
+    pub fn add_symbol(&mut self, symbol: BuilderSymbol) -> SymbolId {
+        self.symbols.push(symbol);
+        SymbolId(self.symbols.len() - 1)
+    }
+
+    /// Adds a relocation of the `target` section. The offset is written as is and the addend is
+    /// dropped when writing relocations without addends.
+    pub fn add_relocation(
+        &mut self,
+        target: SectionId,
+        offset: u64,
+        typ: u32,
+        symbol: Option<SymbolId>,
+        addend: i64,
+    ) {
+        self.relocations.push(BuilderRelocation {
+            target,
+            offset,
+            typ,
+            symbol,
+            addend,
+        });
+    }
+
+    /// Adds a segment covering the consecutive `sections`. Segments without sections, like
+    /// `PT_GNU_STACK`, are empty.
+    pub fn add_segment(
+        &mut self,
+        typ: ProgramHeaderSegmentType,
+        flags: u32,
+        sections: &[SectionId],
+    ) {
+        self.segments.push(BuilderSegment {
+            typ,
+            flags,
+            sections: sections.to_vec(),
+        });
+    }
+
+    /// Lays out and serializes the file
+    pub fn build(&self) -> Result<Vec<u8>> {
+        if !self.page_size.is_power_of_two() {
+            return Err(ParseError::InvalidAlignment(self.page_size));
+        }
+        for (i, segment) in self.segments.iter().enumerate() {
+            let consecutive = segment
+                .sections
+                .windows(2)
+                .all(|pair| pair[1].0 == pair[0].0 + 1);
+            let known = segment
+                .sections
+                .iter()
+                .all(|id| id.0 >= 1 && id.0 <= self.sections.len());
+            if !consecutive || !known {
+                return Err(ParseError::InvalidSegmentLayout(i));
+            }
+        }
+
+        // the sizes of the generated sections don't depend on the layout, so the symbol values
+        // can be filled in by generating them a second time
+        let mut sections = self.sections.clone();
+        self.add_generated_sections(&mut sections, &[])?;
+        let (placements, sheader_start) = self.layout(&sections)?;
+        sections.truncate(self.sections.len());
+        self.add_generated_sections(&mut sections, &placements)?;
+
+        let mut bytes = Vec::new();
+        for (section, placement) in sections.iter().zip(placements.iter()) {
+            if section.has_data() {
+                bytes.resize(placement.offset as usize, 0);
+                bytes.extend_from_slice(&section.data);
+            }
+        }
+        bytes.resize(sheader_start as usize, 0);
+        self.write_section_headers(&mut bytes, &sections, &placements);
+
+        let header = self.header(sections.len() + 1, sheader_start, &placements)?;
+        let header = header.to_bytes(self.word_width, self.endianness);
+        let mut offset = header.len();
+        bytes[..offset].copy_from_slice(&header);
+        for (i, segment) in self.segments.iter().enumerate() {
+            let pheader = self.program_header(i, segment, &sections, &placements)?;
+            let pheader = pheader.to_bytes(self.word_width, self.endianness);
+            bytes[offset..offset + pheader.len()].copy_from_slice(&pheader);
+            offset += pheader.len();
+        }
+        Ok(bytes)
+    }
+
+    const fn header_size(&self) -> u64 {
+        match self.word_width {
+            WordWidth::Width32 => 52,
+            WordWidth::Width64 => 64,
+        }
+    }
+
+    const fn pheader_size(&self) -> u64 {
+        match self.word_width {
+            WordWidth::Width32 => 32,
+            WordWidth::Width64 => 56,
+        }
+    }
+
+    const fn sheader_size(&self) -> u64 {
+        match self.word_width {
+            WordWidth::Width32 => 40,
+            WordWidth::Width64 => 64,
+        }
+    }
+
+    fn word(&self, value: u64) -> Word {
+        Word::new(self.word_width, value)
+    }
+
+    fn has_address(&self, section: &BuilderSection) -> bool {
+        self.file_type != FileType::Relocatable && section.flags.contains(SectionHeaderFlags::ALLOC)
+    }
+
+    fn starts_load_segment(&self, id: SectionId) -> bool {
+        self.segments.iter().any(|segment| {
+            segment.typ == ProgramHeaderSegmentType::Load && segment.sections.first() == Some(&id)
+        })
+    }
+
+    /// Assigns the offset and address of every section and returns them together with the
+    /// offset of the section header table.
+    fn layout(&self, sections: &[BuilderSection]) -> Result<(Vec<Placement>, u64)> {
+        let mut placements = Vec::with_capacity(sections.len());
+        let mut offset = self.header_size() + self.segments.len() as u64 * self.pheader_size();
+        let mut address = self.base_address + offset;
+        for (i, section) in sections.iter().enumerate() {
+            let align = section.align.max(1);
+            if !align.is_power_of_two() {
+                return Err(ParseError::InvalidAlignment(align));
+            }
+            let placement = if self.has_address(section) {
+                if self.starts_load_segment(SectionId(i + 1)) {
+                    offset = align_up(offset, self.page_size);
+                    address = align_up(address, self.page_size);
+                }
+                address = section.address.unwrap_or_else(|| align_up(address, align));
+                if section.has_data() {
+                    offset = align_up(offset, align);
+                    // loading requires the offset to be congruent to the address modulo the page size
+                    offset += address.wrapping_sub(offset) & (self.page_size - 1);
+                }
+                let placement = Placement { offset, address };
+                address += section.size;
+                placement
+            } else {
+                if section.has_data() {
+                    offset = align_up(offset, align);
+                }
+                Placement {
+                    offset,
+                    address: section.address.unwrap_or(0),
+                }
+            };
+            if section.has_data() {
+                offset += section.data.len() as u64;
+            }
+            placements.push(placement);
+        }
+        let sheader_start = align_up(offset, self.word_width.size() as u64);
+        Ok((placements, sheader_start))
+    }
+
+    /// Appends the relocation sections, the symbol table with its string table and extended
+    /// section indices, and the section name table.
+    fn add_generated_sections(
+        &self,
+        sections: &mut Vec<BuilderSection>,
+        placements: &[Placement],
+    ) -> Result<()> {
+        if !self.symbols.is_empty() || !self.relocations.is_empty() {
+            let mut targets: Vec<SectionId> = Vec::new();
+            for relocation in self.relocations.iter() {
+                if !targets.contains(&relocation.target) {
+                    targets.push(relocation.target);
+                }
+            }
+            let symbol_table = SectionId(sections.len() + targets.len() + 1);
+            for target in targets {
+                sections.push(self.relocation_section(target, symbol_table)?);
+            }
+
+            let (locals, globals): (Vec<_>, Vec<_>) = self
+                .symbols
+                .iter()
+                .partition(|symbol| symbol.binding == SymbolBinding::Local);
+            let (strings, names) =
+                string_table(locals.iter().chain(globals.iter()).map(|s| s.name.as_str()));
+            let mut data = vec![0; Symbol::entry_size(self.word_width)];
+            for (symbol, name) in locals.iter().chain(globals.iter()).zip(names) {
+                self.write_symbol(&mut data, symbol, name, placements)?;
+            }
+            sections.push(
+                BuilderSection::new(".symtab", SectionHeaderType::SymbolTable, data)
+                    .align(self.word_width.size() as u64)
+                    .entry_size(Symbol::entry_size(self.word_width) as u64)
+                    .link(SectionId(symbol_table.0 + 1))
+                    .info(locals.len() as u32 + 1),
+            );
+            sections.push(BuilderSection::new(
+                ".strtab",
+                SectionHeaderType::StringTable,
+                strings,
+            ));
+            // section indices from 0xFF00 on don't fit into the symbols
+            let extended =
+                locals
+                    .iter()
+                    .chain(globals.iter())
+                    .map(|symbol| match symbol.section_index {
+                        SymbolSectionIndex::Index(index) if index >= 0xFF00 => index,
+                        _ => 0,
+                    });
+            if extended.clone().any(|index| index != 0) {
+                let mut data = vec![0; 4];
+                for index in extended {
+                    index.write_bytes(&mut data, self.endianness);
+                }
+                sections.push(
+                    BuilderSection::new(".symtab_shndx", SectionHeaderType::SectionIndices, data)
+                        .align(4)
+                        .entry_size(4)
+                        .link(symbol_table),
+                );
+            }
+        }
+        let (names, _) = section_name_table(sections);
+        sections.push(BuilderSection::new(
+            ".shstrtab",
+            SectionHeaderType::StringTable,
+            names,
+        ));
+        Ok(())
+    }
+
+    fn relocation_section(
+        &self,
+        target: SectionId,
+        symbol_table: SectionId,
+    ) -> Result<BuilderSection> {
+        let name = &self
+            .sections
+            .get(target.0.wrapping_sub(1))
+            .ok_or(ParseError::InvalidSectionLink(target.0 as u32))?
+            .name;
+        let (typ, name) = if self.with_addends {
+            (
+                SectionHeaderType::RelocationWithAddends,
+                format!(".rela{}", name),
+            )
+        } else {
+            (SectionHeaderType::Relocation, format!(".rel{}", name))
+        };
+        let symbol_indices = self.symbol_indices();
+        let mut data = Vec::new();
+        for relocation in self.relocations.iter().filter(|r| r.target == target) {
+            let symbol = match relocation.symbol {
+                None => 0,
+                Some(symbol) => *symbol_indices
+                    .get(symbol.0)
+                    .ok_or(ParseError::InvalidSymbolIndex(symbol.0 as u32))?,
+            };
+            self.write_relocation(&mut data, relocation, symbol);
+        }
+        Ok(BuilderSection::new(&name, typ, data)
+            .flags(SectionHeaderFlags::INFO_LINK)
+            .align(self.word_width.size() as u64)
+            .entry_size(Relocation::entry_size(self.with_addends, self.word_width) as u64)
+            .link(symbol_table)
+            .info(target.0 as u32))
+    }
+
+    /// The index of every added symbol in the symbol table, where the local symbols come first
+    fn symbol_indices(&self) -> Vec<u64> {
+        let local_count = self
+            .symbols
+            .iter()
+            .filter(|symbol| symbol.binding == SymbolBinding::Local)
+            .count() as u64;
+        let mut locals = 1;
+        let mut globals = 1 + local_count;
+        self.symbols
+            .iter()
+            .map(|symbol| {
+                let counter = if symbol.binding == SymbolBinding::Local {
+                    &mut locals
+                } else {
+                    &mut globals
+                };
+                *counter += 1;
+                *counter - 1
+            })
+            .collect()
+    }
+
+    fn write_symbol(
+        &self,
+        data: &mut Vec<u8>,
+        symbol: &BuilderSymbol,
+        name: u32,
+        placements: &[Placement],
+    ) -> Result<()> {
+        let mut value = symbol.value;
+        if let SymbolSectionIndex::Index(index) = symbol.section_index {
+            let section = self
+                .sections
+                .get((index as usize).wrapping_sub(1))
+                .ok_or(ParseError::InvalidSectionLink(index))?;
+            if let (true, Some(placement)) = (
+                self.has_address(section),
+                placements.get(index as usize - 1),
+            ) {
+                value += placement.address;
+            }
+        }
+        let info = (symbol.binding.to_u8() << 4) | (symbol.typ.to_u8() & 0xF);
+        let other = symbol.visibility.to_u8();
+        let section_index = symbol.section_index.to_u16();
+        let endianness = self.endianness;
+        name.write_bytes(data, endianness);
+        match self.word_width {
+            WordWidth::Width32 => {
+                (value as u32).write_bytes(data, endianness);
+                (symbol.size as u32).write_bytes(data, endianness);
+                data.push(info);
+                data.push(other);
+                section_index.write_bytes(data, endianness);
+            }
+            WordWidth::Width64 => {
+                data.push(info);
+                data.push(other);
+                section_index.write_bytes(data, endianness);
+                value.write_bytes(data, endianness);
+                symbol.size.write_bytes(data, endianness);
+            }
+        }
+        Ok(())
+    }
+
+    fn write_relocation(&self, data: &mut Vec<u8>, relocation: &BuilderRelocation, symbol: u64) {
+        let typ = u64::from(relocation.typ);
+        let info = match (self.word_width, self.arch, self.endianness) {
+            (WordWidth::Width32, _, _) => (symbol << 8) | (typ & 0xFF),
+            // little endian MIPS64 stores the symbol index first and the type in the top byte
+            (WordWidth::Width64, Arch::Mips, Endianness::Little) => symbol | (typ << 56),
+            (WordWidth::Width64, _, _) => (symbol << 32) | typ,
+        };
+        let (word_width, endianness) = (self.word_width, self.endianness);
+        self.word(relocation.offset)
+            .write_bytes(data, word_width, endianness);
+        self.word(info).write_bytes(data, word_width, endianness);
+        if self.with_addends {
+            self.word(relocation.addend as u64)
+                .write_bytes(data, word_width, endianness);
+        }
+    }
+
+    fn write_section_headers(
+        &self,
+        bytes: &mut Vec<u8>,
+        sections: &[BuilderSection],
+        placements: &[Placement],
+    ) {
+        let count = sections.len() as u64 + 1;
+        let names_index = sections.len() as u32;
+        let pheader_count = self.segments.len() as u32;
+        // counts that don't fit into the header are stored in the first section header
+        let first = UnnamedSectionHeader::new(
+            0,
+            SectionHeaderType::Null,
+            SectionHeaderFlags::empty(),
+            self.word(0),
+            self.word(0),
+            self.word(if count >= 0xFF00 { count } else { 0 }),
+            if names_index >= 0xFF00 {
+                names_index
+            } else {
+                0
+            },
+            if pheader_count >= u32::from(Header::PN_XNUM) {
+                pheader_count
+            } else {
+                0
+            },
+            self.word(0),
+            self.word(0),
+        );
+        bytes.extend_from_slice(&first.to_bytes(self.word_width, self.endianness));
+        let (_, names) = section_name_table(sections);
+        for ((section, placement), name) in sections.iter().zip(placements.iter()).zip(names) {
+            let sheader = UnnamedSectionHeader::new(
+                name,
+                section.typ,
+                section.flags,
+                self.word(placement.address),
+                self.word(placement.offset),
+                self.word(section.size),
+                section.link.map_or(0, |link| link.0 as u32),
+                section.info,
+                self.word(section.align),
+                self.word(section.entry_size),
+            );
+            bytes.extend_from_slice(&sheader.to_bytes(self.word_width, self.endianness));
+        }
+    }
+
+    fn header(
+        &self,
+        section_count: usize,
+        sheader_start: u64,
+        placements: &[Placement],
+    ) -> Result<Header> {
+        let entry_point = match self.entry_section {
+            Some((section, offset)) => {
+                let placement = placements
+                    .get(section.0.wrapping_sub(1))
+                    .ok_or(ParseError::InvalidSectionLink(section.0 as u32))?;
+                placement.address + offset
+            }
+            None => self.entry_point,
+        };
+        let pheader_count = self.segments.len();
+        let (pheader_start, pheader_size) = if pheader_count == 0 {
+            (0, 0)
+        } else {
+            (self.header_size(), self.pheader_size() as u16)
+        };
+        let names_index = section_count - 1;
+        Ok(Header::minimal(self.word_width, self.endianness)
+            .with_header_version(1)
+            .with_abi(self.abi)
+            .with_file_type(self.file_type)
+            .with_arch(self.arch)
+            .with_version(1)
+            .with_entry_point(self.word(entry_point))
+            .with_program_header_start(self.word(pheader_start))
+            .with_section_header_start(self.word(sheader_start))
+            .with_flags(self.flags)
+            .with_program_header_entry_size(pheader_size)
+            .with_program_header_entry_count(pheader_count.min(Header::PN_XNUM as usize) as u16)
+            .with_section_header_entry_size(self.sheader_size() as u16)
+            .with_section_header_entry_count(if section_count >= 0xFF00 {
+                0
+            } else {
+                section_count as u16
+            })
+            .with_section_names_index(if names_index >= 0xFF00 {
+                Header::SHN_XINDEX
+            } else {
+                names_index as u16
+            }))
+    }
+
+    fn program_header(
+        &self,
+        index: usize,
+        segment: &BuilderSegment,
+        sections: &[BuilderSection],
+        placements: &[Placement],
+    ) -> Result<ProgramHeader> {
+        let start = match segment.sections.first() {
+            Some(first) => placements[first.0 - 1],
+            None => {
+                let zero = self.word(0);
+                return Ok(ProgramHeader::new(
+                    segment.typ,
+                    zero,
+                    zero,
+                    zero,
+                    zero,
+                    zero,
+                    segment.flags,
+                    self.word(1),
+                ));
+            }
+        };
+        let mut file_end = start.offset;
+        let mut memory_end = start.address;
+        let mut align = 1;
+        for id in segment.sections.iter() {
+            let (section, placement) = (&sections[id.0 - 1], placements[id.0 - 1]);
+            if section.has_data() {
+                // the segment maps the contents to their addresses as a single block
+                if placement.offset - start.offset != placement.address.wrapping_sub(start.address)
+                {
+                    return Err(ParseError::InvalidSegmentLayout(index));
+                }
+                file_end = file_end.max(placement.offset + section.size);
+            }
+            memory_end = memory_end.max(placement.address + section.size);
+            align = align.max(section.align);
+        }
+        if segment.typ == ProgramHeaderSegmentType::Load {
+            align = self.page_size;
+        }
+        Ok(ProgramHeader::new(
+            segment.typ,
+            self.word(start.offset),
+            self.word(start.address),
+            self.word(start.address),
+            self.word(file_end - start.offset),
+            self.word(memory_end - start.address),
+            segment.flags,
+            self.word(align),
+        ))
+    }
+}
+
+/// Builds the section name table, where `.shstrtab` is the name of the last section
+fn section_name_table(sections: &[BuilderSection]) -> (Vec<u8>, Vec<u32>) {
+    let names = sections.iter().map(|section| section.name.as_str());
+    match sections.last() {
+        Some(last) if last.name == ".shstrtab" => string_table(names),
+        _ => string_table(names.chain(std::iter::once(".shstrtab"))),
+    }
+}
+
+/// Builds a string table starting with the empty string and returns the index of every string
+fn string_table<'a>(strings: impl Iterator<Item = &'a str>) -> (Vec<u8>, Vec<u32>) {
+    let mut table = vec![0];
+    let indices = strings
+        .map(|string| {
+            let index = u32::try_from(table.len()).unwrap_or(u32::MAX);
+            table.extend_from_slice(string.as_bytes());
+            table.push(0);
+            index
+        })
+        .collect();
+    (table, indices)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_build_executable() {
+        let mut builder = ElfBuilder::new(
+            WordWidth::Width64,
+            Endianness::Little,
+            FileType::Executable,
+            Arch::X86_64,
+        );
+        let text = builder.add_section(
+            BuilderSection::new(".text", SectionHeaderType::ProgramBits, vec![0xC3; 0x21])
+                .flags(SectionHeaderFlags::ALLOC | SectionHeaderFlags::EXEC)
+                .align(16),
+        );
+        let data = builder.add_section(
+            BuilderSection::new(".data", SectionHeaderType::ProgramBits, vec![1, 2, 3])
+                .flags(SectionHeaderFlags::ALLOC | SectionHeaderFlags::WRITE)
+                .align(8),
+        );
+        let bss = builder.add_section(
+            BuilderSection::no_data(".bss", 0x100)
+                .flags(SectionHeaderFlags::ALLOC | SectionHeaderFlags::WRITE)
+                .align(32),
+        );
+        builder.add_section(BuilderSection::new(
+            ".comment",
+            SectionHeaderType::ProgramBits,
+            b"test\0".to_vec(),
+        ));
+        builder.add_segment(ProgramHeaderSegmentType::Load, 0x5, &[text]);
+        builder.add_segment(ProgramHeaderSegmentType::Load, 0x6, &[data, bss]);
+        builder.add_segment(ProgramHeaderSegmentType::GNU_STACK, 0x6, &[]);
+        builder.add_symbol(
+            BuilderSymbol::new("counter", SymbolType::Object, SymbolBinding::Local)
+                .defined_in(bss, 0x10, 8),
+        );
+        builder.add_symbol(
+            BuilderSymbol::new("_start", SymbolType::Function, SymbolBinding::Global)
+                .defined_in(text, 0, 0x21),
+        );
+        builder.set_entry_point_to(text, 0);
+        let bytes = builder.build().unwrap();
+
+        let metadata = Metadata::parse_bytes(&bytes).unwrap();
+        let names: Vec<_> = metadata
+            .section_headers()
+            .iter()
+            .map(|sheader| sheader.name())
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "",
+                ".text",
+                ".data",
+                ".bss",
+                ".comment",
+                ".symtab",
+                ".strtab",
+                ".shstrtab"
+            ]
+        );
+        let sheaders = metadata.section_headers();
+        assert_eq!(sheaders[1].address(), Word::Word64(0x401000));
+        assert_eq!(sheaders[1].offset(), Word::Word64(0x1000));
+        assert_eq!(sheaders[2].address(), Word::Word64(0x402000));
+        assert_eq!(sheaders[2].offset(), Word::Word64(0x2000));
+        assert_eq!(sheaders[3].address(), Word::Word64(0x402020));
+        assert_eq!(sheaders[4].address(), Word::Word64(0));
+        assert_eq!(metadata.header().entry_point(), Word::Word64(0x401000));
+
+        let pheaders = metadata.program_headers();
+        assert_eq!(pheaders.len(), 3);
+        assert_eq!(pheaders[1].offset(), Word::Word64(0x2000));
+        assert_eq!(pheaders[1].vaddress(), Word::Word64(0x402000));
+        assert_eq!(pheaders[1].filesize(), Word::Word64(3));
+        assert_eq!(pheaders[1].memsize(), Word::Word64(0x120));
+        assert_eq!(pheaders[1].alignment(), Word::Word64(0x1000));
+        assert_eq!(pheaders[2].memsize(), Word::Word64(0));
+
+        let elf = ElfFile::parse(&bytes).unwrap();
+        let symbols: Vec<_> = elf
+            .symbol_table()
+            .unwrap()
+            .unwrap()
+            .map(|symbol| symbol.unwrap())
+            .collect();
+        assert_eq!(symbols.len(), 3);
+        assert_eq!(symbols[1].name(), "counter");
+        assert_eq!(symbols[1].value(), Word::Word64(0x402030));
+        assert_eq!(symbols[2].name(), "_start");
+        assert_eq!(symbols[2].binding(), SymbolBinding::Global);
+        assert_eq!(symbols[2].section_index(), SymbolSectionIndex::Index(1));
+        assert_eq!(metadata.section_headers()[5].info(), 2);
+    }
+
+    #[test]
+    fn test_build_relocatable() {
+        let mut builder = ElfBuilder::new(
+            WordWidth::Width32,
+            Endianness::Big,
+            FileType::Relocatable,
+            Arch::PowerPC,
+        );
+        let text = builder.add_section(
+            BuilderSection::new(".text", SectionHeaderType::ProgramBits, vec![0; 16])
+                .flags(SectionHeaderFlags::ALLOC | SectionHeaderFlags::EXEC)
+                .align(4),
+        );
+        let main = builder.add_symbol(
+            BuilderSymbol::new("main", SymbolType::Function, SymbolBinding::Global)
+                .defined_in(text, 4, 12),
+        );
+        let puts = builder.add_symbol(BuilderSymbol::new(
+            "puts",
+            SymbolType::NoType,
+            SymbolBinding::Global,
+        ));
+        let local = builder.add_symbol(
+            BuilderSymbol::new("helper", SymbolType::Function, SymbolBinding::Local)
+                .defined_in(text, 0, 4),
+        );
+        builder.add_relocation(text, 8, 10, Some(puts), -4);
+        builder.add_relocation(text, 12, 10, Some(local), 0);
+        builder.add_relocation(text, 0, 1, Some(main), 16);
+        let bytes = builder.build().unwrap();
+
+        let metadata = Metadata::parse_bytes(&bytes).unwrap();
+        assert!(metadata.program_headers().is_empty());
+        let sheaders = metadata.section_headers();
+        assert_eq!(sheaders[1].address(), Word::Word32(0));
+        assert_eq!(sheaders[2].name(), ".rela.text");
+        assert_eq!(sheaders[2].link(), 3);
+        assert_eq!(sheaders[2].info(), 1);
+
+        let elf = ElfFile::parse(&bytes).unwrap();
+        let tables = elf.relocation_tables().unwrap();
+        assert_eq!(tables.len(), 1);
+        let table = &tables[0];
+        let relocations: Vec<_> = table.entries().map(|r| r.unwrap()).collect();
+        let names: Vec<_> = relocations
+            .iter()
+            .map(|r| table.symbol(r).unwrap().unwrap().name().to_string())
+            .collect();
+        assert_eq!(names, ["puts", "helper", "main"]);
+        assert_eq!(relocations[0].offset(), Word::Word32(8));
+        assert_eq!(relocations[0].typ(), 10);
+        assert_eq!(relocations[0].addend(), Some(-4));
+        assert_eq!(relocations[1].symbol_index(), 1);
+        let main = table.symbols().unwrap().get(2).unwrap().unwrap();
+        assert_eq!(main.value(), Word::Word32(4));
+    }
+
+    #[test]
+    fn test_invalid_segment() {
+        let mut builder = ElfBuilder::new(
+            WordWidth::Width64,
+            Endianness::Little,
+            FileType::Executable,
+            Arch::AArch64,
+        );
+        let first = builder.add_section(BuilderSection::new(
+            ".first",
+            SectionHeaderType::ProgramBits,
+            vec![0],
+        ));
+        builder.add_section(BuilderSection::new(
+            ".second",
+            SectionHeaderType::ProgramBits,
+            vec![0],
+        ));
+        let third = builder.add_section(BuilderSection::new(
+            ".third",
+            SectionHeaderType::ProgramBits,
+            vec![0],
+        ));
+        builder.add_segment(ProgramHeaderSegmentType::Load, 0x4, &[first, third]);
+        assert_eq!(builder.build(), Err(ParseError::InvalidSegmentLayout(0)));
+    }
+
+    #[test]
+    fn test_build_extended_section_indices() {
+        let mut builder = ElfBuilder::new(
+            WordWidth::Width64,
+            Endianness::Little,
+            FileType::Relocatable,
+            Arch::X86_64,
+        );
+        for _ in 0..0xFF00 {
+            builder.add_section(BuilderSection::new(
+                ".filler",
+                SectionHeaderType::ProgramBits,
+                Vec::new(),
+            ));
+        }
+        let text = builder.add_section(
+            BuilderSection::new(".text.f", SectionHeaderType::ProgramBits, vec![0xC3]).flags(
+                SectionHeaderFlags::ALLOC | SectionHeaderFlags::EXEC | SectionHeaderFlags::GROUP,
+            ),
+        );
+        builder.add_symbol(
+            BuilderSymbol::new("", SymbolType::Section, SymbolBinding::Local)
+                .defined_in(text, 0, 0),
+        );
+        builder.add_symbol(
+            BuilderSymbol::new("f", SymbolType::Function, SymbolBinding::Global)
+                .defined_in(text, 0, 1),
+        );
+        // a COMDAT group signed by the section symbol of its only member
+        let mut group = Vec::new();
+        for word in [1_u32, text.0 as u32].iter() {
+            group.extend_from_slice(&word.to_le_bytes());
+        }
+        let group = builder.add_section(
+            BuilderSection::new(".group", SectionHeaderType::Group, group)
+                .align(4)
+                .entry_size(4)
+                .link(SectionId(text.0 + 2))
+                .info(1),
+        );
+        let bytes = builder.build().expect("valid builder input");
+
+        let elf = ElfFile::parse(&bytes).expect("valid ELF");
+        let symbols: Vec<_> = elf
+            .symbol_table()
+            .unwrap()
+            .unwrap()
+            .map(|symbol| symbol.unwrap())
+            .collect();
+        assert_eq!(
+            symbols[1].section_index(),
+            SymbolSectionIndex::Index(0xFF01)
+        );
+        assert_eq!(symbols[2].name(), "f");
+        assert_eq!(
+            symbols[2].section_index(),
+            SymbolSectionIndex::Index(0xFF01)
+        );
+        let groups = elf.section_groups().expect("valid groups");
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].signature(), ".text.f");
+        let sheader = elf.section_header(group.0).unwrap().unwrap();
+        assert_eq!(
+            elf.section_group(&sheader)
+                .expect("valid group")
+                .signature(),
+            ".text.f"
+        );
+    }
+}
@@ -1,70 +1,128 @@
-use super::Endianness;
+use std::convert::TryInto;
 
-pub trait FromBytesEndianned {
-    fn from_bytes(bytes: &[u8], endianness: Endianness) -> Self;
+use super::{Endianness, ParseError, Result};
+
+/// Reads an integer from the start of a byte slice in the given byte order
+pub trait FromBytesEndianned: Sized {
+    /// Fails with [`ParseError::InsufficientPartLength`] if `bytes` is shorter than the integer
+    fn from_bytes(bytes: &[u8], endianness: Endianness) -> Result<Self>;
 }
 
 impl FromBytesEndianned for u16 {
-    fn from_bytes(bytes: &[u8], endianness: Endianness) -> Self {
-        assert!(bytes.len() >= 2);
-        let bytes = get_u16_bytes(bytes);
-        match endianness {
+    fn from_bytes(bytes: &[u8], endianness: Endianness) -> Result<Self> {
+        let bytes = get_u16_bytes(bytes)?;
+        Ok(match endianness {
             Endianness::Little => u16::from_le_bytes(bytes),
             Endianness::Big => u16::from_be_bytes(bytes),
-        }
+        })
     }
 }
 
 impl FromBytesEndianned for u32 {
-    fn from_bytes(bytes: &[u8], endianness: Endianness) -> Self {
-        assert!(bytes.len() >= 4);
-        let bytes = get_u32_bytes(bytes);
-        match endianness {
+    fn from_bytes(bytes: &[u8], endianness: Endianness) -> Result<Self> {
+        let bytes = get_u32_bytes(bytes)?;
+        Ok(match endianness {
             Endianness::Little => u32::from_le_bytes(bytes),
             Endianness::Big => u32::from_be_bytes(bytes),
-        }
+        })
     }
 }
 
 impl FromBytesEndianned for u64 {
-    fn from_bytes(bytes: &[u8], endianness: Endianness) -> Self {
-        assert!(bytes.len() >= 8);
-        let bytes = get_u64_bytes(bytes);
-        match endianness {
+    fn from_bytes(bytes: &[u8], endianness: Endianness) -> Result<Self> {
+        let bytes = get_u64_bytes(bytes)?;
+        Ok(match endianness {
             Endianness::Little => u64::from_le_bytes(bytes),
             Endianness::Big => u64::from_be_bytes(bytes),
+        })
+    }
+}
+
+/// The inverse of [`FromBytesEndianned`], appends the bytes of a value in the given byte order.
+pub trait ToBytesEndianned {
+    fn write_bytes(self, bytes: &mut Vec<u8>, endianness: Endianness);
+}
+
+impl ToBytesEndianned for u16 {
+    fn write_bytes(self, bytes: &mut Vec<u8>, endianness: Endianness) {
+        match endianness {
+            Endianness::Little => bytes.extend_from_slice(&self.to_le_bytes()),
+            Endianness::Big => bytes.extend_from_slice(&self.to_be_bytes()),
         }
     }
 }
 
-pub(super) fn get_u16_bytes(bytes: &[u8]) -> [u8; 2] {
-    [bytes[0], bytes[1]]
+impl ToBytesEndianned for u32 {
+    fn write_bytes(self, bytes: &mut Vec<u8>, endianness: Endianness) {
+        match endianness {
+            Endianness::Little => bytes.extend_from_slice(&self.to_le_bytes()),
+            Endianness::Big => bytes.extend_from_slice(&self.to_be_bytes()),
+        }
+    }
 }
 
-pub(super) fn get_u32_bytes(bytes: &[u8]) -> [u8; 4] {
-    [bytes[0], bytes[1], bytes[2], bytes[3]]
+impl ToBytesEndianned for u64 {
+    fn write_bytes(self, bytes: &mut Vec<u8>, endianness: Endianness) {
+        match endianness {
+            Endianness::Little => bytes.extend_from_slice(&self.to_le_bytes()),
+            Endianness::Big => bytes.extend_from_slice(&self.to_be_bytes()),
+        }
+    }
+}
+
+fn get_bytes<const N: usize>(bytes: &[u8]) -> Result<[u8; N]> {
+    bytes
+        .get(..N)
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or(ParseError::InsufficientPartLength(bytes.len()))
+}
+
+fn get_u16_bytes(bytes: &[u8]) -> Result<[u8; 2]> {
+    get_bytes(bytes)
+}
+
+fn get_u32_bytes(bytes: &[u8]) -> Result<[u8; 4]> {
+    get_bytes(bytes)
 }
 
-pub(super) fn get_u64_bytes(bytes: &[u8]) -> [u8; 8] {
-    [
-        bytes[0], bytes[1], bytes[2], bytes[3], bytes[4], bytes[5], bytes[6], bytes[7],
-    ]
+fn get_u64_bytes(bytes: &[u8]) -> Result<[u8; 8]> {
+    get_bytes(bytes)
 }
 
 #[cfg(test)]
 mod test {
-    use super::{Endianness, FromBytesEndianned};
+    use super::{Endianness, FromBytesEndianned, ParseError, ToBytesEndianned};
+
+    #[test]
+    fn test_write_bytes_round_trip() {
+        for endianness in [Endianness::Little, Endianness::Big].iter() {
+            let mut bytes = Vec::new();
+            0xE3FF_u16.write_bytes(&mut bytes, *endianness);
+            0x1F72D4E3_u32.write_bytes(&mut bytes, *endianness);
+            0x09D24C000000E3FF_u64.write_bytes(&mut bytes, *endianness);
+            assert_eq!(bytes.len(), 14);
+            assert_eq!(u16::from_bytes(&bytes, *endianness), Ok(0xE3FF));
+            assert_eq!(u32::from_bytes(&bytes[2..], *endianness), Ok(0x1F72D4E3));
+            assert_eq!(
+                u64::from_bytes(&bytes[6..], *endianness),
+                Ok(0x09D24C000000E3FF)
+            );
+        }
+        let mut bytes = Vec::new();
+        0x0110_u16.write_bytes(&mut bytes, Endianness::Big);
+        assert_eq!(bytes, [0x01, 0x10]);
+    }
 
     #[test]
     fn test_from_bytes_u16_little_zero() {
         let test_data = [0x00, 0x00];
-        assert_eq!(u16::from_bytes(&test_data, Endianness::Little), 0);
+        assert_eq!(u16::from_bytes(&test_data, Endianness::Little), Ok(0));
     }
 
     #[test]
     fn test_from_bytes_u16_big_zero() {
         let test_data = [0x00, 0x00];
-        assert_eq!(u16::from_bytes(&test_data, Endianness::Big), 0);
+        assert_eq!(u16::from_bytes(&test_data, Endianness::Big), Ok(0));
     }
 
     #[test]
@@ -75,7 +133,7 @@ mod test {
             ([0xFF, 0x00], 0x00FF),
         ];
         for (data, expected) in test_data.iter() {
-            assert_eq!(u16::from_bytes(data, Endianness::Little), *expected);
+            assert_eq!(u16::from_bytes(data, Endianness::Little), Ok(*expected));
         }
     }
 
@@ -87,7 +145,7 @@ mod test {
             ([0x00, 0xFF], 0x00FF),
         ];
         for (data, expected) in test_data.iter() {
-            assert_eq!(u16::from_bytes(data, Endianness::Big), *expected);
+            assert_eq!(u16::from_bytes(data, Endianness::Big), Ok(*expected));
         }
     }
 
@@ -99,7 +157,7 @@ mod test {
             ([0xFF, 0xE3], 0xE3FF),
         ];
         for (data, expected) in test_data.iter() {
-            assert_eq!(u16::from_bytes(data, Endianness::Little), *expected);
+            assert_eq!(u16::from_bytes(data, Endianness::Little), Ok(*expected));
         }
     }
 
@@ -111,20 +169,20 @@ mod test {
             ([0xE3, 0xFF], 0xE3FF),
         ];
         for (data, expected) in test_data.iter() {
-            assert_eq!(u16::from_bytes(data, Endianness::Big), *expected);
+            assert_eq!(u16::from_bytes(data, Endianness::Big), Ok(*expected));
         }
     }
 
     #[test]
     fn test_from_bytes_u32_little_zero() {
         let test_data = [0x00, 0x00, 0x00, 0x00];
-        assert_eq!(u32::from_bytes(&test_data, Endianness::Little), 0);
+        assert_eq!(u32::from_bytes(&test_data, Endianness::Little), Ok(0));
     }
 
     #[test]
     fn test_from_bytes_u32_big_zero() {
         let test_data = [0x00, 0x00, 0x00, 0x00];
-        assert_eq!(u32::from_bytes(&test_data, Endianness::Big), 0);
+        assert_eq!(u32::from_bytes(&test_data, Endianness::Big), Ok(0));
     }
 
     #[test]
@@ -135,7 +193,7 @@ mod test {
             ([0xFF, 0x00, 0x00, 0x00], 0x000000FF),
         ];
         for (data, expected) in test_data.iter() {
-            assert_eq!(u32::from_bytes(data, Endianness::Little), *expected);
+            assert_eq!(u32::from_bytes(data, Endianness::Little), Ok(*expected));
         }
     }
 
@@ -147,7 +205,7 @@ mod test {
             ([0x00, 0x00, 0x00, 0xFF], 0x000000FF),
         ];
         for (data, expected) in test_data.iter() {
-            assert_eq!(u32::from_bytes(data, Endianness::Big), *expected);
+            assert_eq!(u32::from_bytes(data, Endianness::Big), Ok(*expected));
         }
     }
 
@@ -160,7 +218,7 @@ mod test {
             ([0xE3, 0xD4, 0x72, 0x1F], 0x1F72D4E3),
         ];
         for (data, expected) in test_data.iter() {
-            assert_eq!(u32::from_bytes(data, Endianness::Little), *expected);
+            assert_eq!(u32::from_bytes(data, Endianness::Little), Ok(*expected));
         }
     }
 
@@ -173,20 +231,20 @@ mod test {
             ([0x1F, 0x72, 0xD4, 0xE3], 0x1F72D4E3),
         ];
         for (data, expected) in test_data.iter() {
-            assert_eq!(u32::from_bytes(data, Endianness::Big), *expected);
+            assert_eq!(u32::from_bytes(data, Endianness::Big), Ok(*expected));
         }
     }
 
     #[test]
     fn test_from_bytes_u64_little_zero() {
         let test_data = [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
-        assert_eq!(u64::from_bytes(&test_data, Endianness::Little), 0);
+        assert_eq!(u64::from_bytes(&test_data, Endianness::Little), Ok(0));
     }
 
     #[test]
     fn test_from_bytes_u64_big_zero() {
         let test_data = [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
-        assert_eq!(u64::from_bytes(&test_data, Endianness::Big), 0);
+        assert_eq!(u64::from_bytes(&test_data, Endianness::Big), Ok(0));
     }
 
     #[test]
@@ -206,7 +264,7 @@ mod test {
             ),
         ];
         for (data, expected) in test_data.iter() {
-            assert_eq!(u64::from_bytes(data, Endianness::Little), *expected);
+            assert_eq!(u64::from_bytes(data, Endianness::Little), Ok(*expected));
         }
     }
 
@@ -227,7 +285,7 @@ mod test {
             ),
         ];
         for (data, expected) in test_data.iter() {
-            assert_eq!(u64::from_bytes(data, Endianness::Big), *expected);
+            assert_eq!(u64::from_bytes(data, Endianness::Big), Ok(*expected));
         }
     }
 
@@ -252,7 +310,7 @@ mod test {
             ),
         ];
         for (data, expected) in test_data.iter() {
-            assert_eq!(u64::from_bytes(data, Endianness::Little), *expected);
+            assert_eq!(u64::from_bytes(data, Endianness::Little), Ok(*expected));
         }
     }
 
@@ -277,7 +335,24 @@ mod test {
             ),
         ];
         for (data, expected) in test_data.iter() {
-            assert_eq!(u64::from_bytes(data, Endianness::Big), *expected);
+            assert_eq!(u64::from_bytes(data, Endianness::Big), Ok(*expected));
         }
     }
+
+    #[test]
+    fn test_from_bytes_too_short() {
+        let test_data = [0xFF, 0x3E, 0x00];
+        assert_eq!(
+            u16::from_bytes(&test_data[..1], Endianness::Little),
+            Err(ParseError::InsufficientPartLength(1))
+        );
+        assert_eq!(
+            u32::from_bytes(&test_data, Endianness::Big),
+            Err(ParseError::InsufficientPartLength(3))
+        );
+        assert_eq!(
+            u64::from_bytes(&[], Endianness::Little),
+            Err(ParseError::InsufficientPartLength(0))
+        );
+    }
 }
@@ -1,8 +1,10 @@
-use super::{FromBytesEndianned, SectionHeaderType};
+use super::{FromBytesEndianned, SectionHeaderType, ToBytesEndianned};
 
 use std::{
+    error::Error,
     ffi::IntoStringError,
-    fmt::{Binary, Debug, Formatter, LowerHex, UpperHex},
+    fmt::{Binary, Debug, Display, Formatter, LowerHex, UpperHex},
+    str::Utf8Error,
 };
 
 #[derive(Debug, Eq, PartialEq, Copy, Clone)]
@@ -13,6 +15,8 @@ pub enum FileType {
     Shared,
     Core,
     Specific(u16),
+    /// A value outside of the defined and the reserved ranges, only produced by lenient parsing
+    Unknown(u16),
 }
 
 #[derive(Debug, Eq, PartialEq, Copy, Clone)]
@@ -47,7 +51,7 @@ pub enum Abi {
     FenixOS,
     CloudABI,
     OpenVOS,
-    Unknown,
+    Unknown(u8),
 }
 
 #[derive(Debug, Eq, PartialEq, Copy, Clone)]
@@ -77,7 +81,7 @@ pub enum Arch {
     RiscV,
     Bpf,
     WDC65C816,
-    Unknown,
+    Unknown(u16),
 }
 
 #[derive(Eq, PartialEq, Copy, Clone, Ord, PartialOrd)]
@@ -104,10 +108,385 @@ pub enum ParseError {
     UnterminatedString,
     InvalidSectionName(IntoStringError),
     InvalidSectionNameTableType(SectionHeaderType),
+    InvalidDataRange(u64, u64),
+    InvalidString(Utf8Error),
+    InvalidSectionLink(u32),
+    InvalidStringTableType(SectionHeaderType),
+    InvalidSymbolTableType(SectionHeaderType),
+    InsufficientSymbolLength(usize),
+    InvalidSymbolBinding(u8),
+    InvalidSymbolType(u8),
+    InvalidDynamicTag(u64),
+    UnmappedAddress(u64),
+    InsufficientRelocationLength(usize),
+    InvalidRelocationTableType(SectionHeaderType),
+    InvalidSymbolIndex(u32),
+    InsufficientNoteLength(usize),
+    InvalidNoteSectionType(SectionHeaderType),
+    MissingExtendedNumbering,
+    InsufficientGroupLength(usize),
+    InvalidGroupSectionType(SectionHeaderType),
+    InsufficientVersionLength(usize),
+    InsufficientHashTableLength(usize),
+    InvalidHashTableType(SectionHeaderType),
+    InsufficientCompressionHeaderLength(usize),
+    InvalidCompressionType(u32),
+    InvalidCompressedData,
+    InvalidDecompressedSize(u64, u64),
+    NoArchive,
+    InsufficientArchiveMemberLength(usize),
+    InvalidArchiveMemberHeader(usize),
+    InvalidArchiveMemberName(usize),
+    InsufficientArchiveSymbolTableLength(usize),
+    InvalidSegmentLayout(usize),
+    NoDynamicSection,
+    NoInterpreter,
+    /// The error occurred while parsing the given location of the file
+    Located(ErrorLocation, Box<ParseError>),
+}
+
+/// The structure of an ELF file being parsed when an error occurred
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum Structure {
+    Header,
+    /// The program header with the given index
+    ProgramHeader(usize),
+    /// The section header with the given index
+    SectionHeader(usize),
+    /// The contents of the segment with the given program header index
+    Segment(usize),
+    /// The contents of the section with the given section header index
+    Section(usize),
+    ProgramHeaderTable,
+    SectionHeaderTable,
+}
+
+/// Where in the file a [`ParseError`] occurred
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct ErrorLocation {
+    offset: u64,
+    structure: Structure,
+    field: Option<&'static str>,
 }
 
 pub type Result<T> = std::result::Result<T, ParseError>;
 
+impl ParseError {
+    /// Where in the file the error occurred, if known
+    pub fn location(&self) -> Option<&ErrorLocation> {
+        match self {
+            ParseError::Located(location, _) => Some(location),
+            _ => None,
+        }
+    }
+
+    /// The error without its location
+    pub fn kind(&self) -> &ParseError {
+        match self {
+            ParseError::Located(_, error) => error.kind(),
+            error => error,
+        }
+    }
+
+    /// Adds the location to the error, unless it already has a more precise one
+    pub(crate) fn at(self, offset: u64, structure: Structure, field: Option<&'static str>) -> Self {
+        match self {
+            ParseError::Located(..) => self,
+            error => ParseError::Located(
+                ErrorLocation {
+                    offset,
+                    structure,
+                    field,
+                },
+                Box::new(error),
+            ),
+        }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        use ParseError::*;
+        match self {
+            InsuffcientHeaderLength(length) => {
+                write!(f, "invalid ELF header length of {} bytes", length)
+            }
+            NoELF(magic) => write!(f, "not an ELF file, the magic number is {:#010x}", magic),
+            InvalidWordWidth(class) => write!(f, "invalid word width (class) {}", class),
+            InvalidEndianness(data) => write!(f, "invalid endianness (data encoding) {}", data),
+            InvalidFileType(typ) => write!(f, "unknown file type {:#x}", typ),
+            InsufficientProgramHeaderLength(length) => {
+                write!(f, "program header too short, {} bytes", length)
+            }
+            InvalidProgramHeaderType(typ) => write!(f, "unknown program header type {:#x}", typ),
+            InvalidAlignment(align) => write!(f, "invalid alignment {:#x}", align),
+            InvalidVirtualAddress(address) => write!(
+                f,
+                "virtual address {:#x} does not match the file offset modulo the alignment",
+                address
+            ),
+            InsufficientPartLength(length) => write!(f, "field too short, {} bytes", length),
+            InsufficientSectionHeaderLength(length) => {
+                write!(f, "section header too short, {} bytes", length)
+            }
+            InvalidSectionHeaderType(typ) => write!(f, "unknown section header type {:#x}", typ),
+            InvalidSectionHeaderFlags(flags) => {
+                write!(f, "unknown section header flags {:#x}", flags)
+            }
+            UnterminatedString => write!(f, "string is not NUL-terminated"),
+            InvalidSectionName(_) => write!(f, "section name is not valid UTF-8"),
+            InvalidSectionNameTableType(typ) => {
+                write!(
+                    f,
+                    "section name table has type {:?} instead of StringTable",
+                    typ
+                )
+            }
+            InvalidDataRange(offset, size) => write!(
+                f,
+                "{:#x} bytes at offset {:#x} are outside of the file",
+                size, offset
+            ),
+            InvalidString(_) => write!(f, "string is not valid UTF-8"),
+            InvalidSectionLink(index) => write!(f, "invalid section index {}", index),
+            InvalidStringTableType(typ) => {
+                write!(f, "string table has type {:?} instead of StringTable", typ)
+            }
+            InvalidSymbolTableType(typ) => {
+                write!(f, "section of type {:?} is not a symbol table", typ)
+            }
+            InsufficientSymbolLength(length) => write!(f, "symbol too short, {} bytes", length),
+            InvalidSymbolBinding(binding) => write!(f, "unknown symbol binding {}", binding),
+            InvalidSymbolType(typ) => write!(f, "unknown symbol type {}", typ),
+            InvalidDynamicTag(tag) => write!(f, "unknown dynamic tag {:#x}", tag),
+            UnmappedAddress(address) => {
+                write!(f, "address {:#x} is not inside a Load segment", address)
+            }
+            InsufficientRelocationLength(length) => {
+                write!(f, "relocation too short, {} bytes", length)
+            }
+            InvalidRelocationTableType(typ) => {
+                write!(f, "section of type {:?} is not a relocation table", typ)
+            }
+            InvalidSymbolIndex(index) => write!(f, "invalid symbol index {}", index),
+            InsufficientNoteLength(length) => write!(f, "note too short, {} bytes", length),
+            InvalidNoteSectionType(typ) => {
+                write!(f, "section of type {:?} is not a note section", typ)
+            }
+            MissingExtendedNumbering => write!(
+                f,
+                "the first section header holding the extended numbering is missing"
+            ),
+            InsufficientGroupLength(length) => {
+                write!(f, "section group too short, {} bytes", length)
+            }
+            InvalidGroupSectionType(typ) => {
+                write!(f, "section of type {:?} is not a section group", typ)
+            }
+            InsufficientVersionLength(length) => {
+                write!(f, "version entry too short, {} bytes", length)
+            }
+            InsufficientHashTableLength(length) => {
+                write!(f, "hash table too short, {} bytes", length)
+            }
+            InvalidHashTableType(typ) => write!(f, "section of type {:?} is not a hash table", typ),
+            InsufficientCompressionHeaderLength(length) => {
+                write!(f, "compression header too short, {} bytes", length)
+            }
+            InvalidCompressionType(typ) => write!(f, "unknown compression type {:#x}", typ),
+            InvalidCompressedData => write!(f, "invalid compressed data"),
+            InvalidDecompressedSize(expected, actual) => write!(
+                f,
+                "decompressed to {} bytes instead of {}",
+                actual, expected
+            ),
+            NoArchive => write!(f, "not a static archive"),
+            InsufficientArchiveMemberLength(length) => {
+                write!(f, "archive member too short, {} bytes", length)
+            }
+            InvalidArchiveMemberHeader(offset) => {
+                write!(f, "invalid archive member header at offset {:#x}", offset)
+            }
+            InvalidArchiveMemberName(offset) => {
+                write!(f, "invalid archive member name at offset {:#x}", offset)
+            }
+            InsufficientArchiveSymbolTableLength(length) => {
+                write!(f, "archive symbol table too short, {} bytes", length)
+            }
+            InvalidSegmentLayout(index) => {
+                write!(f, "invalid segment layout at program header #{}", index)
+            }
+            NoDynamicSection => write!(f, "the file has no dynamic section"),
+            NoInterpreter => write!(f, "the file has no program interpreter"),
+            Located(location, error) => write!(f, "{} in {}", error, location),
+        }
+    }
+}
+
+/// The `Display` output of a [`ParseError`] already includes its source, which is the error
+/// without its location for [`ParseError::Located`].
+impl Error for ParseError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ParseError::Located(_, error) => Some(error.as_ref()),
+            ParseError::InvalidSectionName(error) => Some(error),
+            ParseError::InvalidString(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Structure {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Structure::Header => write!(f, "ELF header"),
+            Structure::ProgramHeader(index) => write!(f, "program header #{}", index),
+            Structure::SectionHeader(index) => write!(f, "section header #{}", index),
+            Structure::Segment(index) => write!(f, "segment #{}", index),
+            Structure::Section(index) => write!(f, "section #{}", index),
+            Structure::ProgramHeaderTable => write!(f, "program header table"),
+            Structure::SectionHeaderTable => write!(f, "section header table"),
+        }
+    }
+}
+
+impl ErrorLocation {
+    /// The file offset of the field, or of the structure if the field is unknown
+    pub const fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    pub const fn structure(&self) -> Structure {
+        self.structure
+    }
+
+    /// The name of the field as in the ELF specification, e.g. `sh_type`
+    pub const fn field(&self) -> Option<&'static str> {
+        self.field
+    }
+}
+
+impl Display for ErrorLocation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if let Some(field) = self.field {
+            write!(f, "{} of ", field)?;
+        }
+        write!(f, "{} at offset {:#x}", self.structure, self.offset)
+    }
+}
+
+/// A violation found by lenient parsing, like an unknown type or a misaligned address, which
+/// strict parsing reports as an error. See [`crate::elf::Metadata::parse_lenient`]. The error
+/// carries the location of the violation.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct ParseWarning {
+    offset: u64,
+    error: ParseError,
+}
+
+impl ParseWarning {
+    /// The file offset of the offending field
+    pub const fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// The error strict parsing returns for the violation
+    pub const fn error(&self) -> &ParseError {
+        &self.error
+    }
+}
+
+/// Decides whether violations abort parsing or are collected as [`ParseWarning`]s, and adds the
+/// location of the structure being parsed to both
+pub(crate) struct Warnings<'a> {
+    warnings: Option<&'a mut Vec<ParseWarning>>,
+    /// The file offset and the kind of the structure being parsed, if known
+    base: u64,
+    structure: Option<Structure>,
+}
+
+impl<'a> Warnings<'a> {
+    pub(crate) fn strict() -> Warnings<'a> {
+        Warnings {
+            warnings: None,
+            base: 0,
+            structure: None,
+        }
+    }
+
+    pub(crate) fn lenient(warnings: &'a mut Vec<ParseWarning>) -> Warnings<'a> {
+        Warnings {
+            warnings: Some(warnings),
+            base: 0,
+            structure: None,
+        }
+    }
+
+    pub(crate) fn is_lenient(&self) -> bool {
+        self.warnings.is_some()
+    }
+
+    /// Reports the violations of the structure at the file offset `offset`
+    pub(crate) fn at(&mut self, offset: u64, structure: Structure) -> Warnings<'_> {
+        Warnings {
+            warnings: self.warnings.as_deref_mut(),
+            base: offset,
+            structure: Some(structure),
+        }
+    }
+
+    /// Adds the location of the field at `offset` within the current structure to `error`
+    pub(crate) fn locate(
+        &self,
+        offset: usize,
+        field: Option<&'static str>,
+        error: ParseError,
+    ) -> ParseError {
+        match self.structure {
+            Some(structure) => error.at(self.base.saturating_add(offset as u64), structure, field),
+            None => error,
+        }
+    }
+
+    /// Returns `error` when parsing strictly, records it for the field at `offset` within the
+    /// current structure otherwise.
+    pub(crate) fn report(
+        &mut self,
+        offset: usize,
+        field: Option<&'static str>,
+        error: ParseError,
+    ) -> Result<()> {
+        let error = self.locate(offset, field, error);
+        match self.warnings.as_deref_mut() {
+            None => Err(error),
+            Some(warnings) => {
+                warnings.push(ParseWarning {
+                    offset: self.base.saturating_add(offset as u64),
+                    error,
+                });
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Returns the NUL-terminated string starting at `index` in a string table.
+pub(crate) fn parse_string(table: &[u8], index: usize) -> Result<&str> {
+    let bytes = table.get(index..).unwrap_or(&[]);
+    let null_index = bytes
+        .iter()
+        .position(|byte| *byte == 0)
+        .ok_or(ParseError::UnterminatedString)?;
+    std::str::from_utf8(&bytes[..null_index]).map_err(ParseError::InvalidString)
+}
+
+/// Rounds `value` up to a multiple of `align`, which has to be a power of two. An alignment of
+/// 0 is treated like 1.
+pub(crate) fn align_up(value: u64, align: u64) -> u64 {
+    let align = align.max(1);
+    value.saturating_add(align - 1) & !(align - 1)
+}
+
 impl FileType {
     fn parse_u16(i: u16) -> Result<FileType> {
         use FileType::*;
@@ -122,11 +501,23 @@ impl FileType {
         }
     }
 
+    pub(crate) fn to_u16(self) -> u16 {
+        use FileType::*;
+        match self {
+            None => 0x0000,
+            Relocatable => 0x0001,
+            Executable => 0x0002,
+            Shared => 0x0003,
+            Core => 0x0004,
+            Specific(i) | Unknown(i) => i,
+        }
+    }
+
     pub(crate) fn parse_bytes(bytes: &[u8], endianness: Endianness) -> Result<FileType> {
         if bytes.len() < 2 {
             Err(ParseError::InsufficientPartLength(bytes.len()))
         } else {
-            FileType::parse_u16(u16::from_bytes(bytes, endianness))
+            FileType::parse_u16(u16::from_bytes(bytes, endianness)?)
         }
     }
 }
@@ -141,6 +532,13 @@ impl WordWidth {
         }
     }
 
+    pub(crate) fn to_byte(self) -> u8 {
+        match self {
+            WordWidth::Width32 => 0x01,
+            WordWidth::Width64 => 0x02,
+        }
+    }
+
     pub fn size(&self) -> usize {
         match *self {
             WordWidth::Width32 => 4,
@@ -158,6 +556,13 @@ impl Endianness {
             _ => Err(ParseError::InvalidEndianness(b)),
         }
     }
+
+    pub(crate) fn to_byte(self) -> u8 {
+        match self {
+            Endianness::Little => 0x01,
+            Endianness::Big => 0x02,
+        }
+    }
 }
 
 impl Abi {
@@ -182,7 +587,32 @@ impl Abi {
             0x10 => FenixOS,
             0x11 => CloudABI,
             0x12 => OpenVOS,
-            _ => Unknown,
+            _ => Unknown(b),
+        }
+    }
+
+    pub(crate) fn to_byte(self) -> u8 {
+        use Abi::*;
+        match self {
+            SysV => 0x00,
+            HpUx => 0x01,
+            NetBSD => 0x02,
+            Linux => 0x03,
+            GnuHurd => 0x04,
+            Solaris => 0x06,
+            Aix => 0x07,
+            Irix => 0x08,
+            FreeBSD => 0x09,
+            Tru64 => 0x0A,
+            NovellModesto => 0x0B,
+            OpenBSD => 0x0C,
+            OpenVMS => 0x0D,
+            NonStopKernel => 0x0E,
+            Aros => 0x0F,
+            FenixOS => 0x10,
+            CloudABI => 0x11,
+            OpenVOS => 0x12,
+            Unknown(b) => b,
         }
     }
 }
@@ -216,7 +646,39 @@ impl Arch {
             0x00F3 => RiscV,
             0x00F7 => Bpf,
             0x0101 => WDC65C816,
-            _ => Unknown,
+            _ => Unknown(i),
+        }
+    }
+
+    pub(crate) fn to_u16(self) -> u16 {
+        use Arch::*;
+        match self {
+            Unspecified => 0x0000,
+            WE32100 => 0x0001,
+            Sparc => 0x0002,
+            X86 => 0x0003,
+            M68k => 0x0004,
+            M88k => 0x0005,
+            IntelMCU => 0x0006,
+            Intel80860 => 0x0007,
+            Mips => 0x0008,
+            System370 => 0x0009,
+            RS3000 => 0x000A,
+            Parsic => 0x000E,
+            Intel80960 => 0x0013,
+            PowerPC => 0x0014,
+            PowerPC64 => 0x0015,
+            S390 => 0x0016,
+            Arm => 0x0028,
+            SuperH => 0x002A,
+            IA64 => 0x0032,
+            X86_64 => 0x003E,
+            TMS320C6000 => 0x008C,
+            AArch64 => 0x00B7,
+            RiscV => 0x00F3,
+            Bpf => 0x00F7,
+            WDC65C816 => 0x0101,
+            Unknown(i) => i,
         }
     }
 
@@ -225,7 +687,7 @@ impl Arch {
         if bytes.len() < 2 {
             Err(ParseError::InsufficientPartLength(bytes.len()))
         } else {
-            Ok(Arch::from_u16(u16::from_bytes(bytes, endianness)))
+            Ok(Arch::from_u16(u16::from_bytes(bytes, endianness)?))
         }
     }
 }
@@ -241,14 +703,14 @@ impl Word {
                 if bytes.len() < 4 {
                     Err(ParseError::InsufficientPartLength(bytes.len()))
                 } else {
-                    Ok(Word::Word32(u32::from_bytes(bytes, endianness)))
+                    Ok(Word::Word32(u32::from_bytes(bytes, endianness)?))
                 }
             }
             WordWidth::Width64 => {
                 if bytes.len() < 8 {
                     Err(ParseError::InsufficientPartLength(bytes.len()))
                 } else {
-                    Ok(Word::Word64(u64::from_bytes(bytes, endianness)))
+                    Ok(Word::Word64(u64::from_bytes(bytes, endianness)?))
                 }
             }
         }
@@ -261,12 +723,34 @@ impl Word {
         }
     }
 
+    /// Appends the word with the given width, the value is truncated if it doesn't fit into 32
+    /// bits.
+    pub(crate) fn write_bytes(
+        self,
+        bytes: &mut Vec<u8>,
+        word_width: WordWidth,
+        endianness: Endianness,
+    ) {
+        match word_width {
+            WordWidth::Width32 => (u64::from(self) as u32).write_bytes(bytes, endianness),
+            WordWidth::Width64 => u64::from(self).write_bytes(bytes, endianness),
+        }
+    }
+
     pub fn zero(word_width: WordWidth) -> Self {
         match word_width {
             WordWidth::Width32 => Word::Word32(0),
             WordWidth::Width64 => Word::Word64(0),
         }
     }
+
+    /// A word of the given width, 32-bit words keep the lower half of `value`
+    pub fn new(word_width: WordWidth, value: u64) -> Self {
+        match word_width {
+            WordWidth::Width32 => Word::Word32(value as u32),
+            WordWidth::Width64 => Word::Word64(value),
+        }
+    }
 }
 
 impl From<Word> for u64 {
@@ -339,6 +823,30 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_located_error() {
+        let error = ParseError::InvalidSectionHeaderType(0x14).at(
+            0x144,
+            Structure::SectionHeader(1),
+            Some("sh_type"),
+        );
+        assert_eq!(
+            error.to_string(),
+            "unknown section header type 0x14 in sh_type of section header #1 at offset 0x144"
+        );
+        assert_eq!(error.kind(), &ParseError::InvalidSectionHeaderType(0x14));
+        assert_eq!(
+            error.source().map(ToString::to_string),
+            Some(String::from("unknown section header type 0x14"))
+        );
+        // an outer structure does not replace the more precise location
+        let error = error.at(0x100, Structure::SectionHeaderTable, None);
+        let location = error.location().expect("located error");
+        assert_eq!(location.offset(), 0x144);
+        assert_eq!(location.structure(), Structure::SectionHeader(1));
+        assert_eq!(location.field(), Some("sh_type"));
+    }
+
     #[test]
     fn test_file_type_err() {
         let test_data = [
@@ -413,7 +921,7 @@ mod test {
             NetBSD,
             Linux,
             GnuHurd,
-            Unknown,
+            Unknown(0x05),
             Solaris,
             Aix,
             Irix,
@@ -427,10 +935,11 @@ mod test {
             FenixOS,
             CloudABI,
             OpenVOS,
-            Unknown,
+            Unknown(0x13),
         ];
         for (i, expected) in test_data.iter().enumerate() {
             assert_eq!(Abi::from_byte(i as u8), *expected);
+            assert_eq!(expected.to_byte(), i as u8);
         }
     }
 
@@ -463,12 +972,13 @@ mod test {
             (0x00F3, RiscV),
             (0x00F7, Bpf),
             (0x0101, WDC65C816),
-            (0x0102, Unknown),
-            (0xFFFF, Unknown),
+            (0x0102, Unknown(0x0102)),
+            (0xFFFF, Unknown(0xFFFF)),
         ];
         for (code, expected) in test_data.iter() {
             let bytes = code.to_le_bytes();
             assert_eq!(Arch::parse_bytes(&bytes, Endianness::Little), Ok(*expected));
+            assert_eq!(expected.to_u16(), *code);
         }
     }
 
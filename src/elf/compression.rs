@@ -0,0 +1,238 @@
+use std::convert::TryFrom;
+use std::io::Read;
+
+use super::*;
+
+/// Magic bytes of the legacy `.zdebug_*` sections, followed by the big endian decompressed size
+const ZDEBUG_MAGIC: &[u8] = b"ZLIB";
+
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum CompressionType {
+    Zlib,
+    Zstd,
+    OsSpecific(u32),
+    ProcessorSpecific(u32),
+}
+
+/// The `Elf_Chdr` header at the start of the contents of a section with the `COMPRESSED` flag.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct CompressionHeader {
+    typ: CompressionType,
+    size: Word,
+    align: Word,
+}
+
+impl CompressionType {
+    pub fn parse_u32(raw: u32) -> Result<CompressionType> {
+        use CompressionType::*;
+        match raw {
+            1 => Ok(Zlib),
+            2 => Ok(Zstd),
+            0x60000000..=0x6FFFFFFF => Ok(OsSpecific(raw)),
+            0x70000000..=0x7FFFFFFF => Ok(ProcessorSpecific(raw)),
+            _ => Err(ParseError::InvalidCompressionType(raw)),
+        }
+    }
+}
+
+impl CompressionHeader {
+    pub fn parse_bytes(
+        bytes: &[u8],
+        word_width: WordWidth,
+        endianness: Endianness,
+    ) -> Result<CompressionHeader> {
+        CompressionHeader::check_length(CompressionHeader::size_of(word_width), bytes.len())?;
+        let typ = CompressionType::parse_u32(u32::from_bytes(bytes, endianness)?)?;
+        // 64-bit headers have a reserved word after the type
+        let (size, align) = match word_width {
+            WordWidth::Width32 => (4, 8),
+            WordWidth::Width64 => (8, 16),
+        };
+        Ok(CompressionHeader {
+            typ,
+            size: Word::parse_bytes(&bytes[size..], word_width, endianness)?,
+            align: Word::parse_bytes(&bytes[align..], word_width, endianness)?,
+        })
+    }
+
+    /// The size of the compression header in bytes
+    pub const fn size_of(word_width: WordWidth) -> usize {
+        match word_width {
+            WordWidth::Width32 => 12,
+            WordWidth::Width64 => 24,
+        }
+    }
+
+    pub const fn typ(&self) -> CompressionType {
+        self.typ
+    }
+
+    /// The size of the decompressed data
+    pub const fn size(&self) -> Word {
+        self.size
+    }
+
+    /// The alignment of the decompressed data
+    pub const fn align(&self) -> Word {
+        self.align
+    }
+
+    fn check_length(expected: usize, actual: usize) -> Result<()> {
+        if actual < expected {
+            Err(ParseError::InsufficientCompressionHeaderLength(actual))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Decompresses the contents of a section with the `COMPRESSED` flag, which start with a
+/// `CompressionHeader`.
+pub fn decompress_section(
+    bytes: &[u8],
+    word_width: WordWidth,
+    endianness: Endianness,
+) -> Result<Vec<u8>> {
+    let header = CompressionHeader::parse_bytes(bytes, word_width, endianness)?;
+    let data = &bytes[CompressionHeader::size_of(word_width)..];
+    let size = u64::from(header.size());
+    match header.typ() {
+        CompressionType::Zlib => decompress(flate2::read::ZlibDecoder::new(data), size),
+        CompressionType::Zstd => {
+            let decoder = ruzstd::streaming_decoder::StreamingDecoder::new(data)
+                .map_err(|_| ParseError::InvalidCompressedData)?;
+            decompress(decoder, size)
+        }
+        CompressionType::OsSpecific(raw) | CompressionType::ProcessorSpecific(raw) => {
+            Err(ParseError::InvalidCompressionType(raw))
+        }
+    }
+}
+
+/// Decompresses the contents of a legacy `.zdebug_*` section. Sections without the `ZLIB` magic
+/// are not compressed and returned as they are.
+pub fn decompress_zdebug(bytes: &[u8]) -> Result<Vec<u8>> {
+    if !bytes.starts_with(ZDEBUG_MAGIC) {
+        return Ok(bytes.to_vec());
+    }
+    if bytes.len() < 12 {
+        return Err(ParseError::InsufficientCompressionHeaderLength(bytes.len()));
+    }
+    let size = u64::from_bytes(&bytes[4..], Endianness::Big)?;
+    decompress(flate2::read::ZlibDecoder::new(&bytes[12..]), size)
+}
+
+fn decompress<R: Read>(decoder: R, size: u64) -> Result<Vec<u8>> {
+    // the capacity is capped so a bogus size can't exhaust the memory up front
+    let capacity = usize::try_from(size.min(1 << 24)).unwrap_or(0);
+    let mut decompressed = Vec::with_capacity(capacity);
+    decoder
+        .take(size.saturating_add(1))
+        .read_to_end(&mut decompressed)
+        .map_err(|_| ParseError::InvalidCompressedData)?;
+    if decompressed.len() as u64 != size {
+        return Err(ParseError::InvalidDecompressedSize(
+            size,
+            decompressed.len() as u64,
+        ));
+    }
+    Ok(decompressed)
+}
+
+#[cfg(test)]
+mod test {
+    use std::borrow::Cow;
+    use std::io::Write;
+
+    use super::*;
+    use crate::elf::testing::{Fixture, FixtureSection};
+
+    fn zlib(data: &[u8]) -> Vec<u8> {
+        let mut encoder =
+            flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(data).expect("in memory");
+        encoder.finish().expect("in memory")
+    }
+
+    #[test]
+    fn test_compression_header() {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&2_u32.to_be_bytes());
+        bytes.extend_from_slice(&0x100_u32.to_be_bytes());
+        bytes.extend_from_slice(&1_u32.to_be_bytes());
+        let header = CompressionHeader::parse_bytes(&bytes, WordWidth::Width32, Endianness::Big);
+        assert_eq!(
+            header,
+            Ok(CompressionHeader {
+                typ: CompressionType::Zstd,
+                size: Word::Word32(0x100),
+                align: Word::Word32(1),
+            })
+        );
+        assert_eq!(
+            CompressionHeader::parse_bytes(&bytes, WordWidth::Width64, Endianness::Big),
+            Err(ParseError::InsufficientCompressionHeaderLength(12))
+        );
+    }
+
+    #[test]
+    fn test_decompress_zstd() {
+        // "hello hello hello\n" compressed by `zstd -19`
+        let frame = [
+            0x28, 0xB5, 0x2F, 0xFD, 0x04, 0x68, 0x6D, 0x00, 0x00, 0x38, 0x68, 0x65, 0x6C, 0x6C,
+            0x6F, 0x20, 0x0A, 0x01, 0x00, 0x31, 0x4A, 0x11, 0xA3, 0xAA, 0x74, 0xCE,
+        ];
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&2_u32.to_le_bytes());
+        bytes.extend_from_slice(&18_u32.to_le_bytes());
+        bytes.extend_from_slice(&1_u32.to_le_bytes());
+        bytes.extend_from_slice(&frame);
+        assert_eq!(
+            decompress_section(&bytes, WordWidth::Width32, Endianness::Little),
+            Ok(b"hello hello hello\n".to_vec())
+        );
+    }
+
+    #[test]
+    fn test_decompressed_section_data() {
+        let text = b"some debug information, some debug information";
+        let mut fixture = Fixture::new(WordWidth::Width64, Endianness::Little);
+        let mut w = fixture.writer();
+        w.u32(1).u32(0).u64(text.len() as u64).u64(1);
+        w.bytes(&zlib(text));
+        let debug_info =
+            fixture.section(FixtureSection::new(".debug_info", 1, w.bytes).flags(0x800));
+        let mut zdebug = b"ZLIB".to_vec();
+        zdebug.extend_from_slice(&(text.len() as u64).to_be_bytes());
+        zdebug.extend_from_slice(&zlib(text));
+        let zdebug_info = fixture.section(FixtureSection::new(".zdebug_info", 1, zdebug));
+        let bytes = fixture.build();
+        let metadata = Metadata::parse_bytes(&bytes).expect("valid ELF");
+        assert!(metadata.section_headers()[debug_info]
+            .flags()
+            .contains(SectionHeaderFlags::COMPRESSED));
+
+        let elf = ElfFile::parse(&bytes).expect("valid ELF");
+        for index in [debug_info, zdebug_info].iter() {
+            let sheader = elf.section_header(*index).unwrap().unwrap();
+            let data = elf.decompressed_section_data(&sheader);
+            assert_eq!(data.as_deref(), Ok(&text[..]));
+        }
+        let shstrtab = elf.section_header_by_name(".shstrtab").unwrap().unwrap();
+        assert!(matches!(
+            elf.decompressed_section_data(&shstrtab),
+            Ok(Cow::Borrowed(_))
+        ));
+    }
+
+    #[test]
+    fn test_decompress_size_mismatch() {
+        let mut zdebug = b"ZLIB".to_vec();
+        zdebug.extend_from_slice(&100_u64.to_be_bytes());
+        zdebug.extend_from_slice(&zlib(b"short"));
+        assert_eq!(
+            decompress_zdebug(&zdebug),
+            Err(ParseError::InvalidDecompressedSize(100, 5))
+        );
+    }
+}
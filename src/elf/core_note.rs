@@ -0,0 +1,592 @@
+use std::fmt::{Display, Formatter};
+
+use super::*;
+
+pub(crate) const NT_PRSTATUS: u32 = 1;
+pub(crate) const NT_PRFPREG: u32 = 2;
+pub(crate) const NT_PRPSINFO: u32 = 3;
+pub(crate) const NT_TASKSTRUCT: u32 = 4;
+pub(crate) const NT_AUXV: u32 = 6;
+pub(crate) const NT_SIGINFO: u32 = 0x53494749;
+pub(crate) const NT_FILE: u32 = 0x46494C45;
+pub(crate) const NT_X86_XSTATE: u32 = 0x202;
+
+const X86_64_REGISTERS: [&str; 27] = [
+    "r15", "r14", "r13", "r12", "rbp", "rbx", "r11", "r10", "r9", "r8", "rax", "rcx", "rdx", "rsi",
+    "rdi", "orig_rax", "rip", "cs", "eflags", "rsp", "ss", "fs_base", "gs_base", "ds", "es", "fs",
+    "gs",
+];
+const X86_REGISTERS: [&str; 17] = [
+    "ebx", "ecx", "edx", "esi", "edi", "ebp", "eax", "ds", "es", "fs", "gs", "orig_eax", "eip",
+    "cs", "eflags", "esp", "ss",
+];
+const AARCH64_REGISTERS: [&str; 34] = [
+    "x0", "x1", "x2", "x3", "x4", "x5", "x6", "x7", "x8", "x9", "x10", "x11", "x12", "x13", "x14",
+    "x15", "x16", "x17", "x18", "x19", "x20", "x21", "x22", "x23", "x24", "x25", "x26", "x27",
+    "x28", "x29", "x30", "sp", "pc", "pstate",
+];
+const ARM_REGISTERS: [&str; 18] = [
+    "r0", "r1", "r2", "r3", "r4", "r5", "r6", "r7", "r8", "r9", "r10", "fp", "ip", "sp", "lr",
+    "pc", "cpsr", "orig_r0",
+];
+
+/// The decoded descriptor of a Linux core dump note.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum CoreNote<'data> {
+    PrStatus(PrStatus),
+    PrPsInfo(PrPsInfo),
+    SigInfo(SigInfo),
+    Auxv(Vec<AuxvEntry>),
+    File(FileMappings<'data>),
+}
+
+/// The status of a thread (`NT_PRSTATUS`), every thread of the process has its own note.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct PrStatus {
+    pub signal: i16,
+    pub pid: i32,
+    pub ppid: i32,
+    pub pgrp: i32,
+    pub sid: i32,
+    /// The general purpose registers by name. Empty for architectures whose register layout
+    /// isn't known.
+    pub registers: Vec<(&'static str, u64)>,
+}
+
+/// Information about the process (`NT_PRPSINFO`).
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct PrPsInfo {
+    pub state: u8,
+    pub state_name: char,
+    pub zombie: bool,
+    pub nice: i8,
+    pub flags: u64,
+    pub uid: u32,
+    pub gid: u32,
+    pub pid: i32,
+    pub ppid: i32,
+    pub pgrp: i32,
+    pub sid: i32,
+    /// The executable name, truncated to 15 bytes by the kernel
+    pub name: String,
+    /// The start of the command line, truncated to 79 bytes by the kernel
+    pub arguments: String,
+}
+
+/// The signal which caused the core dump (`NT_SIGINFO`).
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct SigInfo {
+    pub signal: i32,
+    pub errno: i32,
+    pub code: i32,
+    /// The faulting address of `SIGSEGV`, `SIGBUS`, `SIGILL` and `SIGFPE`
+    pub address: Option<u64>,
+}
+
+/// An entry of the auxiliary vector passed to the process by the kernel (`NT_AUXV`).
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct AuxvEntry {
+    pub typ: u64,
+    pub value: u64,
+}
+
+/// The files mapped into the address space of the process (`NT_FILE`).
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct FileMappings<'data> {
+    pub page_size: u64,
+    pub files: Vec<MappedFile<'data>>,
+}
+
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct MappedFile<'data> {
+    pub start: u64,
+    pub end: u64,
+    /// The offset of the mapping in the file in units of `page_size`
+    pub page_offset: u64,
+    pub path: &'data str,
+}
+
+/// Reads the word at `index` words into `bytes`
+fn word(bytes: &[u8], index: usize, word_width: WordWidth, endianness: Endianness) -> Result<u64> {
+    let bytes = index
+        .checked_mul(word_width.size())
+        .and_then(|start| bytes.get(start..))
+        .ok_or(ParseError::InsufficientNoteLength(bytes.len()))?;
+    Word::parse_bytes(bytes, word_width, endianness).map(u64::from)
+}
+
+fn check_length(expected: usize, actual: usize) -> Result<()> {
+    if actual < expected {
+        Err(ParseError::InsufficientNoteLength(actual))
+    } else {
+        Ok(())
+    }
+}
+
+fn c_string(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end])
+        .trim_end()
+        .to_string()
+}
+
+impl<'data> CoreNote<'data> {
+    /// Decodes the core dump notes with the name `CORE`. The register layout of `NT_PRSTATUS`
+    /// depends on the architecture. Returns `None` for other notes.
+    pub fn decode(note: &Note<'data>, arch: Arch) -> Result<Option<CoreNote<'data>>> {
+        if note.name() != b"CORE" {
+            return Ok(None);
+        }
+        let descriptor = note.descriptor();
+        let word_width = note.word_width();
+        let endianness = note.endianness();
+        let decoded = match note.typ() {
+            NT_PRSTATUS => CoreNote::PrStatus(PrStatus::parse_bytes(
+                descriptor, arch, word_width, endianness,
+            )?),
+            NT_PRPSINFO => {
+                CoreNote::PrPsInfo(PrPsInfo::parse_bytes(descriptor, word_width, endianness)?)
+            }
+            NT_SIGINFO => {
+                CoreNote::SigInfo(SigInfo::parse_bytes(descriptor, word_width, endianness)?)
+            }
+            NT_AUXV => CoreNote::Auxv(AuxvEntry::parse_all(descriptor, word_width, endianness)?),
+            NT_FILE => CoreNote::File(FileMappings::parse_bytes(
+                descriptor, word_width, endianness,
+            )?),
+            _ => return Ok(None),
+        };
+        Ok(Some(decoded))
+    }
+}
+
+impl PrStatus {
+    pub fn parse_bytes(
+        bytes: &[u8],
+        arch: Arch,
+        word_width: WordWidth,
+        endianness: Endianness,
+    ) -> Result<PrStatus> {
+        let long = word_width.size();
+        let names: &[&'static str] = match arch {
+            Arch::X86_64 => &X86_64_REGISTERS,
+            Arch::X86 => &X86_REGISTERS,
+            Arch::AArch64 => &AARCH64_REGISTERS,
+            Arch::Arm => &ARM_REGISTERS,
+            _ => &[],
+        };
+        // pr_info (3 ints), pr_cursig, pr_sigpend, pr_sighold, 4 pids and 4 timevals
+        let pids = 16 + 2 * long;
+        let registers_start = pids + 16 + 8 * long;
+        check_length(registers_start + names.len() * long, bytes.len())?;
+        let registers = names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| {
+                let value = word(&bytes[registers_start..], i, word_width, endianness)?;
+                Ok((*name, value))
+            })
+            .collect::<Result<_>>()?;
+        let pid =
+            |i: usize| u32::from_bytes(&bytes[pids + 4 * i..], endianness).map(|pid| pid as i32);
+        Ok(PrStatus {
+            signal: u16::from_bytes(&bytes[12..], endianness)? as i16,
+            pid: pid(0)?,
+            ppid: pid(1)?,
+            pgrp: pid(2)?,
+            sid: pid(3)?,
+            registers,
+        })
+    }
+
+    pub fn register(&self, name: &str) -> Option<u64> {
+        self.registers
+            .iter()
+            .find(|(register, _)| *register == name)
+            .map(|(_, value)| *value)
+    }
+}
+
+impl PrPsInfo {
+    pub fn parse_bytes(
+        bytes: &[u8],
+        word_width: WordWidth,
+        endianness: Endianness,
+    ) -> Result<PrPsInfo> {
+        let long = word_width.size();
+        // uid and gid are 16 bit wide in the 32-bit layout
+        let id_size = match word_width {
+            WordWidth::Width32 => 2,
+            WordWidth::Width64 => 4,
+        };
+        let ids = 2 * long;
+        let pids = ids + 2 * id_size;
+        let name = pids + 16;
+        let arguments = name + 16;
+        check_length(arguments + 80, bytes.len())?;
+        let id = |offset: usize| match word_width {
+            WordWidth::Width32 => u16::from_bytes(&bytes[offset..], endianness).map(u32::from),
+            WordWidth::Width64 => u32::from_bytes(&bytes[offset..], endianness),
+        };
+        let pid =
+            |i: usize| u32::from_bytes(&bytes[pids + 4 * i..], endianness).map(|pid| pid as i32);
+        Ok(PrPsInfo {
+            state: bytes[0],
+            state_name: bytes[1] as char,
+            zombie: bytes[2] != 0,
+            nice: bytes[3] as i8,
+            flags: word(bytes, 1, word_width, endianness)?,
+            uid: id(ids)?,
+            gid: id(ids + id_size)?,
+            pid: pid(0)?,
+            ppid: pid(1)?,
+            pgrp: pid(2)?,
+            sid: pid(3)?,
+            name: c_string(&bytes[name..name + 16]),
+            arguments: c_string(&bytes[arguments..arguments + 80]),
+        })
+    }
+}
+
+impl SigInfo {
+    pub fn parse_bytes(
+        bytes: &[u8],
+        word_width: WordWidth,
+        endianness: Endianness,
+    ) -> Result<SigInfo> {
+        // the union of signal specific fields is aligned to the word width
+        let fields = match word_width {
+            WordWidth::Width32 => 12,
+            WordWidth::Width64 => 16,
+        };
+        check_length(fields + word_width.size(), bytes.len())?;
+        let int = |i: usize| u32::from_bytes(&bytes[4 * i..], endianness).map(|int| int as i32);
+        let (signal, code) = (int(0)?, int(2)?);
+        // only signals raised by the kernel have a positive code, a signal sent by `kill` carries
+        // the pid and uid of the sender instead
+        let address = match signal {
+            // SIGILL, SIGFPE, SIGSEGV and SIGBUS
+            4 | 8 | 11 | 7 if code > 0 => Some(word(&bytes[fields..], 0, word_width, endianness)?),
+            _ => None,
+        };
+        Ok(SigInfo {
+            signal,
+            errno: int(1)?,
+            code,
+            address,
+        })
+    }
+}
+
+impl AuxvEntry {
+    /// Parses the auxiliary vector up to the terminating `AT_NULL` entry.
+    pub fn parse_all(
+        bytes: &[u8],
+        word_width: WordWidth,
+        endianness: Endianness,
+    ) -> Result<Vec<AuxvEntry>> {
+        let entry_size = 2 * word_width.size();
+        let mut entries = Vec::new();
+        for entry in bytes.chunks_exact(entry_size) {
+            let typ = word(entry, 0, word_width, endianness)?;
+            if typ == 0 {
+                break;
+            }
+            entries.push(AuxvEntry {
+                typ,
+                value: word(entry, 1, word_width, endianness)?,
+            });
+        }
+        Ok(entries)
+    }
+
+    /// The name of the entry type, e.g. `AT_PHDR`
+    pub fn type_name(&self) -> Option<&'static str> {
+        let name = match self.typ {
+            1 => "AT_IGNORE",
+            2 => "AT_EXECFD",
+            3 => "AT_PHDR",
+            4 => "AT_PHENT",
+            5 => "AT_PHNUM",
+            6 => "AT_PAGESZ",
+            7 => "AT_BASE",
+            8 => "AT_FLAGS",
+            9 => "AT_ENTRY",
+            10 => "AT_NOTELF",
+            11 => "AT_UID",
+            12 => "AT_EUID",
+            13 => "AT_GID",
+            14 => "AT_EGID",
+            15 => "AT_PLATFORM",
+            16 => "AT_HWCAP",
+            17 => "AT_CLKTCK",
+            23 => "AT_SECURE",
+            24 => "AT_BASE_PLATFORM",
+            25 => "AT_RANDOM",
+            26 => "AT_HWCAP2",
+            27 => "AT_RSEQ_FEATURE_SIZE",
+            28 => "AT_RSEQ_ALIGN",
+            29 => "AT_HWCAP3",
+            30 => "AT_HWCAP4",
+            31 => "AT_EXECFN",
+            32 => "AT_SYSINFO",
+            33 => "AT_SYSINFO_EHDR",
+            51 => "AT_MINSIGSTKSZ",
+            _ => return None,
+        };
+        Some(name)
+    }
+}
+
+impl<'data> FileMappings<'data> {
+    pub fn parse_bytes(
+        bytes: &'data [u8],
+        word_width: WordWidth,
+        endianness: Endianness,
+    ) -> Result<FileMappings<'data>> {
+        let long = word_width.size();
+        check_length(2 * long, bytes.len())?;
+        let count = word(bytes, 0, word_width, endianness)?;
+        let page_size = word(bytes, 1, word_width, endianness)?;
+        let names_start = (count as usize)
+            .checked_mul(3)
+            .and_then(|words| words.checked_add(2))
+            .and_then(|words| words.checked_mul(long))
+            .filter(|end| *end <= bytes.len())
+            .ok_or(ParseError::InsufficientNoteLength(bytes.len()))?;
+        let mut names = &bytes[names_start..];
+        let mut files = Vec::with_capacity(count as usize);
+        for i in 0..count as usize {
+            let path = parse_string(names, 0)?;
+            names = &names[path.len() + 1..];
+            let entry = 2 + 3 * i;
+            files.push(MappedFile {
+                start: word(bytes, entry, word_width, endianness)?,
+                end: word(bytes, entry + 1, word_width, endianness)?,
+                page_offset: word(bytes, entry + 2, word_width, endianness)?,
+                path,
+            });
+        }
+        Ok(FileMappings { page_size, files })
+    }
+}
+
+/// The name of a Linux signal number, e.g. `SIGSEGV`
+pub fn signal_name(signal: i32) -> Option<&'static str> {
+    let name = match signal {
+        1 => "SIGHUP",
+        2 => "SIGINT",
+        3 => "SIGQUIT",
+        4 => "SIGILL",
+        5 => "SIGTRAP",
+        6 => "SIGABRT",
+        7 => "SIGBUS",
+        8 => "SIGFPE",
+        9 => "SIGKILL",
+        10 => "SIGUSR1",
+        11 => "SIGSEGV",
+        12 => "SIGUSR2",
+        13 => "SIGPIPE",
+        14 => "SIGALRM",
+        15 => "SIGTERM",
+        16 => "SIGSTKFLT",
+        17 => "SIGCHLD",
+        18 => "SIGCONT",
+        19 => "SIGSTOP",
+        20 => "SIGTSTP",
+        21 => "SIGTTIN",
+        22 => "SIGTTOU",
+        23 => "SIGURG",
+        24 => "SIGXCPU",
+        25 => "SIGXFSZ",
+        26 => "SIGVTALRM",
+        27 => "SIGPROF",
+        28 => "SIGWINCH",
+        29 => "SIGIO",
+        30 => "SIGPWR",
+        31 => "SIGSYS",
+        _ => return None,
+    };
+    Some(name)
+}
+
+/// The name of a core dump note type as printed by `readelf -n`
+pub(crate) fn core_note_type_name(typ: u32) -> Option<&'static str> {
+    match typ {
+        NT_PRSTATUS => Some("NT_PRSTATUS (prstatus structure)"),
+        NT_PRFPREG => Some("NT_FPREGSET (floating point registers)"),
+        NT_PRPSINFO => Some("NT_PRPSINFO (prpsinfo structure)"),
+        NT_TASKSTRUCT => Some("NT_TASKSTRUCT (task structure)"),
+        NT_AUXV => Some("NT_AUXV (auxiliary vector)"),
+        NT_SIGINFO => Some("NT_SIGINFO (siginfo_t data)"),
+        NT_FILE => Some("NT_FILE (mapped files)"),
+        _ => None,
+    }
+}
+
+impl<'data> Display for CoreNote<'data> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CoreNote::PrStatus(status) => write!(
+                f,
+                "pid: {}, ppid: {}, signal: {}",
+                status.pid, status.ppid, status.signal
+            ),
+            CoreNote::PrPsInfo(info) => write!(
+                f,
+                "name: {}, pid: {}, uid: {}, gid: {}, args: {}",
+                info.name, info.pid, info.uid, info.gid, info.arguments
+            ),
+            CoreNote::SigInfo(info) => {
+                write!(
+                    f,
+                    "si_signo: {}, si_errno: {}, si_code: {}",
+                    info.signal, info.errno, info.code
+                )?;
+                if let Some(address) = info.address {
+                    write!(f, ", si_addr: {:#x}", address)?;
+                }
+                Ok(())
+            }
+            CoreNote::Auxv(entries) => write!(f, "{} auxiliary vector entries", entries.len()),
+            CoreNote::File(mappings) => write!(
+                f,
+                "{} mapped files, page size: {}",
+                mappings.files.len(),
+                mappings.page_size
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::elf::testing::{Fixture, FixtureSection, Writer};
+
+    fn prstatus_x86_64(w: &mut Writer) {
+        w.u32(11).u32(0).u32(0).u16(11).u16(0);
+        w.u64(0).u64(0);
+        w.u32(4242).u32(1).u32(4242).u32(4242);
+        w.bytes(&[0; 64]);
+        for i in 0..27_u64 {
+            w.u64(0x1000 + i);
+        }
+        w.u32(1).u32(0);
+    }
+
+    #[test]
+    fn test_prstatus() {
+        let mut w = Writer::new(WordWidth::Width64, Endianness::Little);
+        prstatus_x86_64(&mut w);
+        assert_eq!(w.bytes.len(), 336);
+        let status = PrStatus::parse_bytes(
+            &w.bytes,
+            Arch::X86_64,
+            WordWidth::Width64,
+            Endianness::Little,
+        )
+        .expect("valid prstatus");
+        assert_eq!(status.signal, 11);
+        assert_eq!(status.pid, 4242);
+        assert_eq!(status.register("r15"), Some(0x1000));
+        assert_eq!(status.register("rip"), Some(0x1010));
+        assert_eq!(
+            PrStatus::parse_bytes(
+                &w.bytes,
+                Arch::AArch64,
+                WordWidth::Width64,
+                Endianness::Little
+            ),
+            Err(ParseError::InsufficientNoteLength(336))
+        );
+    }
+
+    #[test]
+    fn test_prpsinfo_32() {
+        let mut w = Writer::new(WordWidth::Width32, Endianness::Little);
+        w.u8(0).u8(b'R').u8(0).u8(0).u32(0x400600);
+        w.u16(1000).u16(100);
+        w.u32(77).u32(1).u32(77).u32(77);
+        let mut name = b"sleep".to_vec();
+        name.resize(16, 0);
+        let mut arguments = b"sleep 100 ".to_vec();
+        arguments.resize(80, 0);
+        w.bytes(&name).bytes(&arguments);
+        assert_eq!(w.bytes.len(), 124);
+        let info = PrPsInfo::parse_bytes(&w.bytes, WordWidth::Width32, Endianness::Little)
+            .expect("valid prpsinfo");
+        assert_eq!(info.state_name, 'R');
+        assert_eq!(info.uid, 1000);
+        assert_eq!(info.gid, 100);
+        assert_eq!(info.pid, 77);
+        assert_eq!(info.name, "sleep");
+        assert_eq!(info.arguments, "sleep 100");
+    }
+
+    #[test]
+    fn test_core_notes() {
+        let mut fixture = Fixture::new(WordWidth::Width64, Endianness::Little);
+        fixture.file_type = 4;
+        let mut notes = fixture.writer();
+        let mut note = |typ: u32, descriptor: &[u8]| {
+            notes.u32(5).u32(descriptor.len() as u32).u32(typ);
+            notes.bytes(b"CORE\0").align(4).bytes(descriptor).align(4);
+        };
+        let mut w = Writer::new(WordWidth::Width64, Endianness::Little);
+        prstatus_x86_64(&mut w);
+        note(NT_PRSTATUS, &w.bytes);
+        let mut w = Writer::new(WordWidth::Width64, Endianness::Little);
+        w.u32(11).u32(0).u32(1).u32(0).u64(0xDEAD);
+        w.bytes(&[0; 104]);
+        note(NT_SIGINFO, &w.bytes);
+        let mut w = Writer::new(WordWidth::Width64, Endianness::Little);
+        w.u64(6).u64(4096).u64(9).u64(0x401000).u64(0).u64(0);
+        note(NT_AUXV, &w.bytes);
+        let mut w = Writer::new(WordWidth::Width64, Endianness::Little);
+        w.u64(2).u64(4096);
+        w.u64(0x400000).u64(0x401000).u64(0);
+        w.u64(0x401000).u64(0x402000).u64(1);
+        w.bytes(b"/bin/crash\0/bin/crash\0");
+        note(NT_FILE, &w.bytes);
+        let notes = notes.bytes;
+        fixture.section(FixtureSection::new("note0", 7, notes).align(4));
+        fixture.segment(4, 0, 1..2, 4);
+        let mut bytes = fixture.build();
+        // core dumps have no section headers
+        bytes[40..48].copy_from_slice(&[0; 8]);
+        bytes[60..64].copy_from_slice(&[0; 4]);
+
+        let elf = ElfFile::parse(&bytes).expect("valid ELF");
+        let core_notes = elf.core_notes().expect("valid core notes");
+        assert_eq!(core_notes.len(), 4);
+        match &core_notes[0] {
+            CoreNote::PrStatus(status) => assert_eq!(status.register("rax"), Some(0x100A)),
+            other => panic!("expected NT_PRSTATUS, got {:?}", other),
+        }
+        assert_eq!(
+            core_notes[1],
+            CoreNote::SigInfo(SigInfo {
+                signal: 11,
+                errno: 0,
+                code: 1,
+                address: Some(0xDEAD)
+            })
+        );
+        match &core_notes[2] {
+            CoreNote::Auxv(entries) => {
+                assert_eq!(entries.len(), 2);
+                assert_eq!(entries[1].type_name(), Some("AT_ENTRY"));
+            }
+            other => panic!("expected NT_AUXV, got {:?}", other),
+        }
+        match &core_notes[3] {
+            CoreNote::File(mappings) => {
+                assert_eq!(mappings.files.len(), 2);
+                assert_eq!(mappings.files[1].path, "/bin/crash");
+                assert_eq!(mappings.files[1].page_offset, 1);
+            }
+            other => panic!("expected NT_FILE, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,633 @@
+use std::convert::TryFrom;
+use std::fmt::{Display, Formatter};
+
+use super::*;
+
+bitflags::bitflags! {
+    /// The flags of a `DT_FLAGS` entry
+    pub struct DynamicFlags: u64 {
+        const ORIGIN = 0x1;
+        const SYMBOLIC = 0x2;
+        const TEXTREL = 0x4;
+        const BIND_NOW = 0x8;
+        const STATIC_TLS = 0x10;
+    }
+}
+
+bitflags::bitflags! {
+    /// The flags of a `DT_FLAGS_1` entry
+    pub struct DynamicFlags1: u64 {
+        const NOW = 0x1;
+        const GLOBAL = 0x2;
+        const GROUP = 0x4;
+        const NODELETE = 0x8;
+        const LOADFLTR = 0x10;
+        const INITFIRST = 0x20;
+        const NOOPEN = 0x40;
+        const ORIGIN = 0x80;
+        const DIRECT = 0x100;
+        const TRANS = 0x200;
+        const INTERPOSE = 0x400;
+        const NODEFLIB = 0x800;
+        const NODUMP = 0x1000;
+        const CONFALT = 0x2000;
+        const ENDFILTEE = 0x4000;
+        const DISPRELDNE = 0x8000;
+        const DISPRELPND = 0x10000;
+        const NODIRECT = 0x20000;
+        const IGNMULDEF = 0x40000;
+        const NOKSYMS = 0x80000;
+        const NOHDR = 0x100000;
+        const EDITED = 0x200000;
+        const NORELOC = 0x400000;
+        const SYMINTPOSE = 0x800000;
+        const GLOBAUDIT = 0x1000000;
+        const SINGLETON = 0x2000000;
+        const STUB = 0x4000000;
+        const PIE = 0x8000000;
+        const KMOD = 0x10000000;
+        const WEAKFILTER = 0x20000000;
+        const NOCOMMON = 0x40000000;
+    }
+}
+
+/// An entry of the dynamic section. Addresses, sizes and counts are kept as raw numbers, string
+/// valued entries are resolved through the dynamic string table (`DT_STRTAB`).
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum DynamicEntry {
+    Null,
+    Needed(String),
+    PltRelSize(u64),
+    PltGot(u64),
+    Hash(u64),
+    StringTable(u64),
+    SymbolTable(u64),
+    Rela(u64),
+    RelaSize(u64),
+    RelaEntrySize(u64),
+    StringTableSize(u64),
+    SymbolEntrySize(u64),
+    Init(u64),
+    Fini(u64),
+    SoName(String),
+    RPath(String),
+    Symbolic,
+    Rel(u64),
+    RelSize(u64),
+    RelEntrySize(u64),
+    PltRel(u64),
+    Debug(u64),
+    TextRel,
+    JmpRel(u64),
+    BindNow,
+    InitArray(u64),
+    FiniArray(u64),
+    InitArraySize(u64),
+    FiniArraySize(u64),
+    RunPath(String),
+    Flags(DynamicFlags),
+    PreInitArray(u64),
+    PreInitArraySize(u64),
+    SymbolTableSectionIndex(u64),
+    RelrSize(u64),
+    Relr(u64),
+    RelrEntrySize(u64),
+    GnuPrelinked(u64),
+    GnuConflictSize(u64),
+    GnuLibListSize(u64),
+    Checksum(u64),
+    PltPadSize(u64),
+    MoveEntrySize(u64),
+    MoveSize(u64),
+    Feature1(u64),
+    PosFlag1(u64),
+    SymInfoSize(u64),
+    SymInfoEntrySize(u64),
+    GnuHash(u64),
+    TlsDescPlt(u64),
+    TlsDescGot(u64),
+    GnuConflict(u64),
+    GnuLibList(u64),
+    Config(String),
+    DepAudit(String),
+    Audit(String),
+    PltPad(u64),
+    MoveTable(u64),
+    SymInfo(u64),
+    VersionSymbol(u64),
+    RelaCount(u64),
+    RelCount(u64),
+    Flags1(DynamicFlags1),
+    VersionDefinition(u64),
+    VersionDefinitionCount(u64),
+    VersionNeed(u64),
+    VersionNeedCount(u64),
+    Auxiliary(String),
+    Filter(String),
+    OsSpecific(u64, u64),
+    ProcessorSpecific(u64, u64),
+}
+
+pub(crate) mod tag {
+    pub const NULL: u64 = 0;
+    pub const NEEDED: u64 = 1;
+    pub const STRTAB: u64 = 5;
+    pub const STRSZ: u64 = 10;
+    pub const SONAME: u64 = 14;
+    pub const RPATH: u64 = 15;
+    pub const RUNPATH: u64 = 29;
+    pub const CONFIG: u64 = 0x6FFFFEFA;
+    pub const DEPAUDIT: u64 = 0x6FFFFEFB;
+    pub const AUDIT: u64 = 0x6FFFFEFC;
+    pub const AUXILIARY: u64 = 0x7FFFFFFD;
+    pub const FILTER: u64 = 0x7FFFFFFF;
+
+    /// Returns whether the value of an entry with the given tag is an offset into the string
+    /// table.
+    pub fn is_string(tag: u64) -> bool {
+        matches!(
+            tag,
+            NEEDED | SONAME | RPATH | RUNPATH | CONFIG | DEPAUDIT | AUDIT | AUXILIARY | FILTER
+        )
+    }
+}
+
+impl DynamicEntry {
+    /// Decodes an entry from its raw tag and value. `strings` is the dynamic string table, it is
+    /// only accessed for string valued entries.
+    pub fn parse(tag: u64, value: u64, strings: &[u8]) -> Result<DynamicEntry> {
+        use DynamicEntry::*;
+        let string = || -> Result<String> {
+            let index = usize::try_from(value).map_err(|_| ParseError::UnterminatedString)?;
+            Ok(parse_string(strings, index)?.to_string())
+        };
+        let entry = match tag {
+            0 => Null,
+            1 => Needed(string()?),
+            2 => PltRelSize(value),
+            3 => PltGot(value),
+            4 => Hash(value),
+            5 => StringTable(value),
+            6 => SymbolTable(value),
+            7 => Rela(value),
+            8 => RelaSize(value),
+            9 => RelaEntrySize(value),
+            10 => StringTableSize(value),
+            11 => SymbolEntrySize(value),
+            12 => Init(value),
+            13 => Fini(value),
+            14 => SoName(string()?),
+            15 => RPath(string()?),
+            16 => Symbolic,
+            17 => Rel(value),
+            18 => RelSize(value),
+            19 => RelEntrySize(value),
+            20 => PltRel(value),
+            21 => Debug(value),
+            22 => TextRel,
+            23 => JmpRel(value),
+            24 => BindNow,
+            25 => InitArray(value),
+            26 => FiniArray(value),
+            27 => InitArraySize(value),
+            28 => FiniArraySize(value),
+            29 => RunPath(string()?),
+            30 => Flags(DynamicFlags::from_bits_truncate(value)),
+            32 => PreInitArray(value),
+            33 => PreInitArraySize(value),
+            34 => SymbolTableSectionIndex(value),
+            35 => RelrSize(value),
+            36 => Relr(value),
+            37 => RelrEntrySize(value),
+            0x6FFFFDF5 => GnuPrelinked(value),
+            0x6FFFFDF6 => GnuConflictSize(value),
+            0x6FFFFDF7 => GnuLibListSize(value),
+            0x6FFFFDF8 => Checksum(value),
+            0x6FFFFDF9 => PltPadSize(value),
+            0x6FFFFDFA => MoveEntrySize(value),
+            0x6FFFFDFB => MoveSize(value),
+            0x6FFFFDFC => Feature1(value),
+            0x6FFFFDFD => PosFlag1(value),
+            0x6FFFFDFE => SymInfoSize(value),
+            0x6FFFFDFF => SymInfoEntrySize(value),
+            0x6FFFFEF5 => GnuHash(value),
+            0x6FFFFEF6 => TlsDescPlt(value),
+            0x6FFFFEF7 => TlsDescGot(value),
+            0x6FFFFEF8 => GnuConflict(value),
+            0x6FFFFEF9 => GnuLibList(value),
+            0x6FFFFEFA => Config(string()?),
+            0x6FFFFEFB => DepAudit(string()?),
+            0x6FFFFEFC => Audit(string()?),
+            0x6FFFFEFD => PltPad(value),
+            0x6FFFFEFE => MoveTable(value),
+            0x6FFFFEFF => SymInfo(value),
+            0x6FFFFFF0 => VersionSymbol(value),
+            0x6FFFFFF9 => RelaCount(value),
+            0x6FFFFFFA => RelCount(value),
+            0x6FFFFFFB => Flags1(DynamicFlags1::from_bits_truncate(value)),
+            0x6FFFFFFC => VersionDefinition(value),
+            0x6FFFFFFD => VersionDefinitionCount(value),
+            0x6FFFFFFE => VersionNeed(value),
+            0x6FFFFFFF => VersionNeedCount(value),
+            0x7FFFFFFD => Auxiliary(string()?),
+            0x7FFFFFFF => Filter(string()?),
+            _ if (0x6000000D..=0x6FFFFFFF).contains(&tag) => OsSpecific(tag, value),
+            _ if (0x70000000..=0x7FFFFFFF).contains(&tag) => ProcessorSpecific(tag, value),
+            _ => return Err(ParseError::InvalidDynamicTag(tag)),
+        };
+        Ok(entry)
+    }
+
+    /// Parses the raw `(tag, value)` pairs of a dynamic section up to and excluding the
+    /// terminating `DT_NULL` entry.
+    pub fn parse_raw_entries(
+        bytes: &[u8],
+        word_width: WordWidth,
+        endianness: Endianness,
+    ) -> Result<Vec<(u64, u64)>> {
+        let size = word_width.size();
+        let mut entries = Vec::new();
+        for entry in bytes.chunks_exact(2 * size) {
+            let tag = u64::from(Word::parse_bytes(entry, word_width, endianness)?);
+            let value = u64::from(Word::parse_bytes(&entry[size..], word_width, endianness)?);
+            if tag == tag::NULL {
+                return Ok(entries);
+            }
+            entries.push((tag, value));
+        }
+        Ok(entries)
+    }
+
+    /// The `DT_*` tag of the entry
+    pub fn tag(&self) -> u64 {
+        use DynamicEntry::*;
+        match *self {
+            Null => 0,
+            Needed(_) => 1,
+            PltRelSize(_) => 2,
+            PltGot(_) => 3,
+            Hash(_) => 4,
+            StringTable(_) => 5,
+            SymbolTable(_) => 6,
+            Rela(_) => 7,
+            RelaSize(_) => 8,
+            RelaEntrySize(_) => 9,
+            StringTableSize(_) => 10,
+            SymbolEntrySize(_) => 11,
+            Init(_) => 12,
+            Fini(_) => 13,
+            SoName(_) => 14,
+            RPath(_) => 15,
+            Symbolic => 16,
+            Rel(_) => 17,
+            RelSize(_) => 18,
+            RelEntrySize(_) => 19,
+            PltRel(_) => 20,
+            Debug(_) => 21,
+            TextRel => 22,
+            JmpRel(_) => 23,
+            BindNow => 24,
+            InitArray(_) => 25,
+            FiniArray(_) => 26,
+            InitArraySize(_) => 27,
+            FiniArraySize(_) => 28,
+            RunPath(_) => 29,
+            Flags(_) => 30,
+            PreInitArray(_) => 32,
+            PreInitArraySize(_) => 33,
+            SymbolTableSectionIndex(_) => 34,
+            RelrSize(_) => 35,
+            Relr(_) => 36,
+            RelrEntrySize(_) => 37,
+            GnuPrelinked(_) => 0x6FFFFDF5,
+            GnuConflictSize(_) => 0x6FFFFDF6,
+            GnuLibListSize(_) => 0x6FFFFDF7,
+            Checksum(_) => 0x6FFFFDF8,
+            PltPadSize(_) => 0x6FFFFDF9,
+            MoveEntrySize(_) => 0x6FFFFDFA,
+            MoveSize(_) => 0x6FFFFDFB,
+            Feature1(_) => 0x6FFFFDFC,
+            PosFlag1(_) => 0x6FFFFDFD,
+            SymInfoSize(_) => 0x6FFFFDFE,
+            SymInfoEntrySize(_) => 0x6FFFFDFF,
+            GnuHash(_) => 0x6FFFFEF5,
+            TlsDescPlt(_) => 0x6FFFFEF6,
+            TlsDescGot(_) => 0x6FFFFEF7,
+            GnuConflict(_) => 0x6FFFFEF8,
+            GnuLibList(_) => 0x6FFFFEF9,
+            Config(_) => 0x6FFFFEFA,
+            DepAudit(_) => 0x6FFFFEFB,
+            Audit(_) => 0x6FFFFEFC,
+            PltPad(_) => 0x6FFFFEFD,
+            MoveTable(_) => 0x6FFFFEFE,
+            SymInfo(_) => 0x6FFFFEFF,
+            VersionSymbol(_) => 0x6FFFFFF0,
+            RelaCount(_) => 0x6FFFFFF9,
+            RelCount(_) => 0x6FFFFFFA,
+            Flags1(_) => 0x6FFFFFFB,
+            VersionDefinition(_) => 0x6FFFFFFC,
+            VersionDefinitionCount(_) => 0x6FFFFFFD,
+            VersionNeed(_) => 0x6FFFFFFE,
+            VersionNeedCount(_) => 0x6FFFFFFF,
+            Auxiliary(_) => 0x7FFFFFFD,
+            Filter(_) => 0x7FFFFFFF,
+            OsSpecific(tag, _) | ProcessorSpecific(tag, _) => tag,
+        }
+    }
+
+    /// The name of the tag as printed by `readelf -d`
+    pub fn tag_name(&self) -> &'static str {
+        use DynamicEntry::*;
+        match *self {
+            Null => "NULL",
+            Needed(_) => "NEEDED",
+            PltRelSize(_) => "PLTRELSZ",
+            PltGot(_) => "PLTGOT",
+            Hash(_) => "HASH",
+            StringTable(_) => "STRTAB",
+            SymbolTable(_) => "SYMTAB",
+            Rela(_) => "RELA",
+            RelaSize(_) => "RELASZ",
+            RelaEntrySize(_) => "RELAENT",
+            StringTableSize(_) => "STRSZ",
+            SymbolEntrySize(_) => "SYMENT",
+            Init(_) => "INIT",
+            Fini(_) => "FINI",
+            SoName(_) => "SONAME",
+            RPath(_) => "RPATH",
+            Symbolic => "SYMBOLIC",
+            Rel(_) => "REL",
+            RelSize(_) => "RELSZ",
+            RelEntrySize(_) => "RELENT",
+            PltRel(_) => "PLTREL",
+            Debug(_) => "DEBUG",
+            TextRel => "TEXTREL",
+            JmpRel(_) => "JMPREL",
+            BindNow => "BIND_NOW",
+            InitArray(_) => "INIT_ARRAY",
+            FiniArray(_) => "FINI_ARRAY",
+            InitArraySize(_) => "INIT_ARRAYSZ",
+            FiniArraySize(_) => "FINI_ARRAYSZ",
+            RunPath(_) => "RUNPATH",
+            Flags(_) => "FLAGS",
+            PreInitArray(_) => "PREINIT_ARRAY",
+            PreInitArraySize(_) => "PREINIT_ARRAYSZ",
+            SymbolTableSectionIndex(_) => "SYMTAB_SHNDX",
+            RelrSize(_) => "RELRSZ",
+            Relr(_) => "RELR",
+            RelrEntrySize(_) => "RELRENT",
+            GnuPrelinked(_) => "GNU_PRELINKED",
+            GnuConflictSize(_) => "GNU_CONFLICTSZ",
+            GnuLibListSize(_) => "GNU_LIBLISTSZ",
+            Checksum(_) => "CHECKSUM",
+            PltPadSize(_) => "PLTPADSZ",
+            MoveEntrySize(_) => "MOVEENT",
+            MoveSize(_) => "MOVESZ",
+            Feature1(_) => "FEATURE_1",
+            PosFlag1(_) => "POSFLAG_1",
+            SymInfoSize(_) => "SYMINSZ",
+            SymInfoEntrySize(_) => "SYMINENT",
+            GnuHash(_) => "GNU_HASH",
+            TlsDescPlt(_) => "TLSDESC_PLT",
+            TlsDescGot(_) => "TLSDESC_GOT",
+            GnuConflict(_) => "GNU_CONFLICT",
+            GnuLibList(_) => "GNU_LIBLIST",
+            Config(_) => "CONFIG",
+            DepAudit(_) => "DEPAUDIT",
+            Audit(_) => "AUDIT",
+            PltPad(_) => "PLTPAD",
+            MoveTable(_) => "MOVETAB",
+            SymInfo(_) => "SYMINFO",
+            VersionSymbol(_) => "VERSYM",
+            RelaCount(_) => "RELACOUNT",
+            RelCount(_) => "RELCOUNT",
+            Flags1(_) => "FLAGS_1",
+            VersionDefinition(_) => "VERDEF",
+            VersionDefinitionCount(_) => "VERDEFNUM",
+            VersionNeed(_) => "VERNEED",
+            VersionNeedCount(_) => "VERNEEDNUM",
+            Auxiliary(_) => "AUXILIARY",
+            Filter(_) => "FILTER",
+            OsSpecific(_, _) => "<OS specific>",
+            ProcessorSpecific(_, _) => "<processor specific>",
+        }
+    }
+}
+
+impl Display for DynamicEntry {
+    /// Formats the value of the entry like `readelf -d` does.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        use DynamicEntry::*;
+        match self {
+            Null | Symbolic | TextRel | BindNow => write!(f, "0x0"),
+            Needed(name) => write!(f, "Shared library: [{}]", name),
+            SoName(name) => write!(f, "Library soname: [{}]", name),
+            RPath(path) => write!(f, "Library rpath: [{}]", path),
+            RunPath(path) => write!(f, "Library runpath: [{}]", path),
+            Config(s) | DepAudit(s) | Audit(s) | Auxiliary(s) | Filter(s) => write!(f, "{}", s),
+            PltRel(7) => write!(f, "RELA"),
+            PltRel(17) => write!(f, "REL"),
+            Flags(flags) => write_flag_names(f, &format!("{:?}", flags)),
+            Flags1(flags) => write_flag_names(f, &format!("{:?}", flags)),
+            PltRelSize(size)
+            | RelaSize(size)
+            | RelaEntrySize(size)
+            | StringTableSize(size)
+            | SymbolEntrySize(size)
+            | RelSize(size)
+            | RelEntrySize(size)
+            | InitArraySize(size)
+            | FiniArraySize(size)
+            | PreInitArraySize(size)
+            | RelrSize(size)
+            | RelrEntrySize(size)
+            | GnuConflictSize(size)
+            | GnuLibListSize(size)
+            | PltPadSize(size)
+            | MoveEntrySize(size)
+            | MoveSize(size)
+            | SymInfoSize(size)
+            | SymInfoEntrySize(size) => write!(f, "{} (bytes)", size),
+            RelaCount(count)
+            | RelCount(count)
+            | VersionDefinitionCount(count)
+            | VersionNeedCount(count) => write!(f, "{}", count),
+            OsSpecific(_, value) | ProcessorSpecific(_, value) => write!(f, "{:#x}", value),
+            PltGot(value)
+            | Hash(value)
+            | StringTable(value)
+            | SymbolTable(value)
+            | Rela(value)
+            | Init(value)
+            | Fini(value)
+            | Rel(value)
+            | PltRel(value)
+            | Debug(value)
+            | JmpRel(value)
+            | InitArray(value)
+            | FiniArray(value)
+            | PreInitArray(value)
+            | SymbolTableSectionIndex(value)
+            | Relr(value)
+            | GnuPrelinked(value)
+            | Checksum(value)
+            | Feature1(value)
+            | PosFlag1(value)
+            | GnuHash(value)
+            | TlsDescPlt(value)
+            | TlsDescGot(value)
+            | GnuConflict(value)
+            | GnuLibList(value)
+            | PltPad(value)
+            | MoveTable(value)
+            | SymInfo(value)
+            | VersionSymbol(value)
+            | VersionDefinition(value)
+            | VersionNeed(value) => {
+                write!(f, "{:#x}", value)
+            }
+        }
+    }
+}
+
+/// Turns the `Debug` output of a bitflags value (`A | B`) into a space separated list.
+fn write_flag_names(f: &mut Formatter<'_>, debug: &str) -> std::fmt::Result {
+    let names: Vec<_> = debug.split(" | ").collect();
+    write!(f, "{}", names.join(" "))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::elf::testing::{string_table, Fixture, FixtureSection};
+
+    #[test]
+    fn test_dynamic_entry_strings() {
+        let (strings, indices) = string_table(&["libc.so.6", "libfoo.so.1", "$ORIGIN/../lib"]);
+        let test_data = [
+            (
+                1,
+                indices[0] as u64,
+                DynamicEntry::Needed("libc.so.6".to_string()),
+            ),
+            (
+                14,
+                indices[1] as u64,
+                DynamicEntry::SoName("libfoo.so.1".to_string()),
+            ),
+            (
+                29,
+                indices[2] as u64,
+                DynamicEntry::RunPath("$ORIGIN/../lib".to_string()),
+            ),
+            (
+                15,
+                indices[2] as u64 + 8,
+                DynamicEntry::RPath("../lib".to_string()),
+            ),
+        ];
+        for (tag, value, expected) in test_data.iter() {
+            let entry = DynamicEntry::parse(*tag, *value, &strings);
+            assert_eq!(entry.as_ref(), Ok(expected));
+            assert_eq!(entry.expect("checked").tag(), *tag);
+        }
+        assert_eq!(
+            DynamicEntry::parse(1, 100, &strings),
+            Err(ParseError::UnterminatedString)
+        );
+    }
+
+    #[test]
+    fn test_dynamic_entry_values() {
+        use DynamicEntry::*;
+        let test_data = [
+            (0x0, 0x0, Null),
+            (0x5, 0x4003B0, StringTable(0x4003B0)),
+            (0x18, 0x0, BindNow),
+            (
+                0x1E,
+                0x8 | 0x2,
+                Flags(DynamicFlags::BIND_NOW | DynamicFlags::SYMBOLIC),
+            ),
+            (
+                0x6FFFFFFB,
+                0x08000001,
+                Flags1(DynamicFlags1::NOW | DynamicFlags1::PIE),
+            ),
+            (0x6FFFFEF5, 0x400308, GnuHash(0x400308)),
+            (0x6FFFFFFF, 2, VersionNeedCount(2)),
+            (0x6000000D, 7, OsSpecific(0x6000000D, 7)),
+            (0x70000001, 7, ProcessorSpecific(0x70000001, 7)),
+        ];
+        for (tag, value, expected) in test_data.iter() {
+            let entry = DynamicEntry::parse(*tag, *value, &[]);
+            assert_eq!(entry.as_ref(), Ok(expected));
+            assert_eq!(entry.expect("checked").tag(), *tag);
+        }
+        assert_eq!(
+            DynamicEntry::parse(38, 0, &[]),
+            Err(ParseError::InvalidDynamicTag(38))
+        );
+    }
+
+    #[test]
+    fn test_dynamic_entry_display() {
+        let flags = DynamicEntry::Flags1(DynamicFlags1::NOW | DynamicFlags1::PIE);
+        assert_eq!(flags.to_string(), "NOW PIE");
+        assert_eq!(DynamicEntry::RelaSize(0x30).to_string(), "48 (bytes)");
+        assert_eq!(
+            DynamicEntry::Needed("libc.so.6".to_string()).to_string(),
+            "Shared library: [libc.so.6]"
+        );
+    }
+
+    #[test]
+    fn test_dynamic_section() {
+        for &word_width in [WordWidth::Width32, WordWidth::Width64].iter() {
+            for &endianness in [Endianness::Little, Endianness::Big].iter() {
+                let mut fixture = Fixture::new(word_width, endianness);
+                let (strings, indices) = string_table(&["libm.so.6", "libplugin.so"]);
+                let strings_len = strings.len() as u64;
+                let dynstr = fixture.section(FixtureSection::new(".dynstr", 3, strings).flags(0x2));
+                let dynstr_address = fixture.address_of(dynstr);
+                let mut w = fixture.writer();
+                w.word(1).word(indices[0] as u64);
+                w.word(14).word(indices[1] as u64);
+                w.word(5).word(dynstr_address);
+                w.word(10).word(strings_len);
+                w.word(0).word(0);
+                w.word(0).word(0);
+                let dynamic = fixture.section(
+                    FixtureSection::new(".dynamic", 6, w.bytes)
+                        .flags(0x3)
+                        .link(dynstr as u32)
+                        .align(8),
+                );
+                fixture.segment(1, 0x6, dynstr..dynamic + 1, 0x1000);
+                fixture.segment(2, 0x6, dynamic..dynamic + 1, 8);
+                let bytes = fixture.build();
+                let elf = ElfFile::parse(&bytes).expect("valid ELF");
+                let entries = elf
+                    .dynamic_entries()
+                    .expect("valid dynamic section")
+                    .expect("has a dynamic section");
+                assert_eq!(
+                    entries,
+                    [
+                        DynamicEntry::Needed("libm.so.6".to_string()),
+                        DynamicEntry::SoName("libplugin.so".to_string()),
+                        DynamicEntry::StringTable(dynstr_address),
+                        DynamicEntry::StringTableSize(strings_len),
+                    ]
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_no_dynamic_section() {
+        let bytes = crate::elf::metadata::test::minimal_elf_64();
+        let elf = ElfFile::parse(&bytes).expect("valid ELF");
+        assert_eq!(elf.dynamic_entries(), Ok(None));
+    }
+}
@@ -0,0 +1,580 @@
+use std::io::Cursor;
+
+use super::dynamic::tag;
+use super::*;
+
+/// The read and write permissions of a segment in its `p_flags` field
+const SEGMENT_READ: u32 = 0x4;
+const SEGMENT_WRITE: u32 = 0x2;
+
+/// The largest gap between the end of the file and the new segment for small files. The gap is
+/// left for the memory image of the other segments, beyond this size their headers are more
+/// likely bogus than worth padding the file for.
+const MAX_SEGMENT_GAP: u64 = 1 << 24;
+
+/// Changes the program interpreter and the dynamic section of executables and shared objects,
+/// similar to `patchelf`.
+///
+/// Strings which are already part of the dynamic string table are reused. Other strings are
+/// appended to a copy of the table, and the dynamic section is copied as well when it has no
+/// unused `DT_NULL` entries left for added entries. The copies are placed in a new `Load`
+/// segment at the end of the file together with the program header table, which has to grow
+/// for the new segment. The interpreter is overwritten in place when the new path fits and
+/// moved to the new segment otherwise. The affected program and section headers are updated,
+/// all other contents keep their offsets and addresses.
+///
+/// ```no_run
+/// use elfreader::elf::ElfEditor;
+///
+/// let data = std::fs::read("app").unwrap();
+/// let edited = ElfEditor::new()
+///     .set_interpreter("/opt/bundle/lib/ld-linux-x86-64.so.2")
+///     .set_rpath("$ORIGIN/../lib")
+///     .apply(&data)
+///     .unwrap();
+/// std::fs::write("app", edited).unwrap();
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ElfEditor {
+    interpreter: Option<String>,
+    rpath: Option<String>,
+    soname: Option<String>,
+    added_needed: Vec<String>,
+    removed_needed: Vec<String>,
+}
+
+/// The state of an edit in progress
+struct Edit {
+    word_width: WordWidth,
+    endianness: Endianness,
+    data: Vec<u8>,
+    pheaders: Vec<ProgramHeader>,
+    sheaders: Vec<SectionHeader>,
+    /// The offset and address of the new segment, if the file has a `Load` segment
+    segment_start: Option<(u64, u64)>,
+    segment: Vec<u8>,
+    writable: bool,
+}
+
+/// The dynamic string table followed by the strings to be added
+struct DynamicStrings<'data> {
+    table: &'data [u8],
+    added: Vec<u8>,
+}
+
+impl ElfEditor {
+    pub fn new() -> ElfEditor {
+        ElfEditor::default()
+    }
+
+    /// Sets the path of the program interpreter in the `Interp` segment
+    pub fn set_interpreter(&mut self, interpreter: &str) -> &mut ElfEditor {
+        self.interpreter = Some(interpreter.to_string());
+        self
+    }
+
+    /// Sets the library search path. An existing `DT_RUNPATH` entry is preferred over an
+    /// existing `DT_RPATH` entry, a `DT_RUNPATH` entry is added if there is neither.
+    pub fn set_rpath(&mut self, rpath: &str) -> &mut ElfEditor {
+        self.rpath = Some(rpath.to_string());
+        self
+    }
+
+    /// Sets or adds the `DT_SONAME` entry
+    pub fn set_soname(&mut self, soname: &str) -> &mut ElfEditor {
+        self.soname = Some(soname.to_string());
+        self
+    }
+
+    /// Adds a `DT_NEEDED` entry in front of the existing ones, unless the library is already
+    /// needed
+    pub fn add_needed(&mut self, library: &str) -> &mut ElfEditor {
+        self.added_needed.push(library.to_string());
+        self
+    }
+
+    /// Removes all `DT_NEEDED` entries of the library
+    pub fn remove_needed(&mut self, library: &str) -> &mut ElfEditor {
+        self.removed_needed.push(library.to_string());
+        self
+    }
+
+    /// Applies the changes to the file contents in `data` and returns the edited file.
+    pub fn apply(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let elf = ElfFile::parse(data)?;
+        let pheaders = elf.program_headers()?.collect::<Result<Vec<_>>>()?;
+        let sheaders = elf.section_headers()?.collect::<Result<Vec<_>>>()?;
+        let mut edit = Edit {
+            word_width: elf.header().word_width(),
+            endianness: elf.header().endianness(),
+            data: data.to_vec(),
+            segment_start: Edit::segment_start(&pheaders, data.len() as u64),
+            pheaders,
+            sheaders,
+            segment: Vec::new(),
+            writable: false,
+        };
+        self.edit_dynamic(&elf, &mut edit)?;
+        if let Some(interpreter) = &self.interpreter {
+            ElfEditor::edit_interpreter(&elf, &mut edit, interpreter)?;
+        }
+        edit.finish(elf.header().clone())
+    }
+
+    fn edits_dynamic(&self) -> bool {
+        self.rpath.is_some()
+            || self.soname.is_some()
+            || !self.added_needed.is_empty()
+            || !self.removed_needed.is_empty()
+    }
+
+    fn edit_dynamic(&self, elf: &ElfFile, edit: &mut Edit) -> Result<()> {
+        if !self.edits_dynamic() {
+            return Ok(());
+        }
+        let index = edit
+            .pheaders
+            .iter()
+            .position(|pheader| pheader.typ() == ProgramHeaderSegmentType::Dynamic)
+            .ok_or(ParseError::NoDynamicSection)?;
+        let dynamic = edit.pheaders[index].clone();
+        let dynamic_data = elf.segment_data(&dynamic)?;
+        let mut entries =
+            DynamicEntry::parse_raw_entries(dynamic_data, edit.word_width, edit.endianness)?;
+        let find = |entries: &[(u64, u64)], wanted: u64| {
+            entries
+                .iter()
+                .find(|(tag, _)| *tag == wanted)
+                .map(|(_, value)| *value)
+        };
+        let (strings_address, strings_size) =
+            match (find(&entries, tag::STRTAB), find(&entries, tag::STRSZ)) {
+                (Some(address), Some(size)) => (address, size),
+                _ => return Err(ParseError::NoDynamicSection),
+            };
+        let table = elf.data_at_address(strings_address)?;
+        let mut strings = DynamicStrings {
+            table: table
+                .get(..strings_size as usize)
+                .ok_or(ParseError::InvalidDataRange(strings_address, strings_size))?,
+            added: Vec::new(),
+        };
+
+        let mut needed = Vec::new();
+        for (tag, value) in entries.iter() {
+            if *tag == tag::NEEDED {
+                needed.push(parse_string(strings.table, *value as usize)?);
+            }
+        }
+        let mut kept = needed.iter();
+        let removed = |name: &str| self.removed_needed.iter().any(|removed| removed == name);
+        entries.retain(|(tag, _)| {
+            *tag != tag::NEEDED || !matches!(kept.next(), Some(name) if removed(name))
+        });
+        if let Some(soname) = &self.soname {
+            let value = strings.offset(soname);
+            set_entry(&mut entries, &[tag::SONAME], tag::SONAME, value);
+        }
+        if let Some(rpath) = &self.rpath {
+            let value = strings.offset(rpath);
+            set_entry(
+                &mut entries,
+                &[tag::RUNPATH, tag::RPATH],
+                tag::RUNPATH,
+                value,
+            );
+        }
+        let mut position = 0;
+        for library in self.added_needed.iter() {
+            if needed.contains(&library.as_str()) {
+                continue;
+            }
+            let value = strings.offset(library);
+            entries.insert(position, (tag::NEEDED, value));
+            needed.push(library);
+            position += 1;
+        }
+
+        if !strings.added.is_empty() {
+            let mut table = strings.table.to_vec();
+            table.extend_from_slice(&strings.added);
+            let (offset, address) = edit.append(&table, 1)?;
+            set_entry(&mut entries, &[tag::STRTAB], tag::STRTAB, address);
+            set_entry(&mut entries, &[tag::STRSZ], tag::STRSZ, table.len() as u64);
+            edit.move_section(
+                SectionHeaderType::StringTable,
+                strings_address,
+                offset,
+                address,
+                table.len() as u64,
+            );
+        }
+
+        let mut bytes = Vec::new();
+        for (tag, value) in entries.iter().chain([(tag::NULL, 0)].iter()) {
+            Word::new(edit.word_width, *tag).write_bytes(
+                &mut bytes,
+                edit.word_width,
+                edit.endianness,
+            );
+            Word::new(edit.word_width, *value).write_bytes(
+                &mut bytes,
+                edit.word_width,
+                edit.endianness,
+            );
+        }
+        let start = u64::from(dynamic.offset()) as usize;
+        if bytes.len() <= dynamic_data.len() {
+            // the unused entries are filled with `DT_NULL`
+            bytes.resize(dynamic_data.len(), 0);
+            edit.data[start..start + bytes.len()].copy_from_slice(&bytes);
+            return Ok(());
+        }
+        let (offset, address) = edit.append(&bytes, edit.word_width.size() as u64)?;
+        // the dynamic loader writes to `DT_DEBUG`
+        edit.writable = true;
+        edit.pheaders[index] = edit.moved(&dynamic, offset, address, bytes.len() as u64);
+        edit.move_section(
+            SectionHeaderType::Dynamic,
+            u64::from(dynamic.vaddress()),
+            offset,
+            address,
+            bytes.len() as u64,
+        );
+        Ok(())
+    }
+
+    fn edit_interpreter(elf: &ElfFile, edit: &mut Edit, interpreter: &str) -> Result<()> {
+        let index = edit
+            .pheaders
+            .iter()
+            .position(|pheader| pheader.typ() == ProgramHeaderSegmentType::Interp)
+            .ok_or(ParseError::NoInterpreter)?;
+        let interp = edit.pheaders[index].clone();
+        let old = elf.segment_data(&interp)?;
+        let mut path = interpreter.as_bytes().to_vec();
+        path.push(0);
+        if path.len() <= old.len() {
+            let start = u64::from(interp.offset()) as usize;
+            path.resize(old.len(), 0);
+            edit.data[start..start + path.len()].copy_from_slice(&path);
+            return Ok(());
+        }
+        let (offset, address) = edit.append(&path, 1)?;
+        edit.pheaders[index] = edit.moved(&interp, offset, address, path.len() as u64);
+        edit.move_section(
+            SectionHeaderType::ProgramBits,
+            u64::from(interp.vaddress()),
+            offset,
+            address,
+            path.len() as u64,
+        );
+        Ok(())
+    }
+}
+
+impl Edit {
+    /// Finds the place of the new segment after the end of the file. Its address keeps the
+    /// distance to the offset of the first `Load` segment, so that the program header table is
+    /// found by loaders computing its address from the first segment. Returns `None` if the
+    /// segment would be too far behind the end of the file, see [`MAX_SEGMENT_GAP`].
+    fn segment_start(pheaders: &[ProgramHeader], file_size: u64) -> Option<(u64, u64)> {
+        let loads: Vec<_> = pheaders
+            .iter()
+            .filter(|pheader| pheader.typ() == ProgramHeaderSegmentType::Load)
+            .collect();
+        let first = loads.first()?;
+        let base = u64::from(first.vaddress()).wrapping_sub(u64::from(first.offset()));
+        let page_size = loads
+            .iter()
+            .map(|pheader| u64::from(pheader.alignment()))
+            .max()
+            .filter(|align| align.is_power_of_two())
+            .unwrap_or(1);
+        let memory_end = loads
+            .iter()
+            .map(|pheader| u64::from(pheader.vaddress()).saturating_add(pheader.memsize().into()))
+            .max()?;
+        let offset =
+            align_up(file_size, page_size).max(align_up(memory_end.wrapping_sub(base), page_size));
+        if offset.saturating_sub(file_size) > MAX_SEGMENT_GAP.max(file_size) {
+            return None;
+        }
+        Some((offset, base.wrapping_add(offset)))
+    }
+
+    /// Appends `bytes` to the new segment and returns their offset and address
+    fn append(&mut self, bytes: &[u8], align: u64) -> Result<(u64, u64)> {
+        let (offset, address) = self
+            .segment_start
+            .ok_or(ParseError::InvalidSegmentLayout(self.pheaders.len()))?;
+        let start = align_up(self.segment.len() as u64, align);
+        self.segment.resize(start as usize, 0);
+        self.segment.extend_from_slice(bytes);
+        match (offset.checked_add(start), address.checked_add(start)) {
+            (Some(offset), Some(address)) => Ok((offset, address)),
+            _ => Err(ParseError::InvalidSegmentLayout(self.pheaders.len())),
+        }
+    }
+
+    fn moved(
+        &self,
+        pheader: &ProgramHeader,
+        offset: u64,
+        address: u64,
+        size: u64,
+    ) -> ProgramHeader {
+        pheader
+            .clone()
+            .with_offset(Word::new(self.word_width, offset))
+            .with_vaddress(Word::new(self.word_width, address))
+            .with_paddress(Word::new(self.word_width, address))
+            .with_filesize(Word::new(self.word_width, size))
+            .with_memsize(Word::new(self.word_width, size))
+    }
+
+    /// Points the allocated section of the given type at `old_address` to its new place
+    fn move_section(
+        &mut self,
+        typ: SectionHeaderType,
+        old_address: u64,
+        offset: u64,
+        address: u64,
+        size: u64,
+    ) {
+        let word_width = self.word_width;
+        for sheader in self.sheaders.iter_mut() {
+            if sheader.typ() == typ
+                && sheader.flags().contains(SectionHeaderFlags::ALLOC)
+                && u64::from(sheader.address()) == old_address
+            {
+                *sheader = sheader
+                    .clone()
+                    .with_offset(Word::new(word_width, offset))
+                    .with_address(Word::new(word_width, address))
+                    .with_size(Word::new(word_width, size));
+            }
+        }
+    }
+
+    /// Adds the new segment with the moved program header table and serializes the file
+    fn finish(mut self, mut header: Header) -> Result<Vec<u8>> {
+        if !self.segment.is_empty() {
+            let (offset, address) = self
+                .segment_start
+                .ok_or(ParseError::InvalidSegmentLayout(self.pheaders.len()))?;
+            let mut flags = SEGMENT_READ;
+            if self.writable {
+                flags |= SEGMENT_WRITE;
+            }
+            let align = self
+                .pheaders
+                .iter()
+                .find(|pheader| pheader.typ() == ProgramHeaderSegmentType::Load)
+                .map_or(Word::new(self.word_width, 1), |pheader| pheader.alignment());
+            // loadable segments have to be sorted by their addresses, the new one is the last
+            let position = self
+                .pheaders
+                .iter()
+                .rposition(|pheader| pheader.typ() == ProgramHeaderSegmentType::Load)
+                .map_or(self.pheaders.len(), |index| index + 1);
+            let zero = Word::zero(self.word_width);
+            self.pheaders.insert(
+                position,
+                ProgramHeader::new(
+                    ProgramHeaderSegmentType::Load,
+                    zero,
+                    zero,
+                    zero,
+                    zero,
+                    zero,
+                    flags,
+                    align,
+                ),
+            );
+            let count = self.pheaders.len();
+            if count >= Header::PN_XNUM as usize {
+                return Err(ParseError::InvalidSegmentLayout(count));
+            }
+            let table_size = count as u64 * u64::from(header.program_header_entry_size());
+            let (table_offset, table_address) =
+                self.append(&vec![0; table_size as usize], self.word_width.size() as u64)?;
+            let size = self.segment.len() as u64;
+            self.pheaders[position] = self.moved(&self.pheaders[position], offset, address, size);
+            for i in 0..count {
+                if self.pheaders[i].typ() == ProgramHeaderSegmentType::HeaderSegment {
+                    self.pheaders[i] =
+                        self.moved(&self.pheaders[i], table_offset, table_address, table_size);
+                }
+            }
+            header = header
+                .with_program_header_start(Word::new(self.word_width, table_offset))
+                .with_program_header_entry_count(count as u16);
+            self.data.resize(offset as usize, 0);
+            self.data.extend_from_slice(&self.segment);
+        }
+        let metadata = Metadata::new(header, self.pheaders, self.sheaders);
+        let mut writer = Cursor::new(Vec::new());
+        metadata
+            .write_to(&self.data, &mut writer)
+            .expect("writing to memory does not fail");
+        Ok(writer.into_inner())
+    }
+}
+
+impl<'data> DynamicStrings<'data> {
+    /// The offset of `string` in the table, it is added if the table doesn't contain it yet
+    fn offset(&mut self, string: &str) -> u64 {
+        let mut wanted = string.as_bytes().to_vec();
+        wanted.push(0);
+        if let Some(offset) = find(self.table, &wanted) {
+            return offset as u64;
+        }
+        if let Some(offset) = find(&self.added, &wanted) {
+            return (self.table.len() + offset) as u64;
+        }
+        let offset = self.table.len() + self.added.len();
+        self.added.extend_from_slice(&wanted);
+        offset as u64
+    }
+}
+
+/// Replaces the value of the first entry with one of the `tags`, or adds an entry with `tag`
+/// in front of the terminating `DT_NULL` entry.
+fn set_entry(entries: &mut Vec<(u64, u64)>, tags: &[u64], tag: u64, value: u64) {
+    for wanted in tags {
+        if let Some(entry) = entries.iter_mut().find(|(tag, _)| tag == wanted) {
+            entry.1 = value;
+            return;
+        }
+    }
+    entries.push((tag, value));
+}
+
+/// Finds `needle` in `haystack`, strings may share their tail with longer strings
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+    use crate::elf::testing::{string_table, Fixture, FixtureSection};
+
+    /// A dynamically linked executable without spare dynamic entries
+    pub(crate) fn dynamic_fixture(word_width: WordWidth, endianness: Endianness) -> Vec<u8> {
+        let mut fixture = Fixture::new(word_width, endianness);
+        let interp = fixture
+            .section(FixtureSection::new(".interp", 1, b"/lib/ld.so.1\0".to_vec()).flags(0x2));
+        let (strings, indices) = string_table(&["libm.so.6", "libc.so.6"]);
+        let strings_len = strings.len() as u64;
+        let dynstr = fixture.section(FixtureSection::new(".dynstr", 3, strings).flags(0x2));
+        let dynstr_address = fixture.address_of(dynstr);
+        let mut w = fixture.writer();
+        w.word(1).word(indices[0] as u64);
+        w.word(1).word(indices[1] as u64);
+        w.word(5).word(dynstr_address);
+        w.word(10).word(strings_len);
+        w.word(0).word(0);
+        let dynamic = fixture.section(
+            FixtureSection::new(".dynamic", 6, w.bytes)
+                .flags(0x3)
+                .link(dynstr as u32)
+                .align(8),
+        );
+        fixture.segment(3, 0x4, interp..interp + 1, 1);
+        fixture.segment(1, 0x6, interp..dynamic + 1, 0x1000);
+        fixture.segment(2, 0x6, dynamic..dynamic + 1, 8);
+        fixture.build()
+    }
+
+    fn needed(elf: &ElfFile) -> Vec<String> {
+        elf.dynamic_entries()
+            .unwrap()
+            .unwrap()
+            .into_iter()
+            .filter_map(|entry| match entry {
+                DynamicEntry::Needed(name) => Some(name),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_edit_in_place() {
+        let data = dynamic_fixture(WordWidth::Width64, Endianness::Little);
+        let edited = ElfEditor::new()
+            .set_interpreter("/lib/ld.so")
+            .remove_needed("libm.so.6")
+            .set_soname("libc.so.6")
+            .apply(&data)
+            .unwrap();
+        assert_eq!(edited.len(), data.len());
+        let elf = ElfFile::parse(&edited).unwrap();
+        assert_eq!(elf.interpreter(), Ok(Some("/lib/ld.so")));
+        assert_eq!(elf.program_headers().unwrap().len(), 3);
+        let entries = elf.dynamic_entries().unwrap().unwrap();
+        assert_eq!(entries[0], DynamicEntry::Needed("libc.so.6".to_string()));
+        assert!(entries.contains(&DynamicEntry::SoName("libc.so.6".to_string())));
+    }
+
+    #[test]
+    fn test_edit_with_new_segment() {
+        for &word_width in [WordWidth::Width32, WordWidth::Width64].iter() {
+            for &endianness in [Endianness::Little, Endianness::Big].iter() {
+                let data = dynamic_fixture(word_width, endianness);
+                let interpreter = "/opt/bundle/lib/ld-linux.so.2";
+                let edited = ElfEditor::new()
+                    .set_interpreter(interpreter)
+                    .set_rpath("$ORIGIN/../lib")
+                    .add_needed("libbundle.so")
+                    .add_needed("libm.so.6")
+                    .apply(&data)
+                    .unwrap();
+                // the original contents stay in place
+                assert_eq!(edited[0x100..0x200], data[0x100..0x200]);
+
+                let elf = ElfFile::parse(&edited).unwrap();
+                assert_eq!(elf.interpreter(), Ok(Some(interpreter)));
+                assert_eq!(needed(&elf), ["libbundle.so", "libm.so.6", "libc.so.6"]);
+                let entries = elf.dynamic_entries().unwrap().unwrap();
+                assert!(entries.contains(&DynamicEntry::RunPath("$ORIGIN/../lib".to_string())));
+
+                let metadata = Metadata::parse_bytes(&edited).unwrap();
+                let pheaders = metadata.program_headers();
+                assert_eq!(pheaders.len(), 4);
+                let load = &pheaders[2];
+                assert_eq!(load.typ(), ProgramHeaderSegmentType::Load);
+                assert_eq!(load.flags(), SEGMENT_READ | SEGMENT_WRITE);
+                let start = u64::from(load.offset());
+                let end = start + u64::from(load.filesize());
+                assert_eq!(end, edited.len() as u64);
+                let phdr_start = u64::from(metadata.header().program_header_start());
+                assert!(phdr_start >= start && phdr_start < end);
+                for pheader in [&pheaders[0], &pheaders[3]].iter() {
+                    assert!(u64::from(pheader.offset()) >= start);
+                }
+                for sheader in metadata.section_headers()[1..4].iter() {
+                    assert!(u64::from(sheader.offset()) >= start, "{}", sheader.name());
+                    assert_eq!(
+                        u64::from(sheader.address()) - u64::from(load.vaddress()),
+                        u64::from(sheader.offset()) - start
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_edit_errors() {
+        let data = crate::elf::metadata::test::minimal_elf_64();
+        let mut editor = ElfEditor::new();
+        editor.add_needed("libm.so.6");
+        assert_eq!(editor.apply(&data), Err(ParseError::NoDynamicSection));
+        let mut editor = ElfEditor::new();
+        editor.set_interpreter("/lib/ld.so");
+        assert_eq!(editor.apply(&data), Err(ParseError::NoInterpreter));
+    }
+}
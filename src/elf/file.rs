@@ -0,0 +1,1191 @@
+use std::borrow::Cow;
+use std::convert::TryFrom;
+
+use super::*;
+
+/// A zero-copy view of an ELF file.
+///
+/// `ElfFile` borrows the complete file contents, typically a memory mapped file, and only parses
+/// the ELF header up front. Program headers, section headers and the contents of sections and
+/// segments are decoded on demand and section/segment contents are returned as slices into the
+/// borrowed buffer, so nothing is copied.
+///
+/// ```no_run
+/// use elfreader::elf::ElfFile;
+///
+/// let file = std::fs::File::open("a.out").unwrap();
+/// let mmap = unsafe { memmap2::Mmap::map(&file) }.unwrap();
+/// let elf = ElfFile::parse(&mmap).unwrap();
+/// for section in elf.section_headers().unwrap() {
+///     let section = section.unwrap();
+///     println!("{}: {} bytes", section.name(), elf.section_data(&section).unwrap().len());
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ElfFile<'data> {
+    data: &'data [u8],
+    header: Header,
+    lenient: bool,
+}
+
+/// All section headers of an [`ElfFile`], parsed once by the functions that handle every section
+/// of a type, so the links between the sections are resolved without parsing them again.
+struct ParsedSections {
+    sheaders: Vec<SectionHeader>,
+    /// The indices of the `SectionIndices` sections
+    section_indices: Vec<usize>,
+}
+
+/// Lazily parses the program headers of an [`ElfFile`].
+#[derive(Debug, Clone)]
+pub struct ProgramHeaders<'data> {
+    table: &'data [u8],
+    /// The file offset and the index of the next entry
+    offset: u64,
+    index: usize,
+    entry_size: usize,
+    remaining: usize,
+    word_width: WordWidth,
+    endianness: Endianness,
+    lenient: bool,
+}
+
+/// Lazily parses the section headers of an [`ElfFile`] and resolves their names.
+#[derive(Debug, Clone)]
+pub struct SectionHeaders<'data> {
+    table: &'data [u8],
+    names: &'data [u8],
+    /// The file offset and the index of the next entry
+    offset: u64,
+    index: usize,
+    entry_size: usize,
+    remaining: usize,
+    word_width: WordWidth,
+    endianness: Endianness,
+    lenient: bool,
+}
+
+impl<'data> ElfFile<'data> {
+    pub fn parse(data: &'data [u8]) -> Result<ElfFile<'data>> {
+        ElfFile::parse_with_leniency(data, false)
+    }
+
+    /// Parses the file like [`ElfFile::parse`], but keeps unknown values in the header, the
+    /// program headers and the section headers instead of failing on them, and names sections
+    /// whose name can't be resolved with an empty string. The violations are not reported, use
+    /// [`Metadata::parse_bytes_lenient`] to list them.
+    pub fn parse_lenient(data: &'data [u8]) -> Result<ElfFile<'data>> {
+        ElfFile::parse_with_leniency(data, true)
+    }
+
+    fn parse_with_leniency(data: &'data [u8], lenient: bool) -> Result<ElfFile<'data>> {
+        let header = parse_tolerant(lenient, 0, Structure::Header, |warnings| {
+            Header::parse_bytes_with_warnings(&data[..data.len().min(64)], warnings)
+        })?;
+        Ok(ElfFile {
+            data,
+            header,
+            lenient,
+        })
+    }
+
+    /// Whether `data` starts with the ELF magic.
+    pub fn is_elf(data: &[u8]) -> bool {
+        data.len() >= 4 && Header::check_magic(data).is_ok()
+    }
+
+    pub fn data(&self) -> &'data [u8] {
+        self.data
+    }
+
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+
+    /// The effective number of program and section headers and the index of the section name
+    /// table, taking extended numbering into account.
+    pub fn counts(&self) -> Result<HeaderCounts> {
+        let header = self.header();
+        if !header.uses_extended_numbering() || u64::from(header.section_header_start()) == 0 {
+            return header
+                .counts(None)
+                .map_err(|err| err.at(0, Structure::Header, None));
+        }
+        // the counts are stored in the first section header
+        let offset = u64::from(header.section_header_start());
+        let locate = |err: ParseError| err.at(offset, Structure::SectionHeader(0), None);
+        let first_section = self
+            .slice(offset, header.section_header_entry_size() as u64)
+            .map_err(locate)?;
+        let first_section = parse_tolerant(
+            self.lenient,
+            offset,
+            Structure::SectionHeader(0),
+            |warnings| {
+                UnnamedSectionHeader::parse_bytes_with_warnings(
+                    first_section,
+                    header.word_width(),
+                    header.endianness(),
+                    warnings,
+                )
+            },
+        )?;
+        header.counts(Some(&first_section)).map_err(locate)
+    }
+
+    pub fn program_headers(&self) -> Result<ProgramHeaders<'data>> {
+        let header = self.header();
+        let entry_size = header.program_header_entry_size() as usize;
+        let count = self.counts()?.program_headers;
+        let offset = u64::from(header.program_header_start());
+        let table = self
+            .slice(offset, (entry_size as u64).saturating_mul(count as u64))
+            .map_err(|err| err.at(offset, Structure::ProgramHeaderTable, None))?;
+        Ok(ProgramHeaders {
+            table,
+            offset,
+            index: 0,
+            entry_size,
+            remaining: count,
+            word_width: header.word_width(),
+            endianness: header.endianness(),
+            lenient: self.lenient,
+        })
+    }
+
+    pub fn section_headers(&self) -> Result<SectionHeaders<'data>> {
+        let header = self.header();
+        let entry_size = header.section_header_entry_size() as usize;
+        let counts = self.counts()?;
+        let count = counts.section_headers;
+        let offset = u64::from(header.section_header_start());
+        let table = self
+            .slice(offset, (entry_size as u64).saturating_mul(count as u64))
+            .map_err(|err| err.at(offset, Structure::SectionHeaderTable, None))?;
+        let mut headers = SectionHeaders {
+            table,
+            names: &[0],
+            offset,
+            index: 0,
+            entry_size,
+            remaining: count,
+            word_width: header.word_width(),
+            endianness: header.endianness(),
+            lenient: self.lenient,
+        };
+        let index = counts.section_names_index;
+        if let Some(names_header) = headers.unnamed(index) {
+            let names_header = names_header?;
+            if names_header.typ() != SectionHeaderType::StringTable && !self.lenient {
+                let type_offset = headers.offset_of(index) + 4;
+                return Err(
+                    ParseError::InvalidSectionNameTableType(names_header.typ()).at(
+                        type_offset,
+                        Structure::SectionHeader(index),
+                        Some("sh_type"),
+                    ),
+                );
+            }
+            let names_offset = u64::from(names_header.offset());
+            headers.names = self
+                .slice(names_offset, u64::from(names_header.size()))
+                .map_err(|err| err.at(names_offset, Structure::Section(index), None))?;
+        }
+        Ok(headers)
+    }
+
+    /// Parses the section header with the given index without parsing the ones before it.
+    pub fn section_header(&self, index: usize) -> Result<Option<SectionHeader>> {
+        self.section_headers()?.get(index).transpose()
+    }
+
+    pub fn section_header_by_name(&self, name: &str) -> Result<Option<SectionHeader>> {
+        for sheader in self.section_headers()? {
+            let sheader = sheader?;
+            if sheader.name() == name {
+                return Ok(Some(sheader));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Returns the section with the given index, which is referenced by the `link` field of
+    /// another section. Only the referenced section header is parsed.
+    pub fn linked_section(&self, link: u32) -> Result<SectionHeader> {
+        self.section_headers()?
+            .get(link as usize)
+            .unwrap_or(Err(ParseError::InvalidSectionLink(link)))
+    }
+
+    /// Looks up a linked section in `parsed` if the section headers were already parsed
+    fn section_in(&self, parsed: Option<&ParsedSections>, index: u32) -> Result<SectionHeader> {
+        match parsed {
+            Some(parsed) => indexed_section(&parsed.sheaders, index),
+            None => self.linked_section(index),
+        }
+    }
+
+    fn parsed_sections(&self) -> Result<ParsedSections> {
+        let sheaders = self.section_headers()?.collect::<Result<Vec<_>>>()?;
+        let section_indices = sheaders
+            .iter()
+            .enumerate()
+            .filter(|(_, sheader)| sheader.typ() == SectionHeaderType::SectionIndices)
+            .map(|(index, _)| index)
+            .collect();
+        Ok(ParsedSections {
+            sheaders,
+            section_indices,
+        })
+    }
+
+    /// Returns the contents of the string table with the given section index.
+    pub fn string_table(&self, index: u32) -> Result<&'data [u8]> {
+        let sheader = self.linked_section(index)?;
+        if sheader.typ() != SectionHeaderType::StringTable {
+            return Err(ParseError::InvalidStringTableType(sheader.typ()));
+        }
+        self.section_data(&sheader)
+    }
+
+    /// Parses the entries of a `SymbolTable` or `DynamicSymbolTable` section. The symbol names are
+    /// looked up in the string table referenced by the section's `link`. Extended section indices
+    /// are resolved through the `SectionIndices` section linked to the symbol table.
+    pub fn symbols(&self, sheader: &SectionHeader) -> Result<Symbols<'data>> {
+        self.symbols_in(sheader, None)
+    }
+
+    /// Parses a symbol table like [`ElfFile::symbols`], looking for its `SectionIndices` section
+    /// in `parsed` if the section headers were already parsed.
+    fn symbols_in(
+        &self,
+        sheader: &SectionHeader,
+        parsed: Option<&ParsedSections>,
+    ) -> Result<Symbols<'data>> {
+        match sheader.typ() {
+            SectionHeaderType::SymbolTable | SectionHeaderType::DynamicSymbolTable => {}
+            typ => return Err(ParseError::InvalidSymbolTableType(typ)),
+        }
+        let table = self.section_data(sheader)?;
+        let names = self.string_table(sheader.link())?;
+        let symbols = Symbols::new(
+            table,
+            names,
+            sheader.entry_size(),
+            self.header.word_width(),
+            self.header.endianness(),
+        );
+        // symbols only use extended section indices in files with 0xFF00 or more sections
+        if self.counts()?.section_headers < 0xFF00 {
+            return Ok(symbols);
+        }
+        let parsed_here;
+        let parsed = match parsed {
+            Some(parsed) => parsed,
+            None => {
+                parsed_here = self.parsed_sections()?;
+                &parsed_here
+            }
+        };
+        match parsed.section_indices_of(sheader) {
+            None => Ok(symbols),
+            Some(section_indices) => {
+                Ok(symbols.with_section_indices(self.section_data(section_indices)?))
+            }
+        }
+    }
+
+    /// Returns the symbols of the `.symtab` section, if there is one.
+    pub fn symbol_table(&self) -> Result<Option<Symbols<'data>>> {
+        self.symbols_of_type(SectionHeaderType::SymbolTable)
+    }
+
+    /// Returns the symbols of the `.dynsym` section, if there is one.
+    pub fn dynamic_symbol_table(&self) -> Result<Option<Symbols<'data>>> {
+        self.symbols_of_type(SectionHeaderType::DynamicSymbolTable)
+    }
+
+    fn symbols_of_type(&self, typ: SectionHeaderType) -> Result<Option<Symbols<'data>>> {
+        match self.section_header_by_type(typ)? {
+            None => Ok(None),
+            Some(sheader) => self.symbols(&sheader).map(Some),
+        }
+    }
+
+    /// Parses a `Hash` or `.gnu.hash` section.
+    pub fn hash_table(&self, sheader: &SectionHeader) -> Result<HashTable<'data>> {
+        let data = self.section_data(sheader)?;
+        match sheader.typ() {
+            SectionHeaderType::Hash => Ok(HashTable::Sysv(SysvHashTable::parse_bytes(
+                data,
+                self.header.endianness(),
+            )?)),
+            SectionHeaderType::OsSpecific(hash::SHT_GNU_HASH) => {
+                Ok(HashTable::Gnu(GnuHashTable::parse_bytes(
+                    data,
+                    self.header.word_width(),
+                    self.header.endianness(),
+                )?))
+            }
+            typ => Err(ParseError::InvalidHashTableType(typ)),
+        }
+    }
+
+    /// Returns the hash tables of the file together with their sections.
+    pub fn hash_tables(&self) -> Result<Vec<(SectionHeader, HashTable<'data>)>> {
+        let mut tables = Vec::new();
+        for sheader in self.section_headers()? {
+            let sheader = sheader?;
+            match sheader.typ() {
+                SectionHeaderType::Hash | SectionHeaderType::OsSpecific(hash::SHT_GNU_HASH) => {
+                    let table = self.hash_table(&sheader)?;
+                    tables.push((sheader, table))
+                }
+                _ => {}
+            }
+        }
+        Ok(tables)
+    }
+
+    /// Looks up a dynamic symbol by name through the hash tables like the dynamic loader does,
+    /// preferring `.gnu.hash` over the SysV `Hash` section. Returns the index of the symbol in
+    /// its symbol table and the symbol itself.
+    pub fn lookup_dynamic_symbol(&self, name: &str) -> Result<Option<(usize, Symbol)>> {
+        let mut tables = self.hash_tables()?;
+        tables.sort_by_key(|(_, table)| match table {
+            HashTable::Gnu(_) => 0,
+            HashTable::Sysv(_) => 1,
+        });
+        match tables.first() {
+            None => Ok(None),
+            Some((sheader, table)) => {
+                let symbols = self.symbols(&self.linked_section(sheader.link())?)?;
+                table.lookup(name, &symbols, self.symbol_versions()?.as_ref())
+            }
+        }
+    }
+
+    /// Parses the GNU symbol versioning sections. Returns `None` if the file has no
+    /// `.gnu.version` section. The version indices are in the order of the dynamic symbol table.
+    pub fn symbol_versions(&self) -> Result<Option<SymbolVersions>> {
+        let versym = match self
+            .section_header_by_type(SectionHeaderType::OsSpecific(version::SHT_GNU_VERSYM))?
+        {
+            None => return Ok(None),
+            Some(versym) => versym,
+        };
+        let endianness = self.header.endianness();
+        let indices = VersionIndex::parse_table(self.section_data(&versym)?, endianness);
+        let definitions = match self
+            .section_header_by_type(SectionHeaderType::OsSpecific(version::SHT_GNU_VERDEF))?
+        {
+            None => Vec::new(),
+            Some(verdef) => VersionDefinition::parse_table(
+                self.section_data(&verdef)?,
+                verdef.info(),
+                self.string_table(verdef.link())?,
+                endianness,
+            )?,
+        };
+        let requirements = match self
+            .section_header_by_type(SectionHeaderType::OsSpecific(version::SHT_GNU_VERNEED))?
+        {
+            None => Vec::new(),
+            Some(verneed) => VersionRequirement::parse_table(
+                self.section_data(&verneed)?,
+                verneed.info(),
+                self.string_table(verneed.link())?,
+                endianness,
+            )?,
+        };
+        Ok(Some(SymbolVersions::new(
+            indices,
+            definitions,
+            requirements,
+        )))
+    }
+
+    fn section_header_by_type(&self, typ: SectionHeaderType) -> Result<Option<SectionHeader>> {
+        for sheader in self.section_headers()? {
+            let sheader = sheader?;
+            if sheader.typ() == typ {
+                return Ok(Some(sheader));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Returns the path of the program interpreter requested by the `Interp` segment.
+    pub fn interpreter(&self) -> Result<Option<&'data str>> {
+        for (i, pheader) in self.program_headers()?.enumerate() {
+            let pheader = pheader?;
+            if pheader.typ() == ProgramHeaderSegmentType::Interp {
+                let locate =
+                    |err: ParseError| err.at(pheader.offset().into(), Structure::Segment(i), None);
+                let data = self.segment_data(&pheader).map_err(locate)?;
+                return ProgramHeader::parse_interpreter(data)
+                    .map(Some)
+                    .map_err(locate);
+            }
+        }
+        Ok(None)
+    }
+
+    /// Parses a `Relocation` or `RelocationWithAddends` section. The symbol table is taken from
+    /// the section's `link` and the section the relocations apply to from its `info` field. Both
+    /// are optional, 0 means the section is not linked.
+    pub fn relocation_table(&self, sheader: &SectionHeader) -> Result<RelocationTable<'data>> {
+        self.relocation_table_in(sheader, None)
+    }
+
+    /// Parses a relocation section like [`ElfFile::relocation_table`], resolving its `link` and
+    /// `info` fields in `parsed` if the section headers were already parsed.
+    fn relocation_table_in(
+        &self,
+        sheader: &SectionHeader,
+        parsed: Option<&ParsedSections>,
+    ) -> Result<RelocationTable<'data>> {
+        let linked_section = |index| self.section_in(parsed, index);
+        let with_addends = match sheader.typ() {
+            SectionHeaderType::Relocation => false,
+            SectionHeaderType::RelocationWithAddends => true,
+            typ => return Err(ParseError::InvalidRelocationTableType(typ)),
+        };
+        let entries = Relocations::new(
+            self.section_data(sheader)?,
+            sheader.entry_size(),
+            with_addends,
+            self.header.arch(),
+            self.header.word_width(),
+            self.header.endianness(),
+        );
+        let symbols = match sheader.link() {
+            0 => None,
+            link => Some(self.symbols_in(&linked_section(link)?, parsed)?),
+        };
+        let target_section = match sheader.info() {
+            0 => None,
+            info => Some(linked_section(info)?),
+        };
+        Ok(RelocationTable::new(
+            sheader.clone(),
+            target_section,
+            symbols,
+            entries,
+        ))
+    }
+
+    /// Returns all relocation sections of the file.
+    pub fn relocation_tables(&self) -> Result<Vec<RelocationTable<'data>>> {
+        // the section headers are parsed once for all the links between them
+        let parsed = self.parsed_sections()?;
+        let mut tables = Vec::new();
+        for sheader in parsed.sheaders.iter() {
+            match sheader.typ() {
+                SectionHeaderType::Relocation | SectionHeaderType::RelocationWithAddends => {
+                    let table = self.relocation_table_in(sheader, Some(&parsed))?;
+                    tables.push(table)
+                }
+                _ => {}
+            }
+        }
+        Ok(tables)
+    }
+
+    /// Parses a `Group` section. The signature is the symbol referenced by the section's `info`
+    /// in the symbol table referenced by its `link`. Groups signed by a section symbol use the
+    /// name of that section instead, like GNU readelf does.
+    pub fn section_group(&self, sheader: &SectionHeader) -> Result<SectionGroup> {
+        self.section_group_in(sheader, None)
+    }
+
+    /// Parses a `Group` section like [`ElfFile::section_group`], resolving the symbol table, the
+    /// signature section and the members in `parsed` if the section headers were already
+    /// parsed.
+    fn section_group_in(
+        &self,
+        sheader: &SectionHeader,
+        parsed: Option<&ParsedSections>,
+    ) -> Result<SectionGroup> {
+        let linked_section = |index| self.section_in(parsed, index);
+        if sheader.typ() != SectionHeaderType::Group {
+            return Err(ParseError::InvalidGroupSectionType(sheader.typ()));
+        }
+        let (flags, indices) =
+            SectionGroup::parse_bytes(self.section_data(sheader)?, self.header.endianness())?;
+        let symbols = self.symbols_in(&linked_section(sheader.link())?, parsed)?;
+        let symbol = symbols
+            .get(sheader.info() as usize)
+            .unwrap_or(Err(ParseError::InvalidSymbolIndex(sheader.info())))?;
+        let signature = match (symbol.typ(), symbol.section_index()) {
+            (SymbolType::Section, SymbolSectionIndex::Index(index)) => {
+                linked_section(index)?.name().to_string()
+            }
+            _ => symbol.name().to_string(),
+        };
+        let members = indices
+            .into_iter()
+            .map(|index| Ok((index, linked_section(index)?)))
+            .collect::<Result<_>>()?;
+        Ok(SectionGroup::new(
+            sheader.clone(),
+            signature,
+            flags,
+            members,
+        ))
+    }
+
+    /// Returns all section groups of the file.
+    pub fn section_groups(&self) -> Result<Vec<SectionGroup>> {
+        // the section headers are parsed once for the signatures and members of all groups
+        let parsed = self.parsed_sections()?;
+        parsed
+            .sheaders
+            .iter()
+            .filter(|sheader| sheader.typ() == SectionHeaderType::Group)
+            .map(|sheader| self.section_group_in(sheader, Some(&parsed)))
+            .collect()
+    }
+
+    /// Iterates over the notes of a `Note` section.
+    pub fn section_notes(&self, sheader: &SectionHeader) -> Result<Notes<'data>> {
+        if sheader.typ() != SectionHeaderType::Note {
+            return Err(ParseError::InvalidNoteSectionType(sheader.typ()));
+        }
+        Ok(Notes::new(
+            self.section_data(sheader)?,
+            sheader.align(),
+            self.header.word_width(),
+            self.header.endianness(),
+        ))
+    }
+
+    /// Iterates over the notes of a `Note` segment.
+    pub fn segment_notes(&self, pheader: &ProgramHeader) -> Result<Notes<'data>> {
+        Ok(Notes::new(
+            self.segment_data(pheader)?,
+            pheader.alignment(),
+            self.header.word_width(),
+            self.header.endianness(),
+        ))
+    }
+
+    /// Returns the notes of the file grouped by the section they are stored in. Files without
+    /// section headers (like core dumps) use the `Note` segments instead, these are named
+    /// `PT_NOTE`.
+    pub fn notes(&self) -> Result<Vec<(String, Vec<Note<'data>>)>> {
+        let mut notes = Vec::new();
+        for sheader in self.section_headers()? {
+            let sheader = sheader?;
+            if sheader.typ() == SectionHeaderType::Note {
+                let section_notes = self.section_notes(&sheader)?.collect::<Result<_>>()?;
+                notes.push((sheader.name().to_string(), section_notes));
+            }
+        }
+        if !notes.is_empty() {
+            return Ok(notes);
+        }
+        for pheader in self.program_headers()? {
+            let pheader = pheader?;
+            if pheader.typ() == ProgramHeaderSegmentType::Note {
+                let segment_notes = self.segment_notes(&pheader)?.collect::<Result<_>>()?;
+                notes.push(("PT_NOTE".to_string(), segment_notes));
+            }
+        }
+        Ok(notes)
+    }
+
+    /// Decodes the notes of the `Note` segments of a core dump: the thread status with the
+    /// registers, the process information, the signal, the auxiliary vector and the mapped files.
+    /// Other notes are skipped.
+    pub fn core_notes(&self) -> Result<Vec<CoreNote<'data>>> {
+        let mut core_notes = Vec::new();
+        for pheader in self.program_headers()? {
+            let pheader = pheader?;
+            if pheader.typ() != ProgramHeaderSegmentType::Note {
+                continue;
+            }
+            for note in self.segment_notes(&pheader)? {
+                if let Some(core_note) = CoreNote::decode(&note?, self.header.arch())? {
+                    core_notes.push(core_note);
+                }
+            }
+        }
+        Ok(core_notes)
+    }
+
+    /// Returns the GNU build-id (`NT_GNU_BUILD_ID`) of the file.
+    pub fn build_id(&self) -> Result<Option<&'data [u8]>> {
+        for (_, notes) in self.notes()? {
+            for note in notes {
+                if let NoteDescriptor::GnuBuildId(id) = note.decode()? {
+                    return Ok(Some(id));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Returns the contents of the dynamic section. The `Dynamic` segment is preferred, the
+    /// `Dynamic` section is used as a fallback for files without program headers.
+    pub fn dynamic_data(&self) -> Result<Option<&'data [u8]>> {
+        for pheader in self.program_headers()? {
+            let pheader = pheader?;
+            if pheader.typ() == ProgramHeaderSegmentType::Dynamic {
+                return self.segment_data(&pheader).map(Some);
+            }
+        }
+        for sheader in self.section_headers()? {
+            let sheader = sheader?;
+            if sheader.typ() == SectionHeaderType::Dynamic {
+                return self.section_data(&sheader).map(Some);
+            }
+        }
+        Ok(None)
+    }
+
+    /// Parses the entries of the dynamic section up to the terminating `DT_NULL` entry. String
+    /// valued entries are resolved through the string table referenced by `DT_STRTAB`.
+    pub fn dynamic_entries(&self) -> Result<Option<Vec<DynamicEntry>>> {
+        let data = match self.dynamic_data()? {
+            None => return Ok(None),
+            Some(data) => data,
+        };
+        let raw_entries = DynamicEntry::parse_raw_entries(
+            data,
+            self.header.word_width(),
+            self.header.endianness(),
+        )?;
+        let strings = self.dynamic_strings(&raw_entries)?;
+        raw_entries
+            .into_iter()
+            .map(|(tag, value)| DynamicEntry::parse(tag, value, strings))
+            .collect::<Result<_>>()
+            .map(Some)
+    }
+
+    /// Returns the string table referenced by `DT_STRTAB` and `DT_STRSZ`. If there are no string
+    /// valued entries the table is not needed and may be missing.
+    fn dynamic_strings(&self, raw_entries: &[(u64, u64)]) -> Result<&'data [u8]> {
+        let find = |wanted: u64| {
+            raw_entries
+                .iter()
+                .find(|(tag, _)| *tag == wanted)
+                .map(|(_, value)| *value)
+        };
+        let address = match find(dynamic::tag::STRTAB) {
+            Some(address) => address,
+            None => return Ok(&[]),
+        };
+        if !raw_entries
+            .iter()
+            .any(|(tag, _)| dynamic::tag::is_string(*tag))
+        {
+            return Ok(&[]);
+        }
+        let strings = self.data_at_address(address)?;
+        match find(dynamic::tag::STRSZ) {
+            Some(size) if (size as usize) < strings.len() => Ok(&strings[..size as usize]),
+            _ => Ok(strings),
+        }
+    }
+
+    /// Returns the file contents from the given virtual address to the end of the file part of
+    /// the `Load` segment containing it.
+    pub(crate) fn data_at_address(&self, address: u64) -> Result<&'data [u8]> {
+        for pheader in self.program_headers()? {
+            let pheader = pheader?;
+            if pheader.typ() != ProgramHeaderSegmentType::Load {
+                continue;
+            }
+            let start = u64::from(pheader.vaddress());
+            let size = u64::from(pheader.filesize());
+            if address >= start && address - start < size {
+                let data = self.segment_data(&pheader)?;
+                return Ok(&data[(address - start) as usize..]);
+            }
+        }
+        Err(ParseError::UnmappedAddress(address))
+    }
+
+    /// Returns the contents of a section. Sections of type `NoData` (like `.bss`) occupy no space
+    /// in the file, so their contents are empty.
+    pub fn section_data(&self, sheader: &SectionHeader) -> Result<&'data [u8]> {
+        if sheader.typ() == SectionHeaderType::NoData {
+            return Ok(&[]);
+        }
+        self.slice(u64::from(sheader.offset()), u64::from(sheader.size()))
+    }
+
+    /// Returns the contents of a section like `section_data`, but decompresses sections with the
+    /// `COMPRESSED` flag and legacy `.zdebug_*` sections. Other sections are borrowed as is.
+    pub fn decompressed_section_data(&self, sheader: &SectionHeader) -> Result<Cow<'data, [u8]>> {
+        let data = self.section_data(sheader)?;
+        if sheader.flags().contains(SectionHeaderFlags::COMPRESSED) {
+            decompress_section(data, self.header.word_width(), self.header.endianness())
+                .map(Cow::Owned)
+        } else if sheader.name().starts_with(".zdebug") {
+            decompress_zdebug(data).map(Cow::Owned)
+        } else {
+            Ok(Cow::Borrowed(data))
+        }
+    }
+
+    /// Returns the part of a segment which is stored in the file. This may be shorter than the
+    /// segment in memory, the remaining bytes are zero-initialized by the loader.
+    pub fn segment_data(&self, pheader: &ProgramHeader) -> Result<&'data [u8]> {
+        self.slice(u64::from(pheader.offset()), u64::from(pheader.filesize()))
+    }
+
+    fn slice(&self, offset: u64, size: u64) -> Result<&'data [u8]> {
+        let range = usize::try_from(offset)
+            .ok()
+            .zip(usize::try_from(size).ok())
+            .and_then(|(start, size)| Some(start..start.checked_add(size)?));
+        range
+            .and_then(|range| self.data.get(range))
+            .ok_or(ParseError::InvalidDataRange(offset, size))
+    }
+}
+
+/// Parses a structure at `offset` with `parse`, which reports its violations to the given
+/// warnings. Lenient views keep the unknown values and drop the violations.
+fn parse_tolerant<T>(
+    lenient: bool,
+    offset: u64,
+    structure: Structure,
+    parse: impl FnOnce(&mut Warnings) -> Result<T>,
+) -> Result<T> {
+    let mut ignored = Vec::new();
+    let mut warnings = if lenient {
+        Warnings::lenient(&mut ignored)
+    } else {
+        Warnings::strict()
+    };
+    parse(&mut warnings.at(offset, structure)).map_err(|err| err.at(offset, structure, None))
+}
+
+impl ParsedSections {
+    /// The `SectionIndices` section holding the extended section indices of `symbols`
+    fn section_indices_of(&self, symbols: &SectionHeader) -> Option<&SectionHeader> {
+        self.section_indices
+            .iter()
+            .map(|&index| &self.sheaders[index])
+            .find(|candidate| self.sheaders.get(candidate.link() as usize) == Some(symbols))
+    }
+}
+
+/// Looks up a section referenced by index in the already parsed section headers
+fn indexed_section(sheaders: &[SectionHeader], index: u32) -> Result<SectionHeader> {
+    sheaders
+        .get(index as usize)
+        .cloned()
+        .ok_or(ParseError::InvalidSectionLink(index))
+}
+
+impl<'data> Iterator for ProgramHeaders<'data> {
+    type Item = Result<ProgramHeader>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let bytes = self.table;
+        self.table = &self.table[self.entry_size.min(self.table.len())..];
+        let (offset, structure) = (self.offset, Structure::ProgramHeader(self.index));
+        self.offset = self.offset.saturating_add(self.entry_size as u64);
+        self.index += 1;
+        Some(parse_tolerant(
+            self.lenient,
+            offset,
+            structure,
+            |warnings| {
+                ProgramHeader::parse_bytes_with_warnings(
+                    bytes,
+                    self.word_width,
+                    self.endianness,
+                    warnings,
+                )
+            },
+        ))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'data> ExactSizeIterator for ProgramHeaders<'data> {}
+
+impl<'data> SectionHeaders<'data> {
+    /// The file offset of the section header `index` entries after the next one
+    fn offset_of(&self, index: usize) -> u64 {
+        self.offset
+            .saturating_add((index as u64).saturating_mul(self.entry_size as u64))
+    }
+
+    /// Parses the section header `index` entries after the next one and resolves its name,
+    /// without parsing the entries in between.
+    pub fn get(&self, index: usize) -> Option<Result<SectionHeader>> {
+        let unnamed = self.unnamed(index)?;
+        let (offset, structure) = (
+            self.offset_of(index),
+            Structure::SectionHeader(self.index + index),
+        );
+        Some(
+            unnamed.and_then(|sheader| match sheader.to_named(self.names) {
+                Err(_) if self.lenient => Ok(sheader.with_name(String::new())),
+                named => named.map_err(|err| err.at(offset, structure, Some("sh_name"))),
+            }),
+        )
+    }
+
+    /// Moves past the next `count` entries without parsing them
+    fn skip(&mut self, count: usize) {
+        let count = count.min(self.remaining);
+        let skipped = count.saturating_mul(self.entry_size).min(self.table.len());
+        self.table = &self.table[skipped..];
+        self.offset = self.offset_of(count);
+        self.index += count;
+        self.remaining -= count;
+    }
+
+    /// Parses the section header `index` entries after the next one
+    fn unnamed(&self, index: usize) -> Option<Result<UnnamedSectionHeader>> {
+        if index >= self.remaining {
+            return None;
+        }
+        let bytes = index
+            .checked_mul(self.entry_size)
+            .and_then(|start| self.table.get(start..))
+            .unwrap_or(&[]);
+        let offset = self.offset_of(index);
+        let structure = Structure::SectionHeader(self.index + index);
+        Some(parse_tolerant(
+            self.lenient,
+            offset,
+            structure,
+            |warnings| {
+                UnnamedSectionHeader::parse_bytes_with_warnings(
+                    bytes,
+                    self.word_width,
+                    self.endianness,
+                    warnings,
+                )
+            },
+        ))
+    }
+}
+
+impl<'data> Iterator for SectionHeaders<'data> {
+    type Item = Result<SectionHeader>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let sheader = self.get(0)?;
+        self.skip(1);
+        Some(sheader)
+    }
+
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        self.skip(n);
+        self.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'data> ExactSizeIterator for SectionHeaders<'data> {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::elf::edit::test::dynamic_fixture;
+    use crate::elf::metadata::test::{extended_numbering_elf_64, minimal_elf_64};
+
+    #[test]
+    fn test_parse_lenient() {
+        let mut bytes = minimal_elf_64();
+        // unknown type and flags of .text
+        bytes[0x144] = 0x14;
+        bytes[0x14C] = 0x01;
+        let elf = ElfFile::parse(&bytes).expect("valid header");
+        assert!(elf
+            .section_headers()
+            .expect("valid table")
+            .any(|sheader| sheader.is_err()));
+
+        let elf = ElfFile::parse_lenient(&bytes).expect("valid header");
+        let (metadata, _) = Metadata::parse_bytes_lenient(&bytes).expect("lenient parse");
+        let sheaders: Vec<_> = elf
+            .section_headers()
+            .expect("valid table")
+            .collect::<Result<_>>()
+            .expect("lenient section headers");
+        assert_eq!(sheaders, metadata.section_headers());
+        assert_eq!(sheaders[1].typ(), SectionHeaderType::Unknown(0x14));
+    }
+
+    #[test]
+    fn test_matches_metadata() {
+        let bytes = minimal_elf_64();
+        let metadata = Metadata::parse_bytes(&bytes).expect("valid ELF");
+        let elf = ElfFile::parse(&bytes).expect("valid ELF");
+        assert_eq!(elf.header(), metadata.header());
+        let pheaders: Result<Vec<_>> = elf.program_headers().expect("in bounds").collect();
+        assert_eq!(pheaders.as_deref(), Ok(metadata.program_headers()));
+        let sheaders: Result<Vec<_>> = elf.section_headers().expect("in bounds").collect();
+        assert_eq!(sheaders.as_deref(), Ok(metadata.section_headers()));
+    }
+
+    #[test]
+    fn test_section_data_borrows() {
+        let bytes = minimal_elf_64();
+        let elf = ElfFile::parse(&bytes).expect("valid ELF");
+        let text = elf
+            .section_header_by_name(".text")
+            .expect("valid section headers")
+            .expect(".text exists");
+        let data = elf.section_data(&text).expect("in bounds");
+        assert_eq!(data, &[0x90; 0x10]);
+        assert_eq!(data.as_ptr(), bytes[0xB0..].as_ptr());
+    }
+
+    #[test]
+    fn test_segment_data() {
+        let bytes = minimal_elf_64();
+        let elf = ElfFile::parse(&bytes).expect("valid ELF");
+        let load = elf
+            .program_headers()
+            .expect("in bounds")
+            .next()
+            .expect("one program header")
+            .expect("valid program header");
+        assert_eq!(elf.segment_data(&load), Ok(&bytes[0xB0..0xC0]));
+    }
+
+    #[test]
+    fn test_section_header_by_index() {
+        let mut bytes = minimal_elf_64();
+        // an unknown type in the header of .text
+        bytes[0x144..0x148].copy_from_slice(&0x14_u32.to_le_bytes());
+        let elf = ElfFile::parse(&bytes).expect("header is intact");
+        // the headers before the requested one are not parsed
+        let shstrtab = elf.section_header(2).expect("valid").expect("in bounds");
+        assert_eq!(shstrtab.name(), ".shstrtab");
+        let mut sheaders = elf.section_headers().expect("in bounds");
+        assert_eq!(sheaders.nth(2).transpose(), Ok(Some(shstrtab)));
+        assert_eq!(sheaders.next(), None);
+        assert!(elf.section_header(1).is_err());
+        assert_eq!(elf.section_header(3), Ok(None));
+    }
+
+    #[test]
+    fn test_linked_section_lookup() {
+        let mut bytes = minimal_elf_64();
+        bytes[0x144..0x148].copy_from_slice(&0x14_u32.to_le_bytes());
+        let elf = ElfFile::parse(&bytes).expect("header is intact");
+        // the broken header of .text doesn't affect lookups of later sections
+        assert_eq!(elf.string_table(2), Ok(&bytes[0xC0..0xD1]));
+        assert_eq!(
+            elf.linked_section(3).map(|_| ()),
+            Err(ParseError::InvalidSectionLink(3))
+        );
+        assert!(elf.string_table(1).is_err());
+    }
+
+    #[test]
+    fn test_truncated_section_table() {
+        let bytes = minimal_elf_64();
+        let elf = ElfFile::parse(&bytes[..0x120]).expect("header is intact");
+        let error = elf.section_headers().map(|_| ()).unwrap_err();
+        assert_eq!(error.kind(), &ParseError::InvalidDataRange(0x100, 3 * 64));
+        let location = error.location().expect("located error");
+        assert_eq!(location.offset(), 0x100);
+        assert_eq!(location.structure(), Structure::SectionHeaderTable);
+    }
+
+    #[test]
+    fn test_extended_numbering() {
+        let bytes = extended_numbering_elf_64();
+        let elf = ElfFile::parse(&bytes).expect("valid ELF");
+        let counts = HeaderCounts {
+            program_headers: 1,
+            section_headers: 3,
+            section_names_index: 2,
+        };
+        assert_eq!(elf.counts(), Ok(counts));
+        assert_eq!(elf.program_headers().map(|pheaders| pheaders.len()), Ok(1));
+        assert!(elf
+            .section_header_by_name(".text")
+            .expect("valid")
+            .is_some());
+    }
+
+    #[test]
+    fn test_extended_numbering_without_section_table() {
+        let mut bytes = extended_numbering_elf_64();
+        bytes[40..48].copy_from_slice(&0_u64.to_le_bytes());
+        let elf = ElfFile::parse(&bytes).expect("header is intact");
+        assert_eq!(
+            elf.counts().map_err(|err| err.kind().clone()),
+            Err(ParseError::MissingExtendedNumbering)
+        );
+    }
+
+    /// A relocatable file with symbols, relocations, a section group and notes, which covers
+    /// more parsing paths than the minimal executable
+    fn relocatable_elf(word_width: WordWidth, endianness: Endianness) -> Vec<u8> {
+        let mut builder =
+            ElfBuilder::new(word_width, endianness, FileType::Relocatable, Arch::X86_64);
+        let text = builder.add_section(
+            BuilderSection::new(".text", SectionHeaderType::ProgramBits, vec![0xC3; 8])
+                .flags(SectionHeaderFlags::ALLOC | SectionHeaderFlags::EXEC),
+        );
+        let mut notes = Vec::new();
+        // an NT_GNU_ABI_TAG and a truncated NT_PRSTATUS note
+        for (name, typ, descriptor) in [(&b"GNU\0"[..], 1, 16), (&b"CORE\0\0\0\0"[..], 1, 8)] {
+            let name_size = if name.starts_with(b"GNU") { 4 } else { 5 };
+            for value in [name_size, descriptor as u32, typ].iter() {
+                value.write_bytes(&mut notes, endianness);
+            }
+            notes.extend_from_slice(name);
+            notes.resize(notes.len() + descriptor, 0x03);
+        }
+        builder.add_section(BuilderSection::new(
+            ".note.ABI-tag",
+            SectionHeaderType::Note,
+            notes,
+        ));
+        let main = builder.add_symbol(
+            BuilderSymbol::new("main", SymbolType::Function, SymbolBinding::Global)
+                .defined_in(text, 0, 8),
+        );
+        builder.add_relocation(text, 4, 2, Some(main), -4);
+        builder.build().expect("valid builder input")
+    }
+
+    /// Calls every parsing function on `bytes`. Only panics matter, errors are expected.
+    fn parse_everything(bytes: &[u8]) {
+        if let Ok(metadata) = Metadata::parse_bytes(bytes) {
+            metadata.segment_sections();
+            metadata.vaddr_to_offset(0x401000);
+            metadata.offset_to_vaddr(0x40);
+            let _ = metadata.write_to(bytes, &mut std::io::Cursor::new(Vec::new()));
+        }
+        let _ = Metadata::parse_bytes_lenient(bytes);
+        let _ = Archive::parse(bytes);
+        let _ = ElfStripper::new().strip_debug().apply(bytes);
+        let _ = ElfStripper::new().strip_symbols().debug_file(bytes);
+        let _ = ElfEditor::new()
+            .set_interpreter("/opt/lib/ld-linux.so.2")
+            .set_rpath("$ORIGIN")
+            .add_needed("libz.so.1")
+            .apply(bytes);
+        let views = [ElfFile::parse(bytes), ElfFile::parse_lenient(bytes)];
+        for elf in views.iter().flatten() {
+            let arch = elf.header().arch();
+            let _ = elf.counts();
+            let _ = elf.interpreter();
+            let _ = elf
+                .symbol_table()
+                .map(|symbols| symbols.map(Iterator::count));
+            let _ = elf.lookup_dynamic_symbol("main");
+            let _ = elf.symbol_versions();
+            let _ = elf.section_groups();
+            let _ = elf.core_notes();
+            let _ = elf.build_id();
+            let _ = elf.dynamic_entries();
+            if let Ok(tables) = elf.hash_tables() {
+                for (_, table) in tables.iter() {
+                    let _ = table.histogram();
+                }
+            }
+            if let Ok(tables) = elf.relocation_tables() {
+                for table in tables.iter() {
+                    for relocation in table.entries().flatten() {
+                        let _ = table.symbol(&relocation);
+                    }
+                }
+            }
+            if let Ok(notes) = elf.notes() {
+                for note in notes.iter().flat_map(|(_, notes)| notes.iter()) {
+                    let _ = note.decode();
+                    let _ = CoreNote::decode(note, arch);
+                }
+            }
+            if let Ok(pheaders) = elf.program_headers() {
+                for pheader in pheaders.flatten() {
+                    let _ = elf.segment_data(&pheader);
+                    let _ = elf.segment_notes(&pheader).map(Iterator::count);
+                }
+            }
+            if let Ok(sheaders) = elf.section_headers() {
+                for sheader in sheaders.flatten() {
+                    let _ = elf.decompressed_section_data(&sheader);
+                    let _ = elf.string_table(sheader.link());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_malformed_input_does_not_panic() {
+        let seeds = [
+            minimal_elf_64(),
+            extended_numbering_elf_64(),
+            relocatable_elf(WordWidth::Width64, Endianness::Little),
+            relocatable_elf(WordWidth::Width32, Endianness::Big),
+            dynamic_fixture(WordWidth::Width64, Endianness::Little),
+        ];
+        // values at the edges of the field ranges, which tend to overflow offset computations
+        let interesting = [
+            0,
+            1,
+            2,
+            0x7F,
+            0x80,
+            0xFF,
+            0xFFFF,
+            0x7FFF_FFFF,
+            u64::MAX,
+            u64::MAX - 1,
+        ];
+        // xorshift64, so failures are reproducible without a dependency on a fuzzer
+        let mut state = 0x9E37_79B9_7F4A_7C15_u64;
+        let mut random = move |bound: usize| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state % bound as u64) as usize
+        };
+        for seed in seeds.iter() {
+            parse_everything(seed);
+            for _ in 0..2000 {
+                let mut bytes = seed.clone();
+                for _ in 0..1 + random(4) {
+                    // most of the structure is in the headers at the start and the end
+                    let position = match random(3) {
+                        0 => random(0x80),
+                        1 => bytes.len().saturating_sub(1 + random(0x200)),
+                        _ => random(bytes.len()),
+                    }
+                    .min(bytes.len() - 1);
+                    match random(4) {
+                        0 => bytes[position] = random(0x100) as u8,
+                        1 => bytes.truncate(position.max(1)),
+                        _ => {
+                            let value = interesting[random(interesting.len())].to_le_bytes();
+                            let width = [1, 2, 4, 8][random(4)];
+                            let end = (position + width).min(bytes.len());
+                            bytes[position..end].copy_from_slice(&value[..end - position]);
+                        }
+                    }
+                }
+                parse_everything(&bytes);
+            }
+        }
+    }
+}
@@ -0,0 +1,139 @@
+use super::*;
+
+bitflags::bitflags! {
+    /// The flags stored in the first word of a `Group` section
+    pub struct GroupFlags: u32 {
+        /// The group is a COMDAT group, the linker keeps only one group per signature
+        const COMDAT = 0x1;
+        const MASK_OS = 0x0FF00000;
+        const MASK_PROCESSOR = 0xF0000000;
+    }
+}
+
+/// A `Group` section (`SHT_GROUP`) with its signature and the resolved member sections.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct SectionGroup {
+    section: SectionHeader,
+    signature: String,
+    flags: GroupFlags,
+    members: Vec<(u32, SectionHeader)>,
+}
+
+impl SectionGroup {
+    /// Parses the contents of a `Group` section into its flags and the indices of its member
+    /// sections.
+    pub fn parse_bytes(bytes: &[u8], endianness: Endianness) -> Result<(GroupFlags, Vec<u32>)> {
+        if bytes.len() < 4 {
+            return Err(ParseError::InsufficientGroupLength(bytes.len()));
+        }
+        let flags = GroupFlags::from_bits_truncate(u32::from_bytes(bytes, endianness)?);
+        let members = bytes[4..]
+            .chunks_exact(4)
+            .map(|chunk| u32::from_bytes(chunk, endianness))
+            .collect::<Result<_>>()?;
+        Ok((flags, members))
+    }
+
+    pub(crate) fn new(
+        section: SectionHeader,
+        signature: String,
+        flags: GroupFlags,
+        members: Vec<(u32, SectionHeader)>,
+    ) -> SectionGroup {
+        SectionGroup {
+            section,
+            signature,
+            flags,
+            members,
+        }
+    }
+
+    /// The group section itself
+    pub fn section(&self) -> &SectionHeader {
+        &self.section
+    }
+
+    /// The name of the symbol identifying the group, or the name of the section for groups
+    /// signed by a section symbol
+    pub fn signature(&self) -> &str {
+        self.signature.as_str()
+    }
+
+    pub const fn flags(&self) -> GroupFlags {
+        self.flags
+    }
+
+    pub fn is_comdat(&self) -> bool {
+        self.flags.contains(GroupFlags::COMDAT)
+    }
+
+    /// The section indices and headers of the members of the group
+    pub fn members(&self) -> &[(u32, SectionHeader)] {
+        self.members.as_slice()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::elf::testing::{string_table, Fixture, FixtureSection};
+
+    #[test]
+    fn test_parse_bytes() {
+        let bytes = [1, 0, 0, 0, 5, 0, 0, 0, 6, 0, 0, 0];
+        assert_eq!(
+            SectionGroup::parse_bytes(&bytes, Endianness::Little),
+            Ok((GroupFlags::COMDAT, vec![5, 6]))
+        );
+        assert_eq!(
+            SectionGroup::parse_bytes(&bytes[..2], Endianness::Little),
+            Err(ParseError::InsufficientGroupLength(2))
+        );
+    }
+
+    #[test]
+    fn test_section_group() {
+        let mut fixture = Fixture::new(WordWidth::Width64, Endianness::Little);
+        fixture.file_type = 1;
+        let text =
+            fixture.section(FixtureSection::new(".text._Z3foov", 1, vec![0xC3]).flags(0x206));
+        let (names, indices) = string_table(&["_Z3foov"]);
+        let strtab = fixture.section(FixtureSection::new(".strtab", 3, names));
+        let mut w = fixture.writer();
+        w.bytes(&[0; 24]);
+        w.u32(indices[0])
+            .u8(0x22)
+            .u8(0)
+            .u16(text as u16)
+            .u64(0)
+            .u64(1);
+        let symtab = fixture.section(
+            FixtureSection::new(".symtab", 2, w.bytes)
+                .link(strtab as u32)
+                .info(1)
+                .entry_size(24),
+        );
+        let mut w = fixture.writer();
+        w.u32(1).u32(text as u32);
+        fixture.section(
+            FixtureSection::new(".group", 0x11, w.bytes)
+                .link(symtab as u32)
+                .info(1)
+                .entry_size(4),
+        );
+        let bytes = fixture.build();
+        let elf = ElfFile::parse(&bytes).expect("valid ELF");
+        let groups = elf.section_groups().expect("valid section groups");
+        assert_eq!(groups.len(), 1);
+        let group = &groups[0];
+        assert_eq!(group.section().name(), ".group");
+        assert_eq!(group.signature(), "_Z3foov");
+        assert!(group.is_comdat());
+        let members: Vec<_> = group
+            .members()
+            .iter()
+            .map(|(index, sheader)| (*index, sheader.name()))
+            .collect();
+        assert_eq!(members, vec![(text as u32, ".text._Z3foov")]);
+    }
+}
@@ -0,0 +1,486 @@
+use super::*;
+
+/// Section type of `.gnu.hash`
+pub(crate) const SHT_GNU_HASH: u32 = 0x6FFFFFF6;
+
+/// The SysV hash function used by `Hash` sections.
+pub fn sysv_hash(name: &[u8]) -> u32 {
+    name.iter().fold(0_u32, |hash, byte| {
+        let hash = (hash << 4).wrapping_add(*byte as u32);
+        let high = hash & 0xF0000000;
+        (hash ^ (high >> 24)) & !high
+    })
+}
+
+/// The DJB hash function used by `.gnu.hash` sections.
+pub fn gnu_hash(name: &[u8]) -> u32 {
+    name.iter().fold(5381_u32, |hash, byte| {
+        hash.wrapping_mul(33).wrapping_add(*byte as u32)
+    })
+}
+
+/// A SysV `Hash` section. Every bucket holds the index of the first symbol of its chain and
+/// the chain array links each symbol to the next one with the same bucket.
+#[derive(Debug, Clone)]
+pub struct SysvHashTable<'data> {
+    buckets: &'data [u8],
+    chains: &'data [u8],
+    endianness: Endianness,
+}
+
+/// A `.gnu.hash` section. The symbols of a bucket are sorted next to each other in the dynamic
+/// symbol table and a bloom filter rejects most lookups of missing symbols early.
+#[derive(Debug, Clone)]
+pub struct GnuHashTable<'data> {
+    symbol_offset: u32,
+    bloom_shift: u32,
+    bloom: &'data [u8],
+    buckets: &'data [u8],
+    chains: &'data [u8],
+    word_width: WordWidth,
+    endianness: Endianness,
+}
+
+/// One of the two hash table formats used for dynamic symbol lookup.
+#[derive(Debug, Clone)]
+pub enum HashTable<'data> {
+    Sysv(SysvHashTable<'data>),
+    Gnu(GnuHashTable<'data>),
+}
+
+fn u32_at(table: &[u8], index: usize, endianness: Endianness) -> Option<u32> {
+    let start = index.checked_mul(4)?;
+    u32::from_bytes(table.get(start..start.checked_add(4)?)?, endianness).ok()
+}
+
+/// Whether `symbol`, the entry `index` of the dynamic symbol table, satisfies an unversioned
+/// lookup of `name` like in the dynamic loader. Undefined symbols, symbols without a value except
+/// for TLS symbols, and hidden versions of a symbol are skipped.
+fn check_match(name: &str, index: u32, symbol: &Symbol, versions: Option<&SymbolVersions>) -> bool {
+    if symbol.name() != name || symbol.section_index() == SymbolSectionIndex::Undefined {
+        return false;
+    }
+    if u64::from(symbol.value()) == 0 && symbol.typ() != SymbolType::ThreadLocalStorage {
+        return false;
+    }
+    versions
+        .and_then(|versions| versions.indices().get(index as usize))
+        .filter(|version| version.is_hidden())
+        .is_none()
+}
+
+fn split(bytes: &[u8], count: usize, size: usize) -> Result<(&[u8], &[u8])> {
+    match count.checked_mul(size) {
+        Some(length) if length <= bytes.len() => Ok(bytes.split_at(length)),
+        _ => Err(ParseError::InsufficientHashTableLength(bytes.len())),
+    }
+}
+
+impl<'data> SysvHashTable<'data> {
+    pub fn parse_bytes(bytes: &'data [u8], endianness: Endianness) -> Result<SysvHashTable<'data>> {
+        let (header, rest) = split(bytes, 2, 4)?;
+        let bucket_count = u32::from_bytes(header, endianness)? as usize;
+        let chain_count = u32::from_bytes(&header[4..], endianness)? as usize;
+        let (buckets, rest) = split(rest, bucket_count, 4)?;
+        let (chains, _) = split(rest, chain_count, 4)?;
+        Ok(SysvHashTable {
+            buckets,
+            chains,
+            endianness,
+        })
+    }
+
+    pub fn bucket_count(&self) -> usize {
+        self.buckets.len() / 4
+    }
+
+    /// Looks up a symbol by name like the dynamic loader does and returns its index and the
+    /// symbol itself. `versions` are the version indices of `symbols`, hidden versions are
+    /// skipped.
+    pub fn lookup(
+        &self,
+        name: &str,
+        symbols: &Symbols,
+        versions: Option<&SymbolVersions>,
+    ) -> Result<Option<(usize, Symbol)>> {
+        if self.buckets.is_empty() {
+            return Ok(None);
+        }
+        let bucket = sysv_hash(name.as_bytes()) as usize % self.bucket_count();
+        let mut index = u32_at(self.buckets, bucket, self.endianness).unwrap_or(0);
+        // a chain can't be longer than the number of symbols, this guards against cycles
+        for _ in 0..self.chains.len() / 4 {
+            if index == 0 {
+                break;
+            }
+            let symbol = symbols
+                .get(index as usize)
+                .unwrap_or(Err(ParseError::InvalidSymbolIndex(index)))?;
+            if check_match(name, index, &symbol, versions) {
+                return Ok(Some((index as usize, symbol)));
+            }
+            index = u32_at(self.chains, index as usize, self.endianness)
+                .ok_or(ParseError::InvalidSymbolIndex(index))?;
+        }
+        Ok(None)
+    }
+
+    /// The number of symbols in the chain of every bucket
+    pub fn chain_lengths(&self) -> Result<Vec<usize>> {
+        (0..self.bucket_count())
+            .map(|bucket| {
+                let mut index = u32_at(self.buckets, bucket, self.endianness).unwrap_or(0);
+                let mut length = 0;
+                while index != 0 && length < self.chains.len() / 4 {
+                    length += 1;
+                    index = u32_at(self.chains, index as usize, self.endianness)
+                        .ok_or(ParseError::InvalidSymbolIndex(index))?;
+                }
+                Ok(length)
+            })
+            .collect()
+    }
+}
+
+impl<'data> GnuHashTable<'data> {
+    pub fn parse_bytes(
+        bytes: &'data [u8],
+        word_width: WordWidth,
+        endianness: Endianness,
+    ) -> Result<GnuHashTable<'data>> {
+        let (header, rest) = split(bytes, 4, 4)?;
+        let bucket_count = u32::from_bytes(header, endianness)? as usize;
+        let symbol_offset = u32::from_bytes(&header[4..], endianness)?;
+        let bloom_size = u32::from_bytes(&header[8..], endianness)? as usize;
+        let bloom_shift = u32::from_bytes(&header[12..], endianness)?;
+        let (bloom, rest) = split(rest, bloom_size, word_width.size())?;
+        let (buckets, chains) = split(rest, bucket_count, 4)?;
+        Ok(GnuHashTable {
+            symbol_offset,
+            bloom_shift,
+            bloom,
+            buckets,
+            chains,
+            word_width,
+            endianness,
+        })
+    }
+
+    pub fn bucket_count(&self) -> usize {
+        self.buckets.len() / 4
+    }
+
+    /// The index of the first symbol covered by the hash table
+    pub const fn symbol_offset(&self) -> u32 {
+        self.symbol_offset
+    }
+
+    /// Checks the bloom filter. `false` means the symbol is definitely not in the table.
+    pub fn may_contain(&self, hash: u32) -> bool {
+        let word_size = self.word_width.size();
+        let word_bits = (word_size * 8) as u32;
+        let words = self.bloom.len() / word_size;
+        if words == 0 {
+            return false;
+        }
+        let index = ((hash / word_bits) as usize % words) * word_size;
+        let word = match Word::parse_bytes(&self.bloom[index..], self.word_width, self.endianness) {
+            Ok(word) => u64::from(word),
+            Err(_) => return false,
+        };
+        let mask = (1_u64 << (hash % word_bits))
+            | (1_u64 << (hash.checked_shr(self.bloom_shift).unwrap_or(0) % word_bits));
+        word & mask == mask
+    }
+
+    /// Looks up a symbol by name like the dynamic loader does and returns its index and the
+    /// symbol itself. `versions` are the version indices of `symbols`, hidden versions are
+    /// skipped.
+    pub fn lookup(
+        &self,
+        name: &str,
+        symbols: &Symbols,
+        versions: Option<&SymbolVersions>,
+    ) -> Result<Option<(usize, Symbol)>> {
+        let hash = gnu_hash(name.as_bytes());
+        if self.buckets.is_empty() || !self.may_contain(hash) {
+            return Ok(None);
+        }
+        let bucket = hash as usize % self.bucket_count();
+        let mut index = u32_at(self.buckets, bucket, self.endianness).unwrap_or(0);
+        if index < self.symbol_offset {
+            return Ok(None);
+        }
+        loop {
+            let chain_hash = u32_at(
+                self.chains,
+                (index - self.symbol_offset) as usize,
+                self.endianness,
+            )
+            .ok_or(ParseError::InvalidSymbolIndex(index))?;
+            if hash | 1 == chain_hash | 1 {
+                let symbol = symbols
+                    .get(index as usize)
+                    .unwrap_or(Err(ParseError::InvalidSymbolIndex(index)))?;
+                if check_match(name, index, &symbol, versions) {
+                    return Ok(Some((index as usize, symbol)));
+                }
+            }
+            // the lowest bit marks the end of the chain
+            if chain_hash & 1 != 0 {
+                return Ok(None);
+            }
+            index += 1;
+        }
+    }
+
+    /// The number of symbols in the chain of every bucket
+    pub fn chain_lengths(&self) -> Result<Vec<usize>> {
+        (0..self.bucket_count())
+            .map(|bucket| {
+                let index = u32_at(self.buckets, bucket, self.endianness).unwrap_or(0);
+                if index < self.symbol_offset {
+                    return Ok(0);
+                }
+                let start = (index - self.symbol_offset) as usize;
+                let mut length = 0;
+                loop {
+                    let chain_hash = u32_at(self.chains, start + length, self.endianness)
+                        .ok_or(ParseError::InvalidSymbolIndex(index))?;
+                    length += 1;
+                    if chain_hash & 1 != 0 {
+                        return Ok(length);
+                    }
+                }
+            })
+            .collect()
+    }
+}
+
+impl<'data> HashTable<'data> {
+    pub fn bucket_count(&self) -> usize {
+        match self {
+            HashTable::Sysv(table) => table.bucket_count(),
+            HashTable::Gnu(table) => table.bucket_count(),
+        }
+    }
+
+    pub fn lookup(
+        &self,
+        name: &str,
+        symbols: &Symbols,
+        versions: Option<&SymbolVersions>,
+    ) -> Result<Option<(usize, Symbol)>> {
+        match self {
+            HashTable::Sysv(table) => table.lookup(name, symbols, versions),
+            HashTable::Gnu(table) => table.lookup(name, symbols, versions),
+        }
+    }
+
+    pub fn chain_lengths(&self) -> Result<Vec<usize>> {
+        match self {
+            HashTable::Sysv(table) => table.chain_lengths(),
+            HashTable::Gnu(table) => table.chain_lengths(),
+        }
+    }
+
+    /// The number of buckets for every chain length, starting at length 0
+    pub fn histogram(&self) -> Result<Vec<usize>> {
+        let lengths = self.chain_lengths()?;
+        let mut histogram = vec![0; lengths.iter().max().map_or(0, |max| max + 1)];
+        for length in lengths {
+            histogram[length] += 1;
+        }
+        Ok(histogram)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::elf::testing::{string_table, Fixture, FixtureSection};
+
+    #[test]
+    fn test_hash_functions() {
+        let test_data = [
+            ("", 0x00000000, 0x00001505),
+            ("printf", 0x077905A6, 0x156B2BB8),
+            ("exit", 0x0006CF04, 0x7C967E3F),
+        ];
+        for (name, sysv, gnu) in test_data.iter() {
+            assert_eq!(sysv_hash(name.as_bytes()), *sysv);
+            assert_eq!(gnu_hash(name.as_bytes()), *gnu);
+        }
+    }
+
+    #[test]
+    fn test_lookup() {
+        let names = ["exit", "main", "printf"];
+        let mut fixture = Fixture::new(WordWidth::Width64, Endianness::Little);
+        let (strings, indices) = string_table(&names);
+        let dynstr = fixture.section(FixtureSection::new(".dynstr", 3, strings).flags(0x2));
+        let mut w = fixture.writer();
+        w.bytes(&[0; 24]);
+        for index in indices.iter() {
+            w.u32(*index).u8(0x12).u8(0).u16(1).u64(0x1000).u64(0);
+        }
+        let dynsym = fixture.section(
+            FixtureSection::new(".dynsym", 0xB, w.bytes)
+                .flags(0x2)
+                .link(dynstr as u32)
+                .info(1)
+                .entry_size(24),
+        );
+
+        // three buckets: "exit" and "main" hash to bucket 1, "printf" to bucket 2
+        let mut w = fixture.writer();
+        w.u32(3).u32(4).u32(0).u32(1).u32(3);
+        w.u32(0).u32(2).u32(0).u32(0);
+        let sysv_hash_section = fixture.section(
+            FixtureSection::new(".hash", 5, w.bytes)
+                .flags(0x2)
+                .link(dynsym as u32)
+                .entry_size(4),
+        );
+
+        // one bucket holding all symbols, the symbols are sorted by bucket
+        let hashes: Vec<_> = names.iter().map(|n| gnu_hash(n.as_bytes())).collect();
+        let bloom = hashes.iter().fold(0_u64, |bloom, hash| {
+            bloom | (1 << (hash % 64)) | (1 << ((hash >> 6) % 64))
+        });
+        let mut w = fixture.writer();
+        w.u32(1).u32(1).u32(1).u32(6).u64(bloom).u32(1);
+        w.u32(hashes[0] & !1).u32(hashes[1] & !1).u32(hashes[2] | 1);
+        let gnu_hash_section = fixture.section(
+            FixtureSection::new(".gnu.hash", SHT_GNU_HASH, w.bytes)
+                .flags(0x2)
+                .link(dynsym as u32),
+        );
+
+        let bytes = fixture.build();
+        let elf = ElfFile::parse(&bytes).expect("valid ELF");
+        let symbols = elf
+            .dynamic_symbol_table()
+            .expect("valid symbol table")
+            .expect(".dynsym exists");
+        let sysv = match elf.hash_table(&elf.section_header(sysv_hash_section).unwrap().unwrap()) {
+            Ok(table @ HashTable::Sysv(_)) => table,
+            other => panic!("expected a SysV hash table, got {:?}", other),
+        };
+        let gnu = elf
+            .hash_table(&elf.section_header(gnu_hash_section).unwrap().unwrap())
+            .expect("valid GNU hash table");
+        for table in [sysv, gnu].iter() {
+            for (i, name) in names.iter().enumerate() {
+                let found = table
+                    .lookup(name, &symbols, None)
+                    .expect("valid hash table");
+                assert_eq!(found.map(|(index, _)| index), Some(i + 1));
+            }
+            assert_eq!(table.lookup("puts", &symbols, None), Ok(None));
+        }
+        assert_eq!(
+            elf.lookup_dynamic_symbol("main")
+                .expect("valid hash table")
+                .map(|(_, symbol)| symbol.name().to_string()),
+            Some("main".to_string())
+        );
+    }
+
+    #[test]
+    fn test_lookup_skips_undefined_and_hidden() {
+        let names = ["puts", "memcpy", "memcpy"];
+        let mut fixture = Fixture::new(WordWidth::Width64, Endianness::Little);
+        let (strings, indices) = string_table(&names);
+        let dynstr = fixture.section(FixtureSection::new(".dynstr", 3, strings).flags(0x2));
+        // an undefined import, a hidden old version and the default version of memcpy
+        let mut w = fixture.writer();
+        w.bytes(&[0; 24]);
+        w.u32(indices[0]).u8(0x12).u8(0).u16(0).u64(0).u64(0);
+        w.u32(indices[1]).u8(0x12).u8(0).u16(1).u64(0x1000).u64(0);
+        w.u32(indices[2]).u8(0x12).u8(0).u16(1).u64(0x2000).u64(0);
+        let dynsym = fixture.section(
+            FixtureSection::new(".dynsym", 0xB, w.bytes)
+                .flags(0x2)
+                .link(dynstr as u32)
+                .info(1)
+                .entry_size(24),
+        );
+        let mut w = fixture.writer();
+        w.u16(0).u16(1).u16(0x8002).u16(3);
+        fixture.section(
+            FixtureSection::new(".gnu.version", version::SHT_GNU_VERSYM, w.bytes)
+                .flags(0x2)
+                .link(dynsym as u32)
+                .entry_size(2),
+        );
+
+        // a single bucket chaining all symbols
+        let mut w = fixture.writer();
+        w.u32(1).u32(4).u32(1).u32(0).u32(2).u32(3).u32(0);
+        let sysv_hash_section = fixture.section(
+            FixtureSection::new(".hash", 5, w.bytes)
+                .flags(0x2)
+                .link(dynsym as u32)
+                .entry_size(4),
+        );
+        let hashes: Vec<_> = names.iter().map(|n| gnu_hash(n.as_bytes())).collect();
+        let bloom = hashes.iter().fold(0_u64, |bloom, hash| {
+            bloom | (1 << (hash % 64)) | (1 << ((hash >> 6) % 64))
+        });
+        let mut w = fixture.writer();
+        w.u32(1).u32(1).u32(1).u32(6).u64(bloom).u32(1);
+        w.u32(hashes[0] & !1).u32(hashes[1] & !1).u32(hashes[2] | 1);
+        let gnu_hash_section = fixture.section(
+            FixtureSection::new(".gnu.hash", SHT_GNU_HASH, w.bytes)
+                .flags(0x2)
+                .link(dynsym as u32),
+        );
+
+        let bytes = fixture.build();
+        let elf = ElfFile::parse(&bytes).expect("valid ELF");
+        let symbols = elf
+            .dynamic_symbol_table()
+            .expect("valid symbol table")
+            .expect(".dynsym exists");
+        let versions = elf.symbol_versions().expect("valid versions");
+        assert!(versions.is_some());
+        for section in [sysv_hash_section, gnu_hash_section].iter() {
+            let table = elf
+                .hash_table(&elf.section_header(*section).unwrap().unwrap())
+                .expect("valid hash table");
+            let lookup = |name, versions| {
+                table
+                    .lookup(name, &symbols, versions)
+                    .expect("valid hash table")
+                    .map(|(index, _)| index)
+            };
+            assert_eq!(lookup("puts", versions.as_ref()), None);
+            assert_eq!(lookup("memcpy", versions.as_ref()), Some(3));
+            assert_eq!(lookup("memcpy", None), Some(2));
+        }
+        assert_eq!(
+            elf.lookup_dynamic_symbol("memcpy")
+                .expect("valid hash table")
+                .map(|(index, _)| index),
+            Some(3)
+        );
+    }
+
+    #[test]
+    fn test_histogram() {
+        let mut bytes = Vec::new();
+        for value in [3_u32, 4, 1, 0, 2, 0, 3, 0, 0].iter() {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        let table = HashTable::Sysv(
+            SysvHashTable::parse_bytes(&bytes, Endianness::Little).expect("valid hash table"),
+        );
+        assert_eq!(table.chain_lengths(), Ok(vec![2, 0, 1]));
+        assert_eq!(table.histogram(), Ok(vec![1, 1, 1]));
+        assert_eq!(
+            SysvHashTable::parse_bytes(&bytes[..24], Endianness::Little).map(|_| ()),
+            Err(ParseError::InsufficientHashTableLength(4))
+        );
+    }
+}
@@ -1,3 +1,5 @@
+use std::convert::TryFrom;
+
 use super::*;
 
 #[derive(Debug, PartialEq, Clone)]
@@ -7,6 +9,7 @@ pub struct Header {
     header_version: u8,
     os_abi: Abi,
     abi_version: u8,
+    ident_padding: [u8; 7],
     file_type: FileType,
     arch: Arch,
     version: u32,
@@ -14,6 +17,7 @@ pub struct Header {
     program_header_start: Word,
     section_header_start: Word,
     flags: u32,
+    header_size: u16,
     pheader_entry_size: u16,
     pheader_entries: u16,
     sheader_entry_size: u16,
@@ -21,19 +25,30 @@ pub struct Header {
     section_names_index: u16,
 }
 
-#[cfg(test)]
+/// The number of program headers, the number of section headers and the index of the section
+/// name table after resolving the extended numbering escape values of the ELF header.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct HeaderCounts {
+    pub program_headers: usize,
+    pub section_headers: usize,
+    pub section_names_index: usize,
+}
+
 impl Header {
-    pub(crate) const fn minimal(word_width: WordWidth, endianness: Endianness) -> Self {
-        let word = match word_width {
-            WordWidth::Width32 => Word::Word32(0),
-            _ => Word::Word64(0),
+    /// A header with all fields zeroed, to be filled in with the `with_*` methods. Only the
+    /// header size is set to the standard size for `word_width`.
+    pub const fn minimal(word_width: WordWidth, endianness: Endianness) -> Self {
+        let (word, header_size) = match word_width {
+            WordWidth::Width32 => (Word::Word32(0), 52),
+            _ => (Word::Word64(0), 64),
         };
         Header {
             word_width,
             endianness,
             header_version: 0,
-            os_abi: Abi::Unknown,
+            os_abi: Abi::SysV,
             abi_version: 0,
+            ident_padding: [0; 7],
             file_type: FileType::None,
             arch: Arch::Unspecified,
             version: 0,
@@ -41,6 +56,7 @@ impl Header {
             program_header_start: word,
             section_header_start: word,
             flags: 0,
+            header_size,
             pheader_entry_size: 0,
             pheader_entries: 0,
             sheader_entry_size: 0,
@@ -64,6 +80,11 @@ impl Header {
         self
     }
 
+    pub const fn with_ident_padding(mut self, ident_padding: [u8; 7]) -> Self {
+        self.ident_padding = ident_padding;
+        self
+    }
+
     pub const fn with_file_type(mut self, file_type: FileType) -> Self {
         self.file_type = file_type;
         self
@@ -99,6 +120,11 @@ impl Header {
         self
     }
 
+    pub const fn with_header_size(mut self, header_size: u16) -> Self {
+        self.header_size = header_size;
+        self
+    }
+
     pub const fn with_program_header_entry_size(mut self, size: u16) -> Self {
         self.pheader_entry_size = size;
         self
@@ -126,13 +152,30 @@ impl Header {
 }
 
 impl Header {
+    /// `e_phnum` value indicating that the number of program headers is stored in the `info`
+    /// field of the first section header
+    pub const PN_XNUM: u16 = 0xFFFF;
+    /// `e_shstrndx` value indicating that the section name table index is stored in the `link`
+    /// field of the first section header
+    pub const SHN_XINDEX: u16 = 0xFFFF;
+
     pub fn parse_bytes(bytes: &[u8]) -> Result<Header> {
+        Header::parse_bytes_with_warnings(bytes, &mut Warnings::strict())
+    }
+
+    /// Parses the header like [`Header::parse_bytes`], but reports an unknown file type and an
+    /// unexpected header size to `warnings`.
+    pub(crate) fn parse_bytes_with_warnings(
+        bytes: &[u8],
+        warnings: &mut Warnings,
+    ) -> Result<Header> {
         // we need at least 52 bytes to parse an ELF header. This is the case for 32-bit ELF files
         Header::check_length(52, bytes.len())?;
 
-        Header::check_magic(bytes)?;
+        Header::check_magic(bytes).map_err(|err| warnings.locate(0, Some("e_ident"), err))?;
 
-        let word_width = WordWidth::parse_byte(bytes[4])?;
+        let word_width = WordWidth::parse_byte(bytes[4])
+            .map_err(|err| warnings.locate(4, Some("EI_CLASS"), err))?;
 
         // now we can check whether we have enough bytes to parse the header
         let required_bytes = match word_width {
@@ -141,14 +184,23 @@ impl Header {
         };
         Header::check_length(required_bytes, bytes.len())?;
 
-        let endianness = Endianness::parse_byte(bytes[5])?;
+        let endianness = Endianness::parse_byte(bytes[5])
+            .map_err(|err| warnings.locate(5, Some("EI_DATA"), err))?;
         let header_version = bytes[6];
         let os_abi = Abi::from_byte(bytes[7]);
         let abi_version = bytes[8];
-
-        let file_type = FileType::parse_bytes(&bytes[16..18], endianness)?;
+        let mut ident_padding = [0; 7];
+        ident_padding.copy_from_slice(&bytes[9..16]);
+
+        let file_type = match FileType::parse_bytes(&bytes[16..18], endianness) {
+            Err(ParseError::InvalidFileType(raw)) => {
+                warnings.report(16, Some("e_type"), ParseError::InvalidFileType(raw))?;
+                FileType::Unknown(raw)
+            }
+            result => result?,
+        };
         let arch = Arch::parse_bytes(&bytes[18..20], endianness)?;
-        let version = u32::from_bytes(&bytes[20..24], endianness);
+        let version = u32::from_bytes(&bytes[20..24], endianness)?;
 
         // these are the word width dependent offsets of the fields:
         // [entry_point, pheader_start, sheader_start, flags, header_size, pheader_entry_size, pheader_entries, sheader_entry_size, sheader_entries, section_names_index]
@@ -161,18 +213,22 @@ impl Header {
         let program_header_start = Word::parse_bytes(&bytes[offsets[1]..], word_width, endianness)?;
         let section_header_start = Word::parse_bytes(&bytes[offsets[2]..], word_width, endianness)?;
 
-        let flags = u32::from_bytes(&bytes[offsets[3]..], endianness);
-        let header_size = u16::from_bytes(&bytes[offsets[4]..], endianness);
+        let flags = u32::from_bytes(&bytes[offsets[3]..], endianness)?;
+        let header_size = u16::from_bytes(&bytes[offsets[4]..], endianness)?;
 
         if required_bytes != header_size as usize {
-            return Err(ParseError::InsuffcientHeaderLength(header_size as usize));
+            warnings.report(
+                offsets[4],
+                Some("e_ehsize"),
+                ParseError::InsuffcientHeaderLength(header_size as usize),
+            )?;
         }
 
-        let pheader_entry_size = u16::from_bytes(&bytes[offsets[5]..], endianness);
-        let pheader_entries = u16::from_bytes(&bytes[offsets[6]..], endianness);
-        let sheader_entry_size = u16::from_bytes(&bytes[offsets[7]..], endianness);
-        let sheader_entries = u16::from_bytes(&bytes[offsets[8]..], endianness);
-        let section_names_index = u16::from_bytes(&bytes[offsets[9]..], endianness);
+        let pheader_entry_size = u16::from_bytes(&bytes[offsets[5]..], endianness)?;
+        let pheader_entries = u16::from_bytes(&bytes[offsets[6]..], endianness)?;
+        let sheader_entry_size = u16::from_bytes(&bytes[offsets[7]..], endianness)?;
+        let sheader_entries = u16::from_bytes(&bytes[offsets[8]..], endianness)?;
+        let section_names_index = u16::from_bytes(&bytes[offsets[9]..], endianness)?;
 
         Ok(Header {
             word_width,
@@ -180,6 +236,7 @@ impl Header {
             header_version,
             os_abi,
             abi_version,
+            ident_padding,
             file_type,
             arch,
             version,
@@ -187,6 +244,7 @@ impl Header {
             program_header_start,
             section_header_start,
             flags,
+            header_size,
             pheader_entry_size,
             pheader_entries,
             sheader_entry_size,
@@ -203,9 +261,9 @@ impl Header {
         }
     }
 
-    fn check_magic(bytes: &[u8]) -> Result<()> {
+    pub(crate) fn check_magic(bytes: &[u8]) -> Result<()> {
         static MAGIC: u32 = u32::from_le_bytes([0x7F, 0x45, 0x4C, 0x46]);
-        let value = u32::from_bytes(bytes, Endianness::Little);
+        let value = u32::from_bytes(bytes, Endianness::Little)?;
         if value != MAGIC {
             Err(ParseError::NoELF(value))
         } else {
@@ -221,6 +279,53 @@ impl Header {
         self.endianness
     }
 
+    pub const fn header_version(&self) -> u8 {
+        self.header_version
+    }
+
+    pub const fn os_abi(&self) -> Abi {
+        self.os_abi
+    }
+
+    pub const fn abi_version(&self) -> u8 {
+        self.abi_version
+    }
+
+    /// The reserved bytes at the end of `e_ident`, which should be zero
+    pub const fn ident_padding(&self) -> [u8; 7] {
+        self.ident_padding
+    }
+
+    pub const fn file_type(&self) -> FileType {
+        self.file_type
+    }
+
+    pub const fn arch(&self) -> Arch {
+        self.arch
+    }
+
+    pub const fn version(&self) -> u32 {
+        self.version
+    }
+
+    pub const fn entry_point(&self) -> Word {
+        self.entry_point
+    }
+
+    pub const fn flags(&self) -> u32 {
+        self.flags
+    }
+
+    /// The processor specific flags decoded according to the architecture of the file
+    pub fn arch_flags(&self) -> ArchFlags {
+        ArchFlags::decode(self.arch, self.flags)
+    }
+
+    /// The raw `e_ehsize` field
+    pub const fn header_size(&self) -> u16 {
+        self.header_size
+    }
+
     pub const fn program_header_start(&self) -> Word {
         self.program_header_start
     }
@@ -233,6 +338,7 @@ impl Header {
         self.pheader_entry_size
     }
 
+    /// The raw `e_phnum` field, see [`Header::counts`] for the effective number of program headers
     pub const fn program_header_entry_count(&self) -> u16 {
         self.pheader_entries
     }
@@ -241,13 +347,81 @@ impl Header {
         self.sheader_entry_size
     }
 
+    /// The raw `e_shnum` field, see [`Header::counts`] for the effective number of section headers
     pub const fn section_header_entry_count(&self) -> u16 {
         self.sheader_entries
     }
 
+    /// The raw `e_shstrndx` field, see [`Header::counts`] for the effective index
     pub const fn section_names_index(&self) -> u16 {
         self.section_names_index
     }
+
+    /// Returns whether any of the counts uses extended numbering, so the first section header is
+    /// needed to resolve them.
+    pub fn uses_extended_numbering(&self) -> bool {
+        (self.sheader_entries == 0 && u64::from(self.section_header_start) != 0)
+            || self.pheader_entries == Header::PN_XNUM
+            || self.section_names_index == Header::SHN_XINDEX
+    }
+
+    /// Resolves the extended numbering of the gABI. Files with 0xFF00 or more sections store the
+    /// real section count in the `size` field of the first section header and the section name
+    /// table index in its `link` field. Files with 0xFFFF or more segments store their count in
+    /// its `info` field.
+    pub fn counts(&self, first_section: Option<&UnnamedSectionHeader>) -> Result<HeaderCounts> {
+        let mut counts = HeaderCounts {
+            program_headers: self.pheader_entries as usize,
+            section_headers: self.sheader_entries as usize,
+            section_names_index: self.section_names_index as usize,
+        };
+        if !self.uses_extended_numbering() {
+            return Ok(counts);
+        }
+        let first_section = first_section.ok_or(ParseError::MissingExtendedNumbering)?;
+        if self.sheader_entries == 0 {
+            counts.section_headers = usize::try_from(u64::from(first_section.size()))
+                .map_err(|_| ParseError::MissingExtendedNumbering)?;
+        }
+        if self.pheader_entries == Header::PN_XNUM {
+            counts.program_headers = first_section.info() as usize;
+        }
+        if self.section_names_index == Header::SHN_XINDEX {
+            counts.section_names_index = first_section.link() as usize;
+        }
+        Ok(counts)
+    }
+
+    /// Serializes the header, the inverse of [`Header::parse_bytes`]. The padding of the
+    /// identification bytes and the header size are written as parsed, so a parsed header
+    /// round-trips byte for byte.
+    pub fn to_bytes(&self, word_width: WordWidth, endianness: Endianness) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(64);
+        bytes.extend_from_slice(&[0x7F, 0x45, 0x4C, 0x46]);
+        bytes.push(word_width.to_byte());
+        bytes.push(endianness.to_byte());
+        bytes.push(self.header_version);
+        bytes.push(self.os_abi.to_byte());
+        bytes.push(self.abi_version);
+        bytes.extend_from_slice(&self.ident_padding);
+        self.file_type.to_u16().write_bytes(&mut bytes, endianness);
+        self.arch.to_u16().write_bytes(&mut bytes, endianness);
+        self.version.write_bytes(&mut bytes, endianness);
+        self.entry_point
+            .write_bytes(&mut bytes, word_width, endianness);
+        self.program_header_start
+            .write_bytes(&mut bytes, word_width, endianness);
+        self.section_header_start
+            .write_bytes(&mut bytes, word_width, endianness);
+        self.flags.write_bytes(&mut bytes, endianness);
+        self.header_size.write_bytes(&mut bytes, endianness);
+        self.pheader_entry_size.write_bytes(&mut bytes, endianness);
+        self.pheader_entries.write_bytes(&mut bytes, endianness);
+        self.sheader_entry_size.write_bytes(&mut bytes, endianness);
+        self.sheader_entries.write_bytes(&mut bytes, endianness);
+        self.section_names_index.write_bytes(&mut bytes, endianness);
+        bytes
+    }
 }
 
 #[cfg(test)]
@@ -371,6 +545,51 @@ mod test {
         assert_eq!(result, Ok(VALID_HEADER_64.clone()));
     }
 
+    #[test]
+    fn test_header_to_bytes() {
+        assert_eq!(
+            VALID_HEADER_32.to_bytes(WordWidth::Width32, Endianness::Little),
+            VALID_HEADER_DATA_32.to_vec()
+        );
+        assert_eq!(
+            VALID_HEADER_64.to_bytes(WordWidth::Width64, Endianness::Little),
+            VALID_HEADER_DATA_64.to_vec()
+        );
+        let big = VALID_HEADER_64.to_bytes(WordWidth::Width64, Endianness::Big);
+        let header = Header::parse_bytes(&big).expect("valid header");
+        assert_eq!(header.endianness(), Endianness::Big);
+        assert_eq!(header.flags(), VALID_HEADER_64.flags());
+        assert_eq!(header.entry_point(), VALID_HEADER_64.entry_point());
+    }
+
+    #[test]
+    fn test_minimal_header() {
+        let header = Header::minimal(WordWidth::Width32, Endianness::Big);
+        assert_eq!(header.os_abi(), Abi::SysV);
+        let bytes = header.to_bytes(WordWidth::Width32, Endianness::Big);
+        assert_eq!(bytes.len(), 52);
+        assert_eq!(bytes[7], 0);
+        assert_eq!(Header::parse_bytes(&bytes), Ok(header));
+    }
+
+    #[test]
+    fn test_header_round_trip_nonstandard() {
+        let mut test_data = VALID_HEADER_DATA_64;
+        test_data[9..16].copy_from_slice(&[1, 2, 3, 4, 5, 6, 7]);
+        test_data[52] = 0x48;
+        let mut warnings = Vec::new();
+        let header =
+            Header::parse_bytes_with_warnings(&test_data, &mut Warnings::lenient(&mut warnings))
+                .expect("lenient parse");
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(header.ident_padding(), [1, 2, 3, 4, 5, 6, 7]);
+        assert_eq!(header.header_size(), 0x48);
+        assert_eq!(
+            header.to_bytes(WordWidth::Width64, Endianness::Little),
+            test_data.to_vec()
+        );
+    }
+
     #[test]
     fn test_header_err_slice_len() {
         let test_data = [];
@@ -394,7 +613,7 @@ mod test {
 
     #[test]
     fn test_header_err_magic() {
-        let mut test_data = VALID_HEADER_DATA_64.clone();
+        let mut test_data = VALID_HEADER_DATA_64;
         test_data[2] = 0x4D;
         let result = Header::parse_bytes(&test_data);
         assert_eq!(result, Err(ParseError::NoELF(u32::to_le(0x464D457F))));
@@ -402,7 +621,7 @@ mod test {
 
     #[test]
     fn test_header_err_word_width() {
-        let mut test_data = VALID_HEADER_DATA_64.clone();
+        let mut test_data = VALID_HEADER_DATA_64;
         test_data[4] = 0x03;
         let result = Header::parse_bytes(&test_data);
         assert_eq!(result, Err(ParseError::InvalidWordWidth(0x03)));
@@ -410,7 +629,7 @@ mod test {
 
     #[test]
     fn test_header_err_endianness() {
-        let mut test_data = VALID_HEADER_DATA_64.clone();
+        let mut test_data = VALID_HEADER_DATA_64;
         test_data[5] = 0xFF;
         let result = Header::parse_bytes(&test_data);
         assert_eq!(result, Err(ParseError::InvalidEndianness(0xFF)));
@@ -418,7 +637,7 @@ mod test {
 
     #[test]
     fn test_header_err_file_type() {
-        let mut test_data = VALID_HEADER_DATA_64.clone();
+        let mut test_data = VALID_HEADER_DATA_64;
         test_data[16] = 0x69;
         test_data[17] = 0x42;
         let result = Header::parse_bytes(&test_data);
@@ -1,14 +1,63 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
 use std::fs::File;
-use std::io::{Read, Seek, SeekFrom};
+use std::io::{Cursor, ErrorKind, Read, Seek, SeekFrom, Write};
 
 use crate::elf::SectionHeaderType;
 
-use super::{Header, ParseError, ProgramHeader, SectionHeader, UnnamedSectionHeader};
+use super::{
+    Header, HeaderCounts, ParseError, ParseWarning, ProgramHeader, ProgramHeaderSegmentType,
+    SectionHeader, Structure, UnnamedSectionHeader, Warnings, WordWidth,
+};
 
 pub struct Metadata {
     header: Header,
     program_headers: Vec<ProgramHeader>,
     section_headers: Vec<SectionHeader>,
+    interpreter: Option<String>,
+}
+
+/// Where a virtual address or a file offset is found in the `Load` segments, see
+/// [`Metadata::vaddr_to_offset`] and [`Metadata::offset_to_vaddr`]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum AddressMapping {
+    /// Mapped by the `Load` segment with the given program header index to the given file offset
+    /// or virtual address
+    Mapped(usize, u64),
+    /// Inside the memory image of the `Load` segment with the given program header index, but
+    /// past its file contents. The loader fills this tail, which usually holds `.bss`, with zeros.
+    ZeroFilled(usize),
+    /// Not inside any `Load` segment. Contains the program header indices of the closest
+    /// segments below and above, if there are any.
+    Unmapped(Option<usize>, Option<usize>),
+}
+
+/// A reader whose seek positions are relative to the position at which the ELF file starts, so
+/// files embedded in a larger stream can be parsed with the offsets from their headers
+struct EmbeddedReader<'r, R> {
+    reader: &'r mut R,
+    base: u64,
+}
+
+impl<'r, R: Read> Read for EmbeddedReader<'r, R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.reader.read(buf)
+    }
+}
+
+impl<'r, R: Seek> Seek for EmbeddedReader<'r, R> {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let pos = match pos {
+            SeekFrom::Start(offset) => SeekFrom::Start(
+                self.base
+                    .checked_add(offset)
+                    .ok_or_else(|| std::io::Error::from(ErrorKind::InvalidInput))?,
+            ),
+            pos => pos,
+        };
+        let position = self.reader.seek(pos)?;
+        Ok(position.saturating_sub(self.base))
+    }
 }
 
 #[derive(Debug)]
@@ -17,6 +66,24 @@ pub enum MetadataParseError {
     IOError(std::io::Error),
 }
 
+impl Display for MetadataParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MetadataParseError::InvalidELF(error) => write!(f, "invalid ELF file: {}", error),
+            MetadataParseError::IOError(error) => write!(f, "I/O error: {}", error),
+        }
+    }
+}
+
+impl Error for MetadataParseError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            MetadataParseError::InvalidELF(error) => Some(error),
+            MetadataParseError::IOError(error) => Some(error),
+        }
+    }
+}
+
 impl Metadata {
     pub fn new(
         header: Header,
@@ -27,6 +94,7 @@ impl Metadata {
             header,
             program_headers,
             section_headers,
+            interpreter: None,
         }
     }
 
@@ -42,99 +110,487 @@ impl Metadata {
         self.section_headers.as_slice()
     }
 
+    /// The effective number of program headers, which may exceed the `e_phnum` field of the
+    /// header when extended numbering is used
+    pub fn program_header_count(&self) -> usize {
+        self.program_headers.len()
+    }
+
+    /// The effective number of section headers, which may exceed the `e_shnum` field of the
+    /// header when extended numbering is used
+    pub fn section_header_count(&self) -> usize {
+        self.section_headers.len()
+    }
+
+    /// The effective index of the section name table
+    pub fn section_names_index(&self) -> usize {
+        match self.header.section_names_index() {
+            Header::SHN_XINDEX => self
+                .section_headers
+                .first()
+                .map_or(0, |sheader| sheader.link() as usize),
+            index => index as usize,
+        }
+    }
+
+    /// The path of the program interpreter (dynamic loader) requested by the `Interp` segment.
+    /// `None` if there is no such segment, or if a lenient parse dropped a path that is not
+    /// terminated inside of the file.
+    pub fn interpreter(&self) -> Option<&str> {
+        self.interpreter.as_deref()
+    }
+
+    /// Returns the indices of the sections contained in each segment, in the order of the
+    /// program headers, like the section to segment mapping printed by `readelf -l`. See
+    /// [`ProgramHeader::contains_section`] for the rules.
+    pub fn segment_sections(&self) -> Vec<Vec<usize>> {
+        self.program_headers
+            .iter()
+            .map(|pheader| {
+                // the null section is never part of a segment
+                (1..self.section_headers.len())
+                    .filter(|&i| pheader.contains_section(&self.section_headers[i]))
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Translates a virtual address to the file offset holding its contents at load time
+    pub fn vaddr_to_offset(&self, vaddr: u64) -> AddressMapping {
+        self.map_address(vaddr, |pheader| {
+            (
+                u64::from(pheader.vaddress()),
+                u64::from(pheader.memsize()),
+                u64::from(pheader.offset()),
+            )
+        })
+    }
+
+    /// Translates a file offset to the virtual address its contents are loaded to. Offsets
+    /// outside of the file contents of all `Load` segments, like those of the section headers or
+    /// the debug information, are not loaded and therefore `Unmapped`.
+    pub fn offset_to_vaddr(&self, offset: u64) -> AddressMapping {
+        self.map_address(offset, |pheader| {
+            (
+                u64::from(pheader.offset()),
+                u64::from(pheader.filesize()),
+                u64::from(pheader.vaddress()),
+            )
+        })
+    }
+
+    /// Looks up `value` in the `Load` segments. `range` returns the start and the size of the
+    /// segment in the space `value` belongs to and the start of the segment in the other one.
+    fn map_address<F>(&self, value: u64, range: F) -> AddressMapping
+    where
+        F: Fn(&ProgramHeader) -> (u64, u64, u64),
+    {
+        let mut zero_filled = None;
+        // the segments with the closest end below and the closest start above `value`
+        let mut below: Option<(usize, u64)> = None;
+        let mut above: Option<(usize, u64)> = None;
+        for (i, pheader) in self.program_headers.iter().enumerate() {
+            if pheader.typ() != ProgramHeaderSegmentType::Load {
+                continue;
+            }
+            let (start, size, target) = range(pheader);
+            // only the file contents of a segment are mapped, anything beyond is zero filled
+            let mapped = u64::from(pheader.filesize()).min(u64::from(pheader.memsize()));
+            if value < start {
+                if !matches!(above, Some((_, closest)) if closest <= start) {
+                    above = Some((i, start));
+                }
+                continue;
+            }
+            let delta = value - start;
+            if delta < mapped {
+                if let Some(mapped) = target.checked_add(delta) {
+                    return AddressMapping::Mapped(i, mapped);
+                }
+            }
+            if delta < size {
+                zero_filled.get_or_insert(i);
+                continue;
+            }
+            let end = start.saturating_add(size);
+            if !matches!(below, Some((_, closest)) if closest >= end) {
+                below = Some((i, end));
+            }
+        }
+        match zero_filled {
+            Some(i) => AddressMapping::ZeroFilled(i),
+            None => AddressMapping::Unmapped(below.map(|(i, _)| i), above.map(|(i, _)| i)),
+        }
+    }
+
+    /// Writes the file contents `data` and serializes the header, the program headers and the
+    /// section headers on top of them at the offsets given by the header. Written with the
+    /// contents of the parsed file, an unmodified `Metadata` reproduces it byte for byte and
+    /// modified headers replace the original ones.
+    pub fn write_to<W: Write + Seek>(&self, data: &[u8], writer: &mut W) -> std::io::Result<()> {
+        let word_width = self.header.word_width();
+        let endianness = self.header.endianness();
+        writer.seek(SeekFrom::Start(0))?;
+        writer.write_all(data)?;
+        writer.seek(SeekFrom::Start(0))?;
+        writer.write_all(&self.header.to_bytes(word_width, endianness))?;
+        Metadata::write_table(
+            writer,
+            u64::from(self.header.program_header_start()),
+            self.header.program_header_entry_size(),
+            self.program_headers
+                .iter()
+                .map(|pheader| pheader.to_bytes(word_width, endianness)),
+        )?;
+        Metadata::write_table(
+            writer,
+            u64::from(self.header.section_header_start()),
+            self.header.section_header_entry_size(),
+            self.section_headers
+                .iter()
+                .map(|sheader| sheader.to_bytes(word_width, endianness)),
+        )
+    }
+
+    fn write_table<W: Write + Seek>(
+        writer: &mut W,
+        offset: u64,
+        entry_size: u16,
+        entries: impl Iterator<Item = Vec<u8>>,
+    ) -> std::io::Result<()> {
+        let entries: Vec<Vec<u8>> = entries.collect();
+        // check the whole table before writing any of it, so nothing is written past the end
+        (entries.len() as u64)
+            .checked_mul(u64::from(entry_size))
+            .and_then(|size| size.checked_add(offset))
+            .ok_or_else(|| std::io::Error::from(ErrorKind::InvalidInput))?;
+        for (i, mut entry) in entries.into_iter().enumerate() {
+            // entries may be larger than the structures we know about, the rest is zero filled
+            entry.resize(entry.len().max(entry_size as usize), 0);
+            writer.seek(SeekFrom::Start(offset + i as u64 * u64::from(entry_size)))?;
+            writer.write_all(&entry[..entry_size as usize])?;
+        }
+        Ok(())
+    }
+
     pub fn parse_file(file: &mut File) -> std::result::Result<Metadata, MetadataParseError> {
+        Metadata::parse(file)
+    }
+
+    pub fn parse_bytes(bytes: &[u8]) -> std::result::Result<Metadata, MetadataParseError> {
+        Metadata::parse(&mut Cursor::new(bytes))
+    }
+
+    /// Parses the ELF file starting at the current position of `reader`. The offsets in the
+    /// headers, the errors and the warnings are relative to that position.
+    pub fn parse<R: Read + Seek>(
+        reader: &mut R,
+    ) -> std::result::Result<Metadata, MetadataParseError> {
+        Metadata::parse_with_warnings(reader, &mut Warnings::strict())
+    }
+
+    /// Parses the metadata like [`Metadata::parse`], but only aborts when the structure of the
+    /// file can't be determined, e.g. for a truncated ELF header or an I/O error. Unknown types
+    /// and flags are kept as raw numbers, program headers and section headers beyond the end of
+    /// the file are left out and unreadable section names are empty. These violations are
+    /// returned as warnings with the file offsets of the offending fields.
+    pub fn parse_lenient<R: Read + Seek>(
+        reader: &mut R,
+    ) -> std::result::Result<(Metadata, Vec<ParseWarning>), MetadataParseError> {
+        let mut warnings = Vec::new();
+        let metadata =
+            Metadata::parse_with_warnings(reader, &mut Warnings::lenient(&mut warnings))?;
+        Ok((metadata, warnings))
+    }
+
+    /// Parses the metadata leniently, see [`Metadata::parse_lenient`]
+    pub fn parse_bytes_lenient(
+        bytes: &[u8],
+    ) -> std::result::Result<(Metadata, Vec<ParseWarning>), MetadataParseError> {
+        Metadata::parse_lenient(&mut Cursor::new(bytes))
+    }
+
+    fn parse_with_warnings<R: Read + Seek>(
+        reader: &mut R,
+        warnings: &mut Warnings,
+    ) -> std::result::Result<Metadata, MetadataParseError> {
         use MetadataParseError::*;
 
-        let mut buf = [0; 64];
-        let status = file.read(&mut buf);
-        let header_buf = match status {
+        let base = match reader.stream_position() {
             Err(err) => return Err(IOError(err)),
-            Ok(n) => &buf[..n],
+            Ok(base) => base,
         };
-        let header = match Header::parse_bytes(header_buf) {
-            Err(err) => return Err(InvalidELF(err)),
+        let reader = &mut EmbeddedReader { reader, base };
+        if let Err(err) = reader.seek(SeekFrom::Start(0)) {
+            return Err(IOError(err));
+        }
+        let mut buf = Vec::with_capacity(64);
+        if let Err(err) = reader.by_ref().take(64).read_to_end(&mut buf) {
+            return Err(IOError(err));
+        }
+        let mut header_warnings = warnings.at(0, Structure::Header);
+        let header = match Header::parse_bytes_with_warnings(buf.as_slice(), &mut header_warnings) {
+            Err(err) => return Err(InvalidELF(err.at(0, Structure::Header, None))),
             Ok(header) => header,
         };
-        let program_headers = Metadata::parse_program_headers_from_file(&header, file)?;
-        let section_headers = Metadata::parse_section_headers_from_file(&header, file)?;
+        let counts = Metadata::parse_counts_from_reader(&header, reader)?;
+        let program_headers =
+            Metadata::parse_program_headers_from_reader(&header, &counts, reader, warnings)?;
+        let section_headers =
+            Metadata::parse_section_headers_from_reader(&header, &counts, reader, warnings)?;
+        let interpreter =
+            Metadata::parse_interpreter_from_reader(&program_headers, reader, warnings)?;
+
+        let mut metadata = Metadata::new(header, program_headers, section_headers);
+        metadata.interpreter = interpreter;
+        Ok(metadata)
+    }
 
-        Ok(Metadata::new(header, program_headers, section_headers))
+    fn parse_interpreter_from_reader<R: Read + Seek>(
+        program_headers: &[ProgramHeader],
+        reader: &mut R,
+        warnings: &mut Warnings,
+    ) -> Result<Option<String>, MetadataParseError> {
+        let (index, pheader) = match program_headers
+            .iter()
+            .enumerate()
+            .find(|(_, pheader)| pheader.typ() == ProgramHeaderSegmentType::Interp)
+        {
+            None => return Ok(None),
+            Some(found) => found,
+        };
+        let offset = u64::from(pheader.offset());
+        let buf = Metadata::read_table(
+            reader,
+            offset,
+            u64::from(pheader.filesize()) as usize,
+            Structure::Segment(index),
+            warnings,
+        )?;
+        match ProgramHeader::parse_interpreter(buf.as_slice()) {
+            Err(err) => {
+                warnings
+                    .at(offset, Structure::Segment(index))
+                    .report(0, None, err)
+                    .map_err(MetadataParseError::InvalidELF)?;
+                Ok(None)
+            }
+            Ok(interpreter) => Ok(Some(interpreter.to_string())),
+        }
     }
 
-    fn parse_program_headers_from_file(
+    fn parse_counts_from_reader<R: Read + Seek>(
         header: &Header,
-        file: &mut File,
-    ) -> Result<Vec<ProgramHeader>, MetadataParseError> {
+        reader: &mut R,
+    ) -> Result<HeaderCounts, MetadataParseError> {
         use MetadataParseError::*;
 
-        let pheader_offset = u64::from(header.program_header_start());
-        let pheader_total_size =
-            (header.program_header_entry_count() * header.program_header_entry_size()) as usize;
-        let mut buf: Vec<_> = std::iter::repeat(0).take(pheader_total_size).collect();
-        if let Err(err) = file.seek(SeekFrom::Start(pheader_offset)) {
+        if !header.uses_extended_numbering() || u64::from(header.section_header_start()) == 0 {
+            return header
+                .counts(None)
+                .map_err(|err| InvalidELF(err.at(0, Structure::Header, None)));
+        }
+        // the counts are stored in the first section header
+        let offset = u64::from(header.section_header_start());
+        let locate =
+            |err: ParseError| InvalidELF(err.at(offset, Structure::SectionHeader(0), None));
+        let buf = Metadata::read_at(reader, offset, header.section_header_entry_size() as usize)?;
+        let first_section = UnnamedSectionHeader::parse_bytes(
+            buf.as_slice(),
+            header.word_width(),
+            header.endianness(),
+        )
+        .map_err(locate)?;
+        header.counts(Some(&first_section)).map_err(locate)
+    }
+
+    fn table_size(entry_size: u16, count: usize) -> Result<usize, MetadataParseError> {
+        (entry_size as usize)
+            .checked_mul(count)
+            .ok_or(MetadataParseError::InvalidELF(
+                ParseError::InvalidDataRange(entry_size as u64, count as u64),
+            ))
+    }
+
+    fn read_at<R: Read + Seek>(
+        reader: &mut R,
+        offset: u64,
+        length: usize,
+    ) -> Result<Vec<u8>, MetadataParseError> {
+        use MetadataParseError::*;
+
+        // the length comes from the file, so the buffer only grows as far as there is data
+        let mut buf = Vec::new();
+        if let Err(err) = reader.seek(SeekFrom::Start(offset)) {
             return Err(IOError(err));
         }
-        if let Err(err) = file.read_exact(buf.as_mut_slice()) {
+        if let Err(err) = reader.take(length as u64).read_to_end(&mut buf) {
             return Err(IOError(err));
         }
-        Metadata::parse_program_headers(header, buf.as_slice())
+        if buf.len() < length {
+            return Err(IOError(ErrorKind::UnexpectedEof.into()));
+        }
+        Ok(buf)
+    }
+
+    /// Reads a table like `read_at`. When parsing leniently, a table extending beyond the end
+    /// of the file is truncated instead.
+    fn read_table<R: Read + Seek>(
+        reader: &mut R,
+        offset: u64,
+        length: usize,
+        structure: Structure,
+        warnings: &mut Warnings,
+    ) -> Result<Vec<u8>, MetadataParseError> {
+        use MetadataParseError::*;
+
+        if !warnings.is_lenient() {
+            return Metadata::read_at(reader, offset, length);
+        }
+        let mut buf = Vec::new();
+        if let Err(err) = reader.seek(SeekFrom::Start(offset)) {
+            return Err(IOError(err));
+        }
+        if let Err(err) = reader.take(length as u64).read_to_end(&mut buf) {
+            return Err(IOError(err));
+        }
+        if buf.len() < length {
+            let error = ParseError::InvalidDataRange(offset, length as u64);
+            warnings
+                .at(offset, structure)
+                .report(0, None, error)
+                .map_err(InvalidELF)?;
+        }
+        Ok(buf)
+    }
+
+    fn parse_program_headers_from_reader<R: Read + Seek>(
+        header: &Header,
+        counts: &HeaderCounts,
+        reader: &mut R,
+        warnings: &mut Warnings,
+    ) -> Result<Vec<ProgramHeader>, MetadataParseError> {
+        let pheader_offset = u64::from(header.program_header_start());
+        let pheader_total_size =
+            Metadata::table_size(header.program_header_entry_size(), counts.program_headers)?;
+        let buf = Metadata::read_table(
+            reader,
+            pheader_offset,
+            pheader_total_size,
+            Structure::ProgramHeaderTable,
+            warnings,
+        )?;
+        Metadata::parse_program_headers(header, counts, buf.as_slice(), warnings)
+    }
+
+    /// The number of complete entries of `entry_size` bytes in a table, which is shorter than
+    /// `count` entries if the table was truncated
+    fn entry_count(raw_table: &[u8], entry_size: u16, count: usize) -> usize {
+        match raw_table.len().checked_div(entry_size as usize) {
+            Some(complete) => count.min(complete),
+            None => count,
+        }
     }
 
     fn parse_program_headers(
         header: &Header,
+        counts: &HeaderCounts,
         raw_pheaders: &[u8],
+        warnings: &mut Warnings,
     ) -> Result<Vec<ProgramHeader>, MetadataParseError> {
         use MetadataParseError::*;
 
         let word_width = header.word_width();
         let endianness = header.endianness();
-        (0..header.program_header_entry_count())
-            .into_iter()
+        let entry_size = header.program_header_entry_size();
+        let start = u64::from(header.program_header_start());
+        (0..Metadata::entry_count(raw_pheaders, entry_size, counts.program_headers))
             .map(|i| {
-                let offset = (i * header.program_header_entry_size()) as usize;
-                match ProgramHeader::parse_bytes(&raw_pheaders[offset..], word_width, endianness) {
-                    Err(err) => Err(InvalidELF(err)),
+                // `entry_count` only counts entries inside of the table
+                let offset = i * entry_size as usize;
+                let file_offset = start.saturating_add(offset as u64);
+                let structure = Structure::ProgramHeader(i);
+                match ProgramHeader::parse_bytes_with_warnings(
+                    raw_pheaders.get(offset..).unwrap_or(&[]),
+                    word_width,
+                    endianness,
+                    &mut warnings.at(file_offset, structure),
+                ) {
+                    Err(err) => Err(InvalidELF(err.at(file_offset, structure, None))),
                     Ok(pheader) => Ok(pheader),
                 }
             })
             .collect()
     }
 
-    fn parse_section_headers_from_file(
+    fn parse_section_headers_from_reader<R: Read + Seek>(
         header: &Header,
-        file: &mut File,
+        counts: &HeaderCounts,
+        reader: &mut R,
+        warnings: &mut Warnings,
     ) -> Result<Vec<SectionHeader>, MetadataParseError> {
-        use MetadataParseError::*;
-
         let sheader_offset = u64::from(header.section_header_start());
         let sheader_total_size =
-            (header.section_header_entry_count() * header.section_header_entry_size()) as usize;
-        let mut buf: Vec<_> = std::iter::repeat(0).take(sheader_total_size).collect();
-        if let Err(err) = file.seek(SeekFrom::Start(sheader_offset)) {
-            return Err(IOError(err));
-        }
-        if let Err(err) = file.read_exact(buf.as_mut_slice()) {
-            return Err(IOError(err));
-        }
-        let unnamed_section_headers = Metadata::parse_section_headers(header, buf.as_slice())?;
-        Metadata::parse_named_section_headers_from_file(header, unnamed_section_headers, file)
+            Metadata::table_size(header.section_header_entry_size(), counts.section_headers)?;
+        let buf = Metadata::read_table(
+            reader,
+            sheader_offset,
+            sheader_total_size,
+            Structure::SectionHeaderTable,
+            warnings,
+        )?;
+        let unnamed_section_headers =
+            Metadata::parse_section_headers(header, counts, buf.as_slice(), warnings)?;
+        Metadata::parse_named_section_headers_from_reader(
+            header,
+            counts,
+            unnamed_section_headers,
+            reader,
+            warnings,
+        )
     }
 
-    fn parse_named_section_headers_from_file(
+    fn parse_named_section_headers_from_reader<R: Read + Seek>(
         header: &Header,
+        counts: &HeaderCounts,
         unnamed_section_headers: Vec<UnnamedSectionHeader>,
-        file: &mut File,
+        reader: &mut R,
+        warnings: &mut Warnings,
     ) -> Result<Vec<SectionHeader>, MetadataParseError> {
         use MetadataParseError::*;
 
+        let start = u64::from(header.section_header_start());
+        let entry_size = u64::from(header.section_header_entry_size());
+        let sheader_offset = |index: usize| start.saturating_add(index as u64 * entry_size);
         let (name_table_offset, name_table_length) =
-            match unnamed_section_headers.get(header.section_names_index() as usize) {
-                None => return Ok(Vec::new()),
+            match unnamed_section_headers.get(counts.section_names_index) {
+                None if unnamed_section_headers.is_empty() || !warnings.is_lenient() => {
+                    return Ok(Vec::new())
+                }
+                None => {
+                    // the section headers are kept without names
+                    let index_offset = match header.word_width() {
+                        WordWidth::Width32 => 50,
+                        WordWidth::Width64 => 62,
+                    };
+                    let error = ParseError::InvalidSectionLink(counts.section_names_index as u32);
+                    warnings
+                        .at(0, Structure::Header)
+                        .report(index_offset, Some("e_shstrndx"), error)
+                        .map_err(InvalidELF)?;
+                    return Ok(unnamed_section_headers
+                        .into_iter()
+                        .map(|sheader| sheader.with_name(String::new()))
+                        .collect());
+                }
                 Some(sheader) => {
                     if sheader.typ() != SectionHeaderType::StringTable {
-                        return Err(InvalidELF(ParseError::InvalidSectionNameTableType(
-                            sheader.typ(),
-                        )));
+                        let error = ParseError::InvalidSectionNameTableType(sheader.typ());
+                        let index = counts.section_names_index;
+                        warnings
+                            .at(sheader_offset(index), Structure::SectionHeader(index))
+                            .report(4, Some("sh_type"), error)
+                            .map_err(InvalidELF)?;
                     }
                     (
                         u64::from(sheader.offset()),
@@ -142,38 +598,55 @@ impl Metadata {
                     )
                 }
             };
-        if let Err(err) = file.seek(SeekFrom::Start(name_table_offset)) {
-            return Err(IOError(err));
-        }
-        let mut buf: Vec<_> = std::iter::repeat(0).take(name_table_length).collect();
-        if let Err(err) = file.read_exact(buf.as_mut_slice()) {
-            return Err(IOError(err));
-        }
-        let section_headers: Result<Vec<_>, ParseError> = unnamed_section_headers
+        let buf = Metadata::read_table(
+            reader,
+            name_table_offset,
+            name_table_length,
+            Structure::Section(counts.section_names_index),
+            warnings,
+        )?;
+        unnamed_section_headers
             .into_iter()
-            .map(|header| header.to_named(buf.as_slice()))
-            .collect();
-        section_headers.map_err(MetadataParseError::InvalidELF)
+            .enumerate()
+            .map(|(i, sheader)| match sheader.to_named(buf.as_slice()) {
+                Ok(sheader) => Ok(sheader),
+                Err(err) => {
+                    // the name index is the first field of a section header
+                    warnings
+                        .at(sheader_offset(i), Structure::SectionHeader(i))
+                        .report(0, Some("sh_name"), err)
+                        .map_err(InvalidELF)?;
+                    Ok(sheader.with_name(String::new()))
+                }
+            })
+            .collect()
     }
 
     fn parse_section_headers(
         header: &Header,
+        counts: &HeaderCounts,
         raw_sheaders: &[u8],
+        warnings: &mut Warnings,
     ) -> Result<Vec<UnnamedSectionHeader>, MetadataParseError> {
         use MetadataParseError::*;
 
         let word_width = header.word_width();
         let endianness = header.endianness();
-        (0..header.section_header_entry_count())
-            .into_iter()
+        let entry_size = header.section_header_entry_size();
+        let start = u64::from(header.section_header_start());
+        (0..Metadata::entry_count(raw_sheaders, entry_size, counts.section_headers))
             .map(|i| {
-                let offset = (i * header.section_header_entry_size()) as usize;
-                match UnnamedSectionHeader::parse_bytes(
-                    &raw_sheaders[offset..],
+                // `entry_count` only counts entries inside of the table
+                let offset = i * entry_size as usize;
+                let file_offset = start.saturating_add(offset as u64);
+                let structure = Structure::SectionHeader(i);
+                match UnnamedSectionHeader::parse_bytes_with_warnings(
+                    raw_sheaders.get(offset..).unwrap_or(&[]),
                     word_width,
                     endianness,
+                    &mut warnings.at(file_offset, structure),
                 ) {
-                    Err(err) => Err(InvalidELF(err)),
+                    Err(err) => Err(InvalidELF(err.at(file_offset, structure, None))),
                     Ok(sheader) => Ok(sheader),
                 }
             })
@@ -181,4 +654,441 @@ impl Metadata {
     }
 }
 
-//TODO: add tests
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+    use crate::elf::testing::{Fixture, FixtureSection};
+    use crate::elf::{
+        Arch, ElfFile, Endianness, FileType, ProgramHeaderSegmentType, Result, SectionHeaderFlags,
+        Word, WordWidth,
+    };
+
+    /// A small, hand assembled 64-bit little endian executable with one `PT_LOAD` segment and
+    /// the sections `<null>`, `.text` and `.shstrtab`.
+    pub(crate) fn minimal_elf_64() -> Vec<u8> {
+        let mut bytes = Vec::new();
+        // ELF header
+        bytes.extend_from_slice(&[0x7F, 0x45, 0x4C, 0x46, 0x02, 0x01, 0x01, 0x00]);
+        bytes.extend_from_slice(&[0x00; 8]);
+        bytes.extend_from_slice(&2_u16.to_le_bytes()); // executable
+        bytes.extend_from_slice(&0x3E_u16.to_le_bytes()); // x86_64
+        bytes.extend_from_slice(&1_u32.to_le_bytes()); // version
+        bytes.extend_from_slice(&0x401000_u64.to_le_bytes()); // entry point
+        bytes.extend_from_slice(&64_u64.to_le_bytes()); // program header start
+        bytes.extend_from_slice(&0x100_u64.to_le_bytes()); // section header start
+        bytes.extend_from_slice(&0_u32.to_le_bytes()); // flags
+        bytes.extend_from_slice(&64_u16.to_le_bytes()); // header size
+        bytes.extend_from_slice(&56_u16.to_le_bytes()); // program header entry size
+        bytes.extend_from_slice(&1_u16.to_le_bytes()); // program header entry count
+        bytes.extend_from_slice(&64_u16.to_le_bytes()); // section header entry size
+        bytes.extend_from_slice(&3_u16.to_le_bytes()); // section header entry count
+        bytes.extend_from_slice(&2_u16.to_le_bytes()); // section names index
+
+        // program header: PT_LOAD, R+X, covering .text
+        bytes.extend_from_slice(&1_u32.to_le_bytes());
+        bytes.extend_from_slice(&5_u32.to_le_bytes());
+        bytes.extend_from_slice(&0xB0_u64.to_le_bytes());
+        bytes.extend_from_slice(&0x4010B0_u64.to_le_bytes());
+        bytes.extend_from_slice(&0x4010B0_u64.to_le_bytes());
+        bytes.extend_from_slice(&0x10_u64.to_le_bytes());
+        bytes.extend_from_slice(&0x10_u64.to_le_bytes());
+        bytes.extend_from_slice(&0x10_u64.to_le_bytes());
+        // .text at 0xB0
+        bytes.resize(0xB0, 0);
+        bytes.extend_from_slice(&[0x90; 0x10]);
+        // .shstrtab at 0xC0
+        bytes.extend_from_slice(b"\0.text\0.shstrtab\0");
+        bytes.resize(0x100, 0);
+        // section headers
+        let section = |bytes: &mut Vec<u8>,
+                       name: u32,
+                       typ: u32,
+                       flags: u64,
+                       addr: u64,
+                       offset: u64,
+                       size: u64,
+                       align: u64| {
+            bytes.extend_from_slice(&name.to_le_bytes());
+            bytes.extend_from_slice(&typ.to_le_bytes());
+            bytes.extend_from_slice(&flags.to_le_bytes());
+            bytes.extend_from_slice(&addr.to_le_bytes());
+            bytes.extend_from_slice(&offset.to_le_bytes());
+            bytes.extend_from_slice(&size.to_le_bytes());
+            bytes.extend_from_slice(&0_u32.to_le_bytes());
+            bytes.extend_from_slice(&0_u32.to_le_bytes());
+            bytes.extend_from_slice(&align.to_le_bytes());
+            bytes.extend_from_slice(&0_u64.to_le_bytes());
+        };
+        section(&mut bytes, 0, 0, 0, 0, 0, 0, 0);
+        section(&mut bytes, 1, 1, 0x6, 0x4010B0, 0xB0, 0x10, 0x10);
+        section(&mut bytes, 7, 3, 0, 0, 0xC0, 17, 1);
+        bytes
+    }
+
+    #[test]
+    fn test_parse_bytes_ok() {
+        let metadata = Metadata::parse_bytes(&minimal_elf_64()).expect("valid ELF");
+        let header = metadata.header();
+        assert_eq!(header.word_width(), WordWidth::Width64);
+        assert_eq!(header.endianness(), Endianness::Little);
+        assert_eq!(header.file_type(), FileType::Executable);
+        assert_eq!(header.arch(), Arch::X86_64);
+        assert_eq!(metadata.program_headers().len(), 1);
+        assert_eq!(
+            metadata.program_headers()[0].typ(),
+            ProgramHeaderSegmentType::Load
+        );
+        let names: Vec<_> = metadata
+            .section_headers()
+            .iter()
+            .map(|sheader| sheader.name())
+            .collect();
+        assert_eq!(names, ["", ".text", ".shstrtab"]);
+    }
+
+    #[test]
+    fn test_interpreter() {
+        let mut bytes = minimal_elf_64();
+        assert_eq!(
+            Metadata::parse_bytes(&bytes)
+                .expect("valid ELF")
+                .interpreter(),
+            None
+        );
+        // turn the PT_LOAD segment into a PT_INTERP segment pointing to a path in .text
+        bytes[64] = 3;
+        bytes[0xB0..0xC0].copy_from_slice(b"/lib/ld-musl.so\0");
+        let metadata = Metadata::parse_bytes(&bytes).expect("valid ELF");
+        assert_eq!(metadata.interpreter(), Some("/lib/ld-musl.so"));
+        // the path must be terminated inside of the segment, like for ElfFile
+        bytes[0xBF] = b'1';
+        assert!(matches!(
+            Metadata::parse_bytes(&bytes),
+            Err(MetadataParseError::InvalidELF(error))
+                if error.kind() == &ParseError::UnterminatedString
+        ));
+        assert_eq!(
+            ElfFile::parse(&bytes).unwrap().interpreter(),
+            Err(ParseError::UnterminatedString.at(0xB0, Structure::Segment(0), None))
+        );
+        // a lenient parse drops the path with a warning
+        let (metadata, warnings) = Metadata::parse_bytes_lenient(&bytes).expect("valid ELF");
+        assert_eq!(metadata.interpreter(), None);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].error().kind(), &ParseError::UnterminatedString);
+        // and so is a path running past the end of the file
+        bytes[0xBF] = 0;
+        bytes[64 + 32..64 + 40].copy_from_slice(&0x1000u64.to_le_bytes());
+        assert!(Metadata::parse_bytes(&bytes).is_err());
+        let (_, warnings) = Metadata::parse_bytes_lenient(&bytes).expect("valid ELF");
+        assert!(!warnings.is_empty());
+    }
+
+    #[test]
+    fn test_parse_reader_matches_parse_bytes() {
+        let bytes = minimal_elf_64();
+        let from_bytes = Metadata::parse_bytes(&bytes).expect("valid ELF");
+        let from_reader = Metadata::parse(&mut Cursor::new(bytes)).expect("valid ELF");
+        assert_eq!(from_bytes.header(), from_reader.header());
+        assert_eq!(from_bytes.program_headers(), from_reader.program_headers());
+        assert_eq!(from_bytes.section_headers(), from_reader.section_headers());
+    }
+
+    #[test]
+    fn test_write_to_invalid_offset() {
+        let bytes = minimal_elf_64();
+        let metadata = Metadata::parse_bytes(&bytes).expect("valid ELF");
+        let header = metadata
+            .header()
+            .clone()
+            .with_section_header_start(Word::Word64(u64::MAX - 8));
+        let modified = Metadata::new(
+            header,
+            metadata.program_headers().to_vec(),
+            metadata.section_headers().to_vec(),
+        );
+        let error = modified
+            .write_to(&bytes, &mut Cursor::new(Vec::new()))
+            .expect_err("offset overflows");
+        assert_eq!(error.kind(), ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_parse_embedded() {
+        let bytes = minimal_elf_64();
+        let mut embedded = vec![0xAA; 0x1000];
+        embedded.extend_from_slice(&bytes);
+        let mut reader = Cursor::new(embedded);
+        reader.set_position(0x1000);
+        let metadata = Metadata::parse(&mut reader).expect("valid ELF");
+        let expected = Metadata::parse_bytes(&bytes).expect("valid ELF");
+        assert_eq!(metadata.header(), expected.header());
+        assert_eq!(metadata.program_headers(), expected.program_headers());
+        assert_eq!(metadata.section_headers(), expected.section_headers());
+    }
+
+    #[test]
+    fn test_parse_bytes_truncated() {
+        let bytes = minimal_elf_64();
+        let result = Metadata::parse_bytes(&bytes[..0x120]);
+        assert!(matches!(result, Err(MetadataParseError::IOError(_))));
+    }
+
+    #[test]
+    fn test_parse_bytes_no_elf() {
+        let result = Metadata::parse_bytes(b"!<arch>\n");
+        assert!(matches!(
+            result,
+            Err(MetadataParseError::InvalidELF(error))
+                if error.kind() == &ParseError::InsuffcientHeaderLength(8)
+        ));
+    }
+
+    #[test]
+    fn test_parse_lenient() {
+        let mut bytes = minimal_elf_64();
+        bytes[16..18].copy_from_slice(&0x1234_u16.to_le_bytes());
+        bytes[64..68].copy_from_slice(&9_u32.to_le_bytes());
+        bytes[80..88].copy_from_slice(&0x4010B1_u64.to_le_bytes());
+        bytes[0x144..0x148].copy_from_slice(&0x14_u32.to_le_bytes());
+        bytes[0x148..0x150].copy_from_slice(&0x1_0000_0006_u64.to_le_bytes());
+        bytes[0x140..0x144].copy_from_slice(&0x1000_u32.to_le_bytes());
+        assert!(matches!(
+            Metadata::parse_bytes(&bytes),
+            Err(MetadataParseError::InvalidELF(error))
+                if error.kind() == &ParseError::InvalidFileType(0x1234)
+        ));
+
+        let (metadata, warnings) = Metadata::parse_bytes_lenient(&bytes).unwrap();
+        let found: Vec<_> = warnings
+            .iter()
+            .map(|warning| (warning.offset(), warning.error().kind().clone()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (16, ParseError::InvalidFileType(0x1234)),
+                (64, ParseError::InvalidProgramHeaderType(9)),
+                (
+                    80,
+                    ParseError::InvalidVirtualAddress(Word::Word64(0x4010B1))
+                ),
+                (0x144, ParseError::InvalidSectionHeaderType(0x14)),
+                (0x148, ParseError::InvalidSectionHeaderFlags(0x1_0000_0006)),
+                (0x140, ParseError::UnterminatedString),
+            ]
+        );
+        assert_eq!(metadata.header().file_type(), FileType::Unknown(0x1234));
+        let pheader = &metadata.program_headers()[0];
+        assert_eq!(pheader.typ(), ProgramHeaderSegmentType::Unknown(9));
+        let sheader = &metadata.section_headers()[1];
+        assert_eq!(sheader.name(), "");
+        assert_eq!(sheader.typ(), SectionHeaderType::Unknown(0x14));
+        assert_eq!(
+            sheader.flags(),
+            SectionHeaderFlags::ALLOC | SectionHeaderFlags::EXEC
+        );
+        assert_eq!(sheader.raw_flags(), 0x1_0000_0006);
+        assert_eq!(metadata.section_headers()[2].name(), ".shstrtab");
+
+        // the unknown values are written back unchanged
+        let mut written = Cursor::new(Vec::new());
+        metadata.write_to(&bytes, &mut written).unwrap();
+        assert_eq!(written.into_inner(), bytes);
+    }
+
+    #[test]
+    fn test_parse_error_location() {
+        let mut bytes = minimal_elf_64();
+        bytes[0x144..0x148].copy_from_slice(&0x14_u32.to_le_bytes());
+        let error = Metadata::parse_bytes(&bytes).err().expect("invalid ELF");
+        assert_eq!(
+            error.to_string(),
+            "invalid ELF file: unknown section header type 0x14 in sh_type of section header #1 \
+             at offset 0x144"
+        );
+        let error = ElfFile::parse(&bytes)
+            .and_then(|elf| elf.section_headers()?.collect::<Result<Vec<_>>>())
+            .unwrap_err();
+        let location = error.location().expect("located error");
+        assert_eq!(location.offset(), 0x144);
+        assert_eq!(location.structure(), Structure::SectionHeader(1));
+        assert_eq!(location.field(), Some("sh_type"));
+    }
+
+    #[test]
+    fn test_parse_lenient_truncated() {
+        let bytes = minimal_elf_64();
+        let (metadata, warnings) = Metadata::parse_bytes_lenient(&bytes[..0x180]).unwrap();
+        let found: Vec<_> = warnings
+            .iter()
+            .map(|warning| (warning.offset(), warning.error().kind().clone()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (0x100, ParseError::InvalidDataRange(0x100, 0xC0)),
+                (62, ParseError::InvalidSectionLink(2)),
+            ]
+        );
+        assert_eq!(metadata.program_header_count(), 1);
+        // the header of the name table is cut off, the complete section headers are unnamed
+        assert_eq!(metadata.section_header_count(), 2);
+        assert_eq!(metadata.section_headers()[1].name(), "");
+        assert_eq!(u64::from(metadata.section_headers()[1].offset()), 0xB0);
+    }
+
+    fn assert_round_trip(bytes: &[u8]) {
+        let metadata = Metadata::parse_bytes(bytes).expect("valid ELF");
+        let mut written = Cursor::new(Vec::new());
+        metadata.write_to(bytes, &mut written).expect("in memory");
+        assert_eq!(written.into_inner(), bytes);
+    }
+
+    #[test]
+    fn test_write_to_round_trip() {
+        assert_round_trip(&minimal_elf_64());
+        assert_round_trip(&extended_numbering_elf_64());
+
+        let mut fixture = Fixture::new(WordWidth::Width32, Endianness::Big);
+        fixture.arch = 0x08;
+        fixture.flags = 0x70001005;
+        let text = fixture.section(FixtureSection::new(".text", 1, vec![0; 0x20]).flags(0x6));
+        fixture.section(FixtureSection::new(".bss", 8, vec![]).flags(0x3).align(16));
+        fixture.segment(1, 5, text..text + 1, 0x1000);
+        assert_round_trip(&fixture.build());
+    }
+
+    #[test]
+    fn test_write_to_modified_header() {
+        let bytes = minimal_elf_64();
+        let metadata = Metadata::parse_bytes(&bytes).expect("valid ELF");
+        let header = metadata.header().clone().with_flags(0x1234);
+        let modified = Metadata::new(
+            header,
+            metadata.program_headers().to_vec(),
+            metadata.section_headers().to_vec(),
+        );
+        let mut written = Cursor::new(Vec::new());
+        modified.write_to(&bytes, &mut written).expect("in memory");
+        let written = written.into_inner();
+        assert_eq!(written[48..52], 0x1234_u32.to_le_bytes());
+        assert_eq!(written[..48], bytes[..48]);
+        assert_eq!(written[52..], bytes[52..]);
+    }
+
+    /// `minimal_elf_64` with all three header counts moved into the first section header
+    pub(crate) fn extended_numbering_elf_64() -> Vec<u8> {
+        let mut bytes = minimal_elf_64();
+        bytes[56..58].copy_from_slice(&Header::PN_XNUM.to_le_bytes());
+        bytes[60..62].copy_from_slice(&0_u16.to_le_bytes());
+        bytes[62..64].copy_from_slice(&Header::SHN_XINDEX.to_le_bytes());
+        bytes[0x120..0x128].copy_from_slice(&3_u64.to_le_bytes()); // size
+        bytes[0x128..0x12C].copy_from_slice(&2_u32.to_le_bytes()); // link
+        bytes[0x12C..0x130].copy_from_slice(&1_u32.to_le_bytes()); // info
+        bytes
+    }
+
+    #[test]
+    fn test_extended_numbering() {
+        let metadata = Metadata::parse_bytes(&extended_numbering_elf_64()).expect("valid ELF");
+        assert_eq!(metadata.header().section_header_entry_count(), 0);
+        assert_eq!(metadata.program_header_count(), 1);
+        assert_eq!(metadata.section_header_count(), 3);
+        assert_eq!(metadata.section_names_index(), 2);
+        assert_eq!(metadata.section_headers()[1].name(), ".text");
+    }
+
+    /// Two `Load` segments with a gap in between, the second one with a `.bss` tail
+    fn segments() -> Metadata {
+        let load = |offset: u64, vaddress: u64, filesize: u64, memsize: u64| {
+            ProgramHeader::new(
+                ProgramHeaderSegmentType::Load,
+                Word::Word64(offset),
+                Word::Word64(vaddress),
+                Word::Word64(vaddress),
+                Word::Word64(filesize),
+                Word::Word64(memsize),
+                0x4,
+                Word::Word64(0x1000),
+            )
+        };
+        let stack = ProgramHeader::new(
+            ProgramHeaderSegmentType::GNU_STACK,
+            Word::Word64(0),
+            Word::Word64(0),
+            Word::Word64(0),
+            Word::Word64(0),
+            Word::Word64(0),
+            0x6,
+            Word::Word64(0x10),
+        );
+        let header = Header::minimal(WordWidth::Width64, Endianness::Little);
+        let pheaders = vec![
+            stack,
+            load(0, 0x400000, 0x1234, 0x1234),
+            load(0x1E00, 0x403E00, 0x200, 0x1000),
+        ];
+        Metadata::new(header, pheaders, Vec::new())
+    }
+
+    #[test]
+    fn test_vaddr_to_offset() {
+        let metadata = segments();
+        assert_eq!(
+            metadata.vaddr_to_offset(0x400000),
+            AddressMapping::Mapped(1, 0)
+        );
+        assert_eq!(
+            metadata.vaddr_to_offset(0x401233),
+            AddressMapping::Mapped(1, 0x1233)
+        );
+        assert_eq!(
+            metadata.vaddr_to_offset(0x403FFF),
+            AddressMapping::Mapped(2, 0x1FFF)
+        );
+        // the bss tail
+        assert_eq!(
+            metadata.vaddr_to_offset(0x404000),
+            AddressMapping::ZeroFilled(2)
+        );
+        assert_eq!(
+            metadata.vaddr_to_offset(0x404DFF),
+            AddressMapping::ZeroFilled(2)
+        );
+        // between, below and above the segments
+        assert_eq!(
+            metadata.vaddr_to_offset(0x401234),
+            AddressMapping::Unmapped(Some(1), Some(2))
+        );
+        assert_eq!(
+            metadata.vaddr_to_offset(0x1000),
+            AddressMapping::Unmapped(None, Some(1))
+        );
+        assert_eq!(
+            metadata.vaddr_to_offset(0x404E00),
+            AddressMapping::Unmapped(Some(2), None)
+        );
+    }
+
+    #[test]
+    fn test_offset_to_vaddr() {
+        let metadata = segments();
+        assert_eq!(
+            metadata.offset_to_vaddr(0x40),
+            AddressMapping::Mapped(1, 0x400040)
+        );
+        assert_eq!(
+            metadata.offset_to_vaddr(0x1F00),
+            AddressMapping::Mapped(2, 0x403F00)
+        );
+        assert_eq!(
+            metadata.offset_to_vaddr(0x1300),
+            AddressMapping::Unmapped(Some(1), Some(2))
+        );
+        assert_eq!(
+            metadata.offset_to_vaddr(0x2000),
+            AddressMapping::Unmapped(Some(2), None)
+        );
+    }
+}
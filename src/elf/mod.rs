@@ -1,28 +1,139 @@
+mod arch_flags;
+mod archive;
+mod builder;
 mod bytes;
 mod common;
+mod compression;
+mod core_note;
+mod dynamic;
+mod edit;
+mod file;
+mod group;
+mod hash;
 mod header;
 mod metadata;
+mod note;
 mod program_header;
+mod relocation;
 mod section_header;
+mod strip;
+mod symbol;
+#[cfg(test)]
+mod testing;
+mod version;
+
+pub use arch_flags::ArchFlags;
+pub use arch_flags::ArmFlags;
+pub use arch_flags::ArmFloatAbi;
+pub use arch_flags::MipsAbi;
+pub use arch_flags::MipsFlags;
+pub use arch_flags::MipsIsa;
+pub use arch_flags::PowerPC64Abi;
+pub use arch_flags::PowerPC64Flags;
+pub use arch_flags::RiscVFlags;
+pub use arch_flags::RiscVFloatAbi;
+
+pub use archive::Archive;
+pub use archive::ArchiveMember;
+pub use archive::ArchiveMembers;
+pub use archive::ArchiveSymbol;
+pub use archive::ARCHIVE_MAGIC;
+
+pub use builder::BuilderSection;
+pub use builder::BuilderSymbol;
+pub use builder::ElfBuilder;
+pub use builder::SectionId;
+pub use builder::SymbolId;
 
 pub use bytes::FromBytesEndianned;
+pub use bytes::ToBytesEndianned;
+pub(crate) use common::align_up;
+pub(crate) use common::parse_string;
 pub use common::Abi;
 pub use common::Arch;
 pub use common::Endianness;
+pub use common::ErrorLocation;
 pub use common::FileType;
 pub use common::ParseError;
+pub use common::ParseWarning;
 pub use common::Result;
+pub use common::Structure;
+pub(crate) use common::Warnings;
 pub use common::Word;
 pub use common::WordWidth;
 
+pub use compression::decompress_section;
+pub use compression::decompress_zdebug;
+pub use compression::CompressionHeader;
+pub use compression::CompressionType;
+
+pub use core_note::signal_name;
+pub use core_note::AuxvEntry;
+pub use core_note::CoreNote;
+pub use core_note::FileMappings;
+pub use core_note::MappedFile;
+pub use core_note::PrPsInfo;
+pub use core_note::PrStatus;
+pub use core_note::SigInfo;
+
+pub use dynamic::DynamicEntry;
+pub use dynamic::DynamicFlags;
+pub use dynamic::DynamicFlags1;
+
+pub use edit::ElfEditor;
+
+pub use file::ElfFile;
+pub use file::ProgramHeaders;
+pub use file::SectionHeaders;
+
+pub use group::GroupFlags;
+pub use group::SectionGroup;
+
+pub use hash::gnu_hash;
+pub use hash::sysv_hash;
+pub use hash::GnuHashTable;
+pub use hash::HashTable;
+pub use hash::SysvHashTable;
+
 pub use header::Header;
+pub use header::HeaderCounts;
+pub use note::GnuAbiOs;
+pub use note::GnuAbiTag;
+pub use note::GnuProperty;
+pub use note::Note;
+pub use note::NoteDescriptor;
+pub use note::Notes;
+
 pub use program_header::ProgramHeader;
 pub use program_header::ProgramHeaderSegmentType;
 
+pub use relocation::relocation_type_name;
+pub use relocation::Relocation;
+pub use relocation::RelocationTable;
+pub use relocation::Relocations;
+
 pub use section_header::SectionHeader;
 pub use section_header::SectionHeaderFlags;
 pub use section_header::SectionHeaderType;
 pub use section_header::UnnamedSectionHeader;
 
+pub use strip::ElfStripper;
+
+pub use symbol::Symbol;
+pub use symbol::SymbolBinding;
+pub use symbol::SymbolSectionIndex;
+pub use symbol::SymbolType;
+pub use symbol::SymbolVisibility;
+pub use symbol::Symbols;
+
+pub use version::SymbolVersion;
+pub use version::SymbolVersions;
+pub use version::VersionDefinition;
+pub use version::VersionFlags;
+pub use version::VersionIndex;
+pub use version::VersionRequirement;
+pub use version::VersionRequirementEntry;
+
+pub use metadata::AddressMapping;
 pub use metadata::Metadata;
 pub use metadata::MetadataParseError;
@@ -0,0 +1,485 @@
+use std::fmt::{Display, Formatter};
+
+use super::*;
+
+pub(crate) const NT_GNU_ABI_TAG: u32 = 1;
+pub(crate) const NT_GNU_HWCAP: u32 = 2;
+pub(crate) const NT_GNU_BUILD_ID: u32 = 3;
+pub(crate) const NT_GNU_GOLD_VERSION: u32 = 4;
+pub(crate) const NT_GNU_PROPERTY_TYPE_0: u32 = 5;
+pub(crate) const NT_FDO_PACKAGING_METADATA: u32 = 0xCAFE1A7E;
+
+/// A single entry of a `Note` section or segment.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct Note<'data> {
+    name: &'data [u8],
+    typ: u32,
+    descriptor: &'data [u8],
+    word_width: WordWidth,
+    endianness: Endianness,
+}
+
+/// Iterates over the notes of a `Note` section or segment.
+#[derive(Debug, Clone)]
+pub struct Notes<'data> {
+    data: &'data [u8],
+    align: usize,
+    word_width: WordWidth,
+    endianness: Endianness,
+}
+
+/// The decoded descriptor of the known note types.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum NoteDescriptor<'data> {
+    GnuAbiTag(GnuAbiTag),
+    GnuBuildId(&'data [u8]),
+    GnuGoldVersion(&'data str),
+    GnuProperties(Vec<GnuProperty>),
+    FdoPackagingMetadata(&'data str),
+    Unknown(&'data [u8]),
+}
+
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum GnuAbiOs {
+    Linux,
+    Hurd,
+    Solaris,
+    FreeBSD,
+    Unknown(u32),
+}
+
+/// The minimum kernel version required by the file (`NT_GNU_ABI_TAG`)
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct GnuAbiTag {
+    pub os: GnuAbiOs,
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+/// An entry of a `NT_GNU_PROPERTY_TYPE_0` note
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum GnuProperty {
+    StackSize(u64),
+    NoCopyOnProtected,
+    X86Feature1And(u32),
+    X86Feature2Used(u32),
+    X86Isa1Needed(u32),
+    X86Isa1Used(u32),
+    AArch64Feature1And(u32),
+    Other(u32, Vec<u8>),
+}
+
+impl<'data> Note<'data> {
+    /// The owner of the note without the terminating NUL byte, e.g. `GNU`
+    pub fn name(&self) -> &'data [u8] {
+        self.name
+    }
+
+    pub fn name_str(&self) -> Option<&'data str> {
+        std::str::from_utf8(self.name).ok()
+    }
+
+    /// The note type, its meaning depends on the name
+    pub fn typ(&self) -> u32 {
+        self.typ
+    }
+
+    pub fn descriptor(&self) -> &'data [u8] {
+        self.descriptor
+    }
+
+    pub const fn word_width(&self) -> WordWidth {
+        self.word_width
+    }
+
+    pub const fn endianness(&self) -> Endianness {
+        self.endianness
+    }
+
+    /// Decodes the descriptor of known note types. Unknown notes return the raw descriptor.
+    pub fn decode(&self) -> Result<NoteDescriptor<'data>> {
+        let descriptor = self.descriptor;
+        let decoded = match (self.name, self.typ) {
+            (b"GNU", NT_GNU_ABI_TAG) => {
+                if descriptor.len() < 16 {
+                    return Err(ParseError::InsufficientNoteLength(descriptor.len()));
+                }
+                let field = |i: usize| u32::from_bytes(&descriptor[4 * i..], self.endianness);
+                NoteDescriptor::GnuAbiTag(GnuAbiTag {
+                    os: GnuAbiOs::from_u32(field(0)?),
+                    major: field(1)?,
+                    minor: field(2)?,
+                    patch: field(3)?,
+                })
+            }
+            (b"GNU", NT_GNU_BUILD_ID) => NoteDescriptor::GnuBuildId(descriptor),
+            (b"GNU", NT_GNU_GOLD_VERSION) => {
+                NoteDescriptor::GnuGoldVersion(parse_string(descriptor, 0)?)
+            }
+            (b"GNU", NT_GNU_PROPERTY_TYPE_0) => NoteDescriptor::GnuProperties(
+                GnuProperty::parse_all(descriptor, self.word_width, self.endianness)?,
+            ),
+            (b"FDO", NT_FDO_PACKAGING_METADATA) => {
+                NoteDescriptor::FdoPackagingMetadata(parse_string(descriptor, 0)?)
+            }
+            _ => NoteDescriptor::Unknown(descriptor),
+        };
+        Ok(decoded)
+    }
+
+    /// The name of the note type as printed by `readelf -n`
+    pub fn type_name(&self) -> Option<&'static str> {
+        match (self.name, self.typ) {
+            (b"GNU", NT_GNU_ABI_TAG) => Some("NT_GNU_ABI_TAG"),
+            (b"GNU", NT_GNU_HWCAP) => Some("NT_GNU_HWCAP"),
+            (b"GNU", NT_GNU_BUILD_ID) => Some("NT_GNU_BUILD_ID"),
+            (b"GNU", NT_GNU_GOLD_VERSION) => Some("NT_GNU_GOLD_VERSION"),
+            (b"GNU", NT_GNU_PROPERTY_TYPE_0) => Some("NT_GNU_PROPERTY_TYPE_0"),
+            (b"FDO", NT_FDO_PACKAGING_METADATA) => Some("FDO_PACKAGING_METADATA"),
+            (b"CORE", typ) => core_note::core_note_type_name(typ),
+            (b"LINUX", core_note::NT_X86_XSTATE) => {
+                Some("NT_X86_XSTATE (x86 XSAVE extended state)")
+            }
+            _ => None,
+        }
+    }
+}
+
+impl<'data> Notes<'data> {
+    /// Creates an iterator over the notes in `data`. Notes are either 4- or 8-byte aligned,
+    /// depending on the alignment of the containing section or segment.
+    pub fn new(
+        data: &'data [u8],
+        align: Word,
+        word_width: WordWidth,
+        endianness: Endianness,
+    ) -> Notes<'data> {
+        let align = if u64::from(align) == 8 { 8 } else { 4 };
+        Notes {
+            data,
+            align,
+            word_width,
+            endianness,
+        }
+    }
+
+    fn parse_next(&mut self) -> Result<Note<'data>> {
+        let data = self.data;
+        if data.len() < 12 {
+            return Err(ParseError::InsufficientNoteLength(data.len()));
+        }
+        let name_size = u32::from_bytes(data, self.endianness)? as usize;
+        let descriptor_size = u32::from_bytes(&data[4..], self.endianness)? as usize;
+        let typ = u32::from_bytes(&data[8..], self.endianness)?;
+        let name_end = 12 + name_size;
+        let descriptor_start = align_up(name_end as u64, self.align as u64);
+        let descriptor_end = descriptor_start.saturating_add(descriptor_size as u64);
+        if descriptor_end > data.len() as u64 {
+            return Err(ParseError::InsufficientNoteLength(data.len()));
+        }
+        // both are inside of data now
+        let (descriptor_start, descriptor_end) =
+            (descriptor_start as usize, descriptor_end as usize);
+        let name = &data[12..name_end];
+        // the name includes the terminating NUL byte, if there is one
+        let name = match name.split_last() {
+            Some((0, name)) => name,
+            _ => name,
+        };
+        let next = align_up(descriptor_end as u64, self.align as u64).min(data.len() as u64);
+        self.data = &data[next as usize..];
+        Ok(Note {
+            name,
+            typ,
+            descriptor: &data[descriptor_start..descriptor_end],
+            word_width: self.word_width,
+            endianness: self.endianness,
+        })
+    }
+}
+
+impl<'data> Iterator for Notes<'data> {
+    type Item = Result<Note<'data>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.data.is_empty() {
+            return None;
+        }
+        let note = self.parse_next();
+        if note.is_err() {
+            // the remaining data can't be interpreted without a valid note size
+            self.data = &[];
+        }
+        Some(note)
+    }
+}
+
+impl GnuAbiOs {
+    fn from_u32(i: u32) -> GnuAbiOs {
+        use GnuAbiOs::*;
+        match i {
+            0 => Linux,
+            1 => Hurd,
+            2 => Solaris,
+            3 => FreeBSD,
+            _ => Unknown(i),
+        }
+    }
+}
+
+impl GnuProperty {
+    fn parse_all(
+        bytes: &[u8],
+        word_width: WordWidth,
+        endianness: Endianness,
+    ) -> Result<Vec<GnuProperty>> {
+        let align = word_width.size();
+        let mut properties = Vec::new();
+        let mut rest = bytes;
+        while !rest.is_empty() {
+            if rest.len() < 8 {
+                return Err(ParseError::InsufficientNoteLength(rest.len()));
+            }
+            let typ = u32::from_bytes(rest, endianness)?;
+            let size = u32::from_bytes(&rest[4..], endianness)? as usize;
+            let data = rest
+                .get(8..8usize.saturating_add(size))
+                .ok_or(ParseError::InsufficientNoteLength(rest.len()))?;
+            properties.push(GnuProperty::parse(typ, data, word_width, endianness)?);
+            let next = align_up(8 + size as u64, align as u64).min(rest.len() as u64);
+            rest = &rest[next as usize..];
+        }
+        Ok(properties)
+    }
+
+    fn parse(
+        typ: u32,
+        data: &[u8],
+        word_width: WordWidth,
+        endianness: Endianness,
+    ) -> Result<GnuProperty> {
+        use GnuProperty::*;
+        let u32_value = || {
+            if data.len() < 4 {
+                Err(ParseError::InsufficientNoteLength(data.len()))
+            } else {
+                Ok(u32::from_bytes(data, endianness)?)
+            }
+        };
+        let property = match typ {
+            1 => StackSize(u64::from(Word::parse_bytes(data, word_width, endianness)?)),
+            2 => NoCopyOnProtected,
+            0xC0000000 => AArch64Feature1And(u32_value()?),
+            0xC0000002 => X86Feature1And(u32_value()?),
+            0xC0008002 => X86Isa1Needed(u32_value()?),
+            0xC0010001 => X86Feature2Used(u32_value()?),
+            0xC0010002 => X86Isa1Used(u32_value()?),
+            _ => Other(typ, data.to_vec()),
+        };
+        Ok(property)
+    }
+}
+
+impl<'data> Display for NoteDescriptor<'data> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NoteDescriptor::GnuAbiTag(tag) => write!(
+                f,
+                "OS: {}, ABI: {}.{}.{}",
+                tag.os, tag.major, tag.minor, tag.patch
+            ),
+            NoteDescriptor::GnuBuildId(id) => write!(f, "Build ID: {}", hex(id)),
+            NoteDescriptor::GnuGoldVersion(version) => write!(f, "Version: {}", version),
+            NoteDescriptor::GnuProperties(properties) => {
+                let properties: Vec<_> = properties.iter().map(|p| p.to_string()).collect();
+                write!(f, "Properties: {}", properties.join(", "))
+            }
+            NoteDescriptor::FdoPackagingMetadata(json) => write!(f, "Packaging Metadata: {}", json),
+            NoteDescriptor::Unknown(data) => write!(f, "description data: {}", hex(data)),
+        }
+    }
+}
+
+impl Display for GnuAbiOs {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            GnuAbiOs::Linux => write!(f, "Linux"),
+            GnuAbiOs::Hurd => write!(f, "Hurd"),
+            GnuAbiOs::Solaris => write!(f, "Solaris"),
+            GnuAbiOs::FreeBSD => write!(f, "FreeBSD"),
+            GnuAbiOs::Unknown(i) => write!(f, "Unknown OS {}", i),
+        }
+    }
+}
+
+impl Display for GnuProperty {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GnuProperty::StackSize(size) => write!(f, "stack size: {:#x}", size),
+            GnuProperty::NoCopyOnProtected => write!(f, "no copy on protected"),
+            GnuProperty::X86Feature1And(bits) => {
+                let mut names = Vec::new();
+                if bits & 0x1 != 0 {
+                    names.push("IBT");
+                }
+                if bits & 0x2 != 0 {
+                    names.push("SHSTK");
+                }
+                write!(f, "x86 feature: {}", names.join(", "))
+            }
+            GnuProperty::X86Feature2Used(bits) => write!(f, "x86 feature used: {:#x}", bits),
+            GnuProperty::X86Isa1Needed(bits) => write!(f, "x86 ISA needed: {:#x}", bits),
+            GnuProperty::X86Isa1Used(bits) => write!(f, "x86 ISA used: {:#x}", bits),
+            GnuProperty::AArch64Feature1And(bits) => {
+                let mut names = Vec::new();
+                if bits & 0x1 != 0 {
+                    names.push("BTI");
+                }
+                if bits & 0x2 != 0 {
+                    names.push("PAC");
+                }
+                write!(f, "AArch64 feature: {}", names.join(", "))
+            }
+            GnuProperty::Other(typ, data) => write!(f, "<type {:#x}>: {}", typ, hex(data)),
+        }
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::elf::testing::{Fixture, FixtureSection, Writer};
+
+    fn note(w: &mut Writer, align: u64, name: &[u8], typ: u32, descriptor: &[u8]) {
+        w.u32(name.len() as u32 + 1)
+            .u32(descriptor.len() as u32)
+            .u32(typ);
+        w.bytes(name).u8(0).align(align);
+        w.bytes(descriptor).align(align);
+    }
+
+    #[test]
+    fn test_notes_4_byte_aligned() {
+        let mut w = Writer::new(WordWidth::Width32, Endianness::Big);
+        note(
+            &mut w,
+            4,
+            b"GNU",
+            NT_GNU_BUILD_ID,
+            &[0xDE, 0xAD, 0xBE, 0xEF, 0x01],
+        );
+        let abi_tag: Vec<u8> = [0_u32, 3, 2, 0]
+            .iter()
+            .flat_map(|i| i.to_be_bytes().to_vec())
+            .collect();
+        note(&mut w, 4, b"GNU", NT_GNU_ABI_TAG, &abi_tag);
+        let notes: Vec<_> = Notes::new(
+            &w.bytes,
+            Word::Word32(4),
+            WordWidth::Width32,
+            Endianness::Big,
+        )
+        .collect::<Result<_>>()
+        .expect("valid notes");
+        assert_eq!(notes.len(), 2);
+        assert_eq!(notes[0].name(), b"GNU");
+        assert_eq!(
+            notes[0].decode(),
+            Ok(NoteDescriptor::GnuBuildId(&[0xDE, 0xAD, 0xBE, 0xEF, 0x01]))
+        );
+        assert_eq!(
+            notes[0].decode().expect("checked").to_string(),
+            "Build ID: deadbeef01"
+        );
+        assert_eq!(
+            notes[1].decode(),
+            Ok(NoteDescriptor::GnuAbiTag(GnuAbiTag {
+                os: GnuAbiOs::Linux,
+                major: 3,
+                minor: 2,
+                patch: 0
+            }))
+        );
+    }
+
+    #[test]
+    fn test_notes_8_byte_aligned() {
+        let mut properties = Writer::new(WordWidth::Width64, Endianness::Little);
+        properties.u32(0xC0000002).u32(4).u32(0x3).align(8);
+        properties.u32(0xC0008002).u32(4).u32(0x1).align(8);
+        let mut w = Writer::new(WordWidth::Width64, Endianness::Little);
+        note(&mut w, 8, b"GNU", NT_GNU_PROPERTY_TYPE_0, &properties.bytes);
+        note(
+            &mut w,
+            8,
+            b"FDO",
+            NT_FDO_PACKAGING_METADATA,
+            b"{\"type\":\"rpm\"}\0",
+        );
+        let notes: Vec<_> = Notes::new(
+            &w.bytes,
+            Word::Word64(8),
+            WordWidth::Width64,
+            Endianness::Little,
+        )
+        .collect::<Result<_>>()
+        .expect("valid notes");
+        assert_eq!(
+            notes[0].decode(),
+            Ok(NoteDescriptor::GnuProperties(vec![
+                GnuProperty::X86Feature1And(0x3),
+                GnuProperty::X86Isa1Needed(0x1),
+            ]))
+        );
+        assert_eq!(
+            notes[0].decode().expect("checked").to_string(),
+            "Properties: x86 feature: IBT, SHSTK, x86 ISA needed: 0x1"
+        );
+        assert_eq!(
+            notes[1].decode(),
+            Ok(NoteDescriptor::FdoPackagingMetadata("{\"type\":\"rpm\"}"))
+        );
+    }
+
+    #[test]
+    fn test_notes_truncated() {
+        let mut w = Writer::new(WordWidth::Width64, Endianness::Little);
+        note(&mut w, 4, b"GNU", NT_GNU_BUILD_ID, &[0x01; 20]);
+        let truncated = &w.bytes[..30];
+        let mut notes = Notes::new(
+            truncated,
+            Word::Word64(4),
+            WordWidth::Width64,
+            Endianness::Little,
+        );
+        assert_eq!(
+            notes.next(),
+            Some(Err(ParseError::InsufficientNoteLength(30)))
+        );
+        assert_eq!(notes.next(), None);
+    }
+
+    #[test]
+    fn test_build_id() {
+        let mut fixture = Fixture::new(WordWidth::Width64, Endianness::Little);
+        let mut w = fixture.writer();
+        note(&mut w, 4, b"GNU", NT_GNU_BUILD_ID, &[0xAB; 20]);
+        let index = fixture.section(
+            FixtureSection::new(".note.gnu.build-id", 7, w.bytes)
+                .flags(0x2)
+                .align(4),
+        );
+        fixture.segment(4, 0x4, index..index + 1, 4);
+        let bytes = fixture.build();
+        let elf = ElfFile::parse(&bytes).expect("valid ELF");
+        assert_eq!(elf.build_id(), Ok(Some(&[0xAB; 20][..])));
+        let notes = elf.notes().expect("valid notes");
+        assert_eq!(notes.len(), 1);
+        assert_eq!(notes[0].0, ".note.gnu.build-id");
+    }
+}
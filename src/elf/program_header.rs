@@ -12,6 +12,8 @@ pub enum ProgramHeaderSegmentType {
     ThreadLocalStorage,
     OSSpecific(u32),
     ProcessorSpecific(u32),
+    /// A value outside of the defined and the reserved ranges, only produced by lenient parsing
+    Unknown(u32),
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -27,6 +29,35 @@ pub struct ProgramHeader {
 }
 
 impl ProgramHeaderSegmentType {
+    /// `PT_GNU_EH_FRAME`, the location of the `.eh_frame_hdr` section
+    pub const GNU_EH_FRAME: ProgramHeaderSegmentType =
+        ProgramHeaderSegmentType::OSSpecific(0x6474E550);
+    /// `PT_GNU_STACK`, whose flags determine whether the stack is executable
+    pub const GNU_STACK: ProgramHeaderSegmentType =
+        ProgramHeaderSegmentType::OSSpecific(0x6474E551);
+    /// `PT_GNU_RELRO`, the part of the data made read-only after relocation
+    pub const GNU_RELRO: ProgramHeaderSegmentType =
+        ProgramHeaderSegmentType::OSSpecific(0x6474E552);
+    /// `PT_GNU_SFRAME`, the location of the `.sframe` section
+    pub const GNU_SFRAME: ProgramHeaderSegmentType =
+        ProgramHeaderSegmentType::OSSpecific(0x6474E554);
+
+    /// Whether segments of this type may only contain sections occupying memory at run time
+    fn contains_only_alloc(self) -> bool {
+        use ProgramHeaderSegmentType::*;
+        match self {
+            Load | Dynamic => true,
+            // PT_GNU_MBIND_LO..=PT_GNU_MBIND_HI
+            OSSpecific(i) if (0x6474E555..=0x6474F554).contains(&i) => true,
+            typ => {
+                typ == Self::GNU_EH_FRAME
+                    || typ == Self::GNU_STACK
+                    || typ == Self::GNU_RELRO
+                    || typ == Self::GNU_SFRAME
+            }
+        }
+    }
+
     fn parse_u32(u: u32) -> Result<ProgramHeaderSegmentType> {
         use ProgramHeaderSegmentType::*;
         match u {
@@ -44,6 +75,21 @@ impl ProgramHeaderSegmentType {
         }
     }
 
+    pub(crate) fn to_u32(self) -> u32 {
+        use ProgramHeaderSegmentType::*;
+        match self {
+            Null => 0x00000000,
+            Load => 0x00000001,
+            Dynamic => 0x00000002,
+            Interp => 0x00000003,
+            Note => 0x00000004,
+            SharedLib => 0x00000005,
+            HeaderSegment => 0x00000006,
+            ThreadLocalStorage => 0x00000007,
+            OSSpecific(i) | ProcessorSpecific(i) | Unknown(i) => i,
+        }
+    }
+
     pub(crate) fn parse_bytes(
         bytes: &[u8],
         endianness: Endianness,
@@ -51,14 +97,14 @@ impl ProgramHeaderSegmentType {
         if bytes.len() < 4 {
             Err(ParseError::InsufficientPartLength(bytes.len()))
         } else {
-            ProgramHeaderSegmentType::parse_u32(u32::from_bytes(bytes, endianness))
+            ProgramHeaderSegmentType::parse_u32(u32::from_bytes(bytes, endianness)?)
         }
     }
 }
 
-#[cfg(test)]
 impl ProgramHeader {
-    pub(crate) const fn new(
+    #[allow(clippy::too_many_arguments)]
+    pub const fn new(
         typ: ProgramHeaderSegmentType,
         offset: Word,
         vaddress: Word,
@@ -82,13 +128,36 @@ impl ProgramHeader {
 }
 
 impl ProgramHeader {
+    #[cfg(test)]
     pub(crate) fn parse_bytes(
         bytes: &[u8],
         word_width: WordWidth,
         endianness: Endianness,
+    ) -> Result<ProgramHeader> {
+        ProgramHeader::parse_bytes_with_warnings(
+            bytes,
+            word_width,
+            endianness,
+            &mut Warnings::strict(),
+        )
+    }
+
+    /// Parses the program header, reporting an unknown type, an invalid alignment and a
+    /// misaligned address to `warnings`.
+    pub(crate) fn parse_bytes_with_warnings(
+        bytes: &[u8],
+        word_width: WordWidth,
+        endianness: Endianness,
+        warnings: &mut Warnings,
     ) -> Result<ProgramHeader> {
         ProgramHeader::check_length(32, bytes.len())?;
-        let typ = ProgramHeaderSegmentType::parse_bytes(bytes, endianness)?;
+        let typ = match ProgramHeaderSegmentType::parse_bytes(bytes, endianness) {
+            Err(ParseError::InvalidProgramHeaderType(raw)) => {
+                warnings.report(0, Some("p_type"), ParseError::InvalidProgramHeaderType(raw))?;
+                ProgramHeaderSegmentType::Unknown(raw)
+            }
+            result => result?,
+        };
         // these are the word width dependent offsets of the fields:
         // [offset, vaddress, paddress, filesize, memsize, flags, alignment]
         let (offsets, size) = match word_width {
@@ -101,9 +170,15 @@ impl ProgramHeader {
         let paddress = Word::parse_bytes(&bytes[offsets[2]..], word_width, endianness)?;
         let filesize = Word::parse_bytes(&bytes[offsets[3]..], word_width, endianness)?;
         let memsize = Word::parse_bytes(&bytes[offsets[4]..], word_width, endianness)?;
-        let flags = u32::from_bytes(&bytes[offsets[5]..], endianness);
+        let flags = u32::from_bytes(&bytes[offsets[5]..], endianness)?;
         let alignment = Word::parse_bytes(&bytes[offsets[6]..], word_width, endianness)?;
-        ProgramHeader::validate_vaddr(offset, vaddress, alignment)?;
+        if let Err(error) = ProgramHeader::validate_vaddr(offset, vaddress, alignment) {
+            let (field_offset, field) = match error {
+                ParseError::InvalidAlignment(_) => (offsets[6], "p_align"),
+                _ => (offsets[1], "p_vaddr"),
+            };
+            warnings.report(field_offset, Some(field), error)?;
+        }
         Ok(ProgramHeader {
             typ,
             flags,
@@ -116,6 +191,155 @@ impl ProgramHeader {
         })
     }
 
+    /// Serializes the program header, the inverse of [`ProgramHeader::parse_bytes_with_warnings`].
+    pub fn to_bytes(&self, word_width: WordWidth, endianness: Endianness) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(56);
+        self.typ.to_u32().write_bytes(&mut bytes, endianness);
+        // the flags follow the type in 64-bit files and the file and memory sizes in 32-bit files
+        if word_width == WordWidth::Width64 {
+            self.flags.write_bytes(&mut bytes, endianness);
+        }
+        for word in [
+            self.offset,
+            self.vaddress,
+            self.paddress,
+            self.filesize,
+            self.memsize,
+        ]
+        .iter()
+        {
+            word.write_bytes(&mut bytes, word_width, endianness);
+        }
+        if word_width == WordWidth::Width32 {
+            self.flags.write_bytes(&mut bytes, endianness);
+        }
+        self.alignment
+            .write_bytes(&mut bytes, word_width, endianness);
+        bytes
+    }
+
+    pub const fn typ(&self) -> ProgramHeaderSegmentType {
+        self.typ
+    }
+
+    pub const fn flags(&self) -> u32 {
+        self.flags
+    }
+
+    pub const fn offset(&self) -> Word {
+        self.offset
+    }
+
+    pub const fn vaddress(&self) -> Word {
+        self.vaddress
+    }
+
+    pub const fn paddress(&self) -> Word {
+        self.paddress
+    }
+
+    pub const fn filesize(&self) -> Word {
+        self.filesize
+    }
+
+    pub const fn memsize(&self) -> Word {
+        self.memsize
+    }
+
+    pub const fn alignment(&self) -> Word {
+        self.alignment
+    }
+
+    /// Whether the section is part of this segment, following the rules of `readelf -l`:
+    ///
+    /// - Sections must lie within the file contents of the segment, except `NoData` sections
+    ///   which occupy no space in the file, and `Alloc` sections within its memory image.
+    /// - Thread local sections are only part of `ThreadLocalStorage`, `Load` and `GNU_RELRO`
+    ///   segments, and `ThreadLocalStorage` segments only contain thread local sections.
+    ///   Outside of the `ThreadLocalStorage` segment, `.tbss` occupies no memory, so it is not
+    ///   part of a `Load` segment either.
+    /// - `Load`, `Dynamic` and the GNU specific segments only contain `Alloc` sections, a
+    ///   `HeaderSegment` contains no sections at all.
+    /// - Empty sections at the boundaries of `Dynamic` and `Note` segments are not part of them.
+    pub fn contains_section(&self, sheader: &SectionHeader) -> bool {
+        use ProgramHeaderSegmentType::*;
+        let flags = sheader.flags();
+        let tls = flags.contains(SectionHeaderFlags::THREAD_LOCAL);
+        let alloc = flags.contains(SectionHeaderFlags::ALLOC);
+        let no_data = sheader.typ() == SectionHeaderType::NoData;
+        if tls && no_data && self.typ != ThreadLocalStorage {
+            return false;
+        }
+        let type_allowed = if tls {
+            matches!(self.typ, ThreadLocalStorage | Load)
+                || self.typ == ProgramHeaderSegmentType::GNU_RELRO
+        } else {
+            !matches!(self.typ, ThreadLocalStorage | HeaderSegment)
+        };
+        if !type_allowed || (!alloc && self.typ.contains_only_alloc()) {
+            return false;
+        }
+
+        let section_offset = u64::from(sheader.offset());
+        let section_address = u64::from(sheader.address());
+        let size = u64::from(sheader.size());
+        let offset = u64::from(self.offset);
+        let vaddress = u64::from(self.vaddress);
+        let filesize = u64::from(self.filesize);
+        let memsize = u64::from(self.memsize);
+        // a section of size 0 at the end of the segment is not part of it
+        let within = |start: u64, segment_start: u64, segment_size: u64| {
+            start >= segment_start
+                && start - segment_start <= segment_size.wrapping_sub(1)
+                && matches!(segment_size.checked_sub(start - segment_start), Some(rest) if size <= rest)
+        };
+        if !no_data && !within(section_offset, offset, filesize) {
+            return false;
+        }
+        if alloc && !within(section_address, vaddress, memsize) {
+            return false;
+        }
+        if matches!(self.typ, Dynamic | Note) && size == 0 && memsize != 0 {
+            let inside_file =
+                no_data || (section_offset > offset && section_offset - offset < filesize);
+            let inside_memory =
+                !alloc || (section_address > vaddress && section_address - vaddress < memsize);
+            return inside_file && inside_memory;
+        }
+        true
+    }
+
+    pub const fn with_offset(mut self, offset: Word) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    pub const fn with_vaddress(mut self, vaddress: Word) -> Self {
+        self.vaddress = vaddress;
+        self
+    }
+
+    pub const fn with_paddress(mut self, paddress: Word) -> Self {
+        self.paddress = paddress;
+        self
+    }
+
+    pub const fn with_filesize(mut self, filesize: Word) -> Self {
+        self.filesize = filesize;
+        self
+    }
+
+    pub const fn with_memsize(mut self, memsize: Word) -> Self {
+        self.memsize = memsize;
+        self
+    }
+
+    /// Extracts the interpreter path from the contents of an `Interp` segment. The path has to
+    /// be NUL-terminated within the segment.
+    pub fn parse_interpreter(segment: &[u8]) -> Result<&str> {
+        parse_string(segment, 0)
+    }
+
     fn check_length(expected: usize, actual: usize) -> Result<()> {
         if actual < expected {
             Err(ParseError::InsufficientProgramHeaderLength(actual))
@@ -145,11 +369,6 @@ impl ProgramHeader {
             if normalized_addr % align == offset % align {
                 Ok(())
             } else {
-                println!("Welp, RIP");
-                println!(
-                    "normalized_addr: {}, offset: {}, align: {}",
-                    normalized_addr, offset, align
-                );
                 Err(ParseError::InvalidVirtualAddress(addr))
             }
         }
@@ -248,21 +467,38 @@ mod test {
 
     #[test]
     fn test_pheader_32_ok() {
-        let test_data = VALID_PHEADER_DATA_32_LITTLE.clone();
+        let test_data = VALID_PHEADER_DATA_32_LITTLE;
         let result = ProgramHeader::parse_bytes(&test_data, WordWidth::Width32, Endianness::Little);
         assert_eq!(result, Ok(VALID_PHEADER_32.clone()));
     }
 
     #[test]
     fn test_pheader_64_ok() {
-        let test_data = VALID_PHEADER_DATA_64_LITTLE.clone();
+        let test_data = VALID_PHEADER_DATA_64_LITTLE;
         let result = ProgramHeader::parse_bytes(&test_data, WordWidth::Width64, Endianness::Little);
         assert_eq!(result, Ok(VALID_PHEADER_64.clone()));
     }
 
+    #[test]
+    fn test_pheader_to_bytes() {
+        assert_eq!(
+            VALID_PHEADER_32.to_bytes(WordWidth::Width32, Endianness::Little),
+            VALID_PHEADER_DATA_32_LITTLE.to_vec()
+        );
+        assert_eq!(
+            VALID_PHEADER_64.to_bytes(WordWidth::Width64, Endianness::Little),
+            VALID_PHEADER_DATA_64_LITTLE.to_vec()
+        );
+        let big = VALID_PHEADER_64.to_bytes(WordWidth::Width64, Endianness::Big);
+        assert_eq!(
+            ProgramHeader::parse_bytes(&big, WordWidth::Width64, Endianness::Big),
+            Ok(VALID_PHEADER_64.clone())
+        );
+    }
+
     #[test]
     fn test_pheader_err_type() {
-        let mut test_data = VALID_PHEADER_DATA_32_LITTLE.clone();
+        let mut test_data = VALID_PHEADER_DATA_32_LITTLE;
         test_data[0] = 0x08;
         let result = ProgramHeader::parse_bytes(&test_data, WordWidth::Width32, Endianness::Little);
         assert_eq!(
@@ -273,7 +509,7 @@ mod test {
 
     #[test]
     fn test_pheader_err_align() {
-        let mut test_data = VALID_PHEADER_DATA_32_LITTLE.clone();
+        let mut test_data = VALID_PHEADER_DATA_32_LITTLE;
         test_data[28] = 0x0F;
         let result = ProgramHeader::parse_bytes(&test_data, WordWidth::Width32, Endianness::Little);
         assert_eq!(
@@ -284,7 +520,7 @@ mod test {
 
     #[test]
     fn test_pheader_err_addr() {
-        let mut test_data = VALID_PHEADER_DATA_32_LITTLE.clone();
+        let mut test_data = VALID_PHEADER_DATA_32_LITTLE;
         test_data[8] = 0x01;
         let result = ProgramHeader::parse_bytes(&test_data, WordWidth::Width32, Endianness::Little);
         assert_eq!(
@@ -292,4 +528,94 @@ mod test {
             Err(ParseError::InvalidVirtualAddress(Word::Word32(0x445C0001)))
         );
     }
+
+    fn segment(
+        typ: ProgramHeaderSegmentType,
+        offset: u64,
+        filesize: u64,
+        memsize: u64,
+    ) -> ProgramHeader {
+        let address = 0x400000 + offset;
+        ProgramHeader::new(
+            typ,
+            Word::Word64(offset),
+            Word::Word64(address),
+            Word::Word64(address),
+            Word::Word64(filesize),
+            Word::Word64(memsize),
+            0x6,
+            Word::Word64(0x8),
+        )
+    }
+
+    fn section(typ: SectionHeaderType, flags: u64, offset: u64, size: u64) -> SectionHeader {
+        let address = if flags & 0x2 != 0 {
+            0x400000 + offset
+        } else {
+            0
+        };
+        UnnamedSectionHeader::new(
+            0,
+            typ,
+            SectionHeaderFlags::from_bits_truncate(flags),
+            Word::Word64(address),
+            Word::Word64(offset),
+            Word::Word64(size),
+            0,
+            0,
+            Word::Word64(8),
+            Word::Word64(0),
+        )
+        .to_named(b"\0")
+        .unwrap()
+    }
+
+    #[test]
+    fn test_contains_section_tls() {
+        use ProgramHeaderSegmentType::*;
+        // .tdata and .tbss, followed by .data and .bss
+        let tdata = section(SectionHeaderType::ProgramBits, 0x403, 0x1000, 0x10);
+        let tbss = section(SectionHeaderType::NoData, 0x403, 0x1010, 0x20);
+        let data = section(SectionHeaderType::ProgramBits, 0x3, 0x1010, 0x8);
+        let bss = section(SectionHeaderType::NoData, 0x3, 0x1018, 0x100);
+        let comment = section(SectionHeaderType::ProgramBits, 0x30, 0x1018, 0x10);
+
+        let load = segment(Load, 0x1000, 0x18, 0x118);
+        assert!(load.contains_section(&tdata));
+        assert!(!load.contains_section(&tbss));
+        assert!(load.contains_section(&data));
+        assert!(load.contains_section(&bss));
+        assert!(!load.contains_section(&comment));
+
+        let tls = segment(ThreadLocalStorage, 0x1000, 0x10, 0x30);
+        assert!(tls.contains_section(&tdata));
+        assert!(tls.contains_section(&tbss));
+        assert!(!tls.contains_section(&data));
+
+        let relro = segment(ProgramHeaderSegmentType::GNU_RELRO, 0x1000, 0x18, 0x18);
+        assert!(relro.contains_section(&tdata));
+        assert!(!relro.contains_section(&tbss));
+        assert!(relro.contains_section(&data));
+        assert!(!relro.contains_section(&bss));
+
+        assert!(!segment(HeaderSegment, 0x1000, 0x18, 0x18).contains_section(&data));
+    }
+
+    #[test]
+    fn test_contains_section_boundaries() {
+        use ProgramHeaderSegmentType::*;
+        let note = segment(Note, 0x200, 0x20, 0x20);
+        assert!(note.contains_section(&section(SectionHeaderType::Note, 0x2, 0x200, 0x20)));
+        assert!(!note.contains_section(&section(SectionHeaderType::Note, 0x2, 0x210, 0x20)));
+        // empty sections at the start or end of a note segment are not part of it
+        assert!(!note.contains_section(&section(SectionHeaderType::Note, 0x2, 0x200, 0)));
+        assert!(note.contains_section(&section(SectionHeaderType::Note, 0x2, 0x208, 0)));
+        // but those at the start of other segments are
+        let load = segment(Load, 0x200, 0x20, 0x20);
+        assert!(load.contains_section(&section(SectionHeaderType::ProgramBits, 0x2, 0x200, 0)));
+        assert!(!load.contains_section(&section(SectionHeaderType::ProgramBits, 0x2, 0x220, 0)));
+        // a Note segment may contain sections which are not loaded
+        let unloaded = segment(Note, 0x200, 0x20, 0);
+        assert!(unloaded.contains_section(&section(SectionHeaderType::Note, 0, 0x200, 0x20)));
+    }
 }
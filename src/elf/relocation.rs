@@ -0,0 +1,808 @@
+use std::convert::TryFrom;
+
+use super::*;
+
+/// An entry of a `Relocation` (REL) or `RelocationWithAddends` (RELA) section.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct Relocation {
+    offset: Word,
+    typ: u32,
+    symbol_index: u32,
+    addend: Option<i64>,
+}
+
+/// Lazily parses the entries of a relocation section.
+#[derive(Debug, Clone)]
+pub struct Relocations<'data> {
+    table: &'data [u8],
+    entry_size: usize,
+    with_addends: bool,
+    arch: Arch,
+    word_width: WordWidth,
+    endianness: Endianness,
+}
+
+/// A relocation section together with the sections referenced by its `link` (the symbol table)
+/// and `info` (the section the relocations are applied to) fields.
+#[derive(Debug, Clone)]
+pub struct RelocationTable<'data> {
+    section: SectionHeader,
+    target_section: Option<SectionHeader>,
+    symbols: Option<Symbols<'data>>,
+    entries: Relocations<'data>,
+}
+
+impl Relocation {
+    /// Parses a single relocation. `arch` is needed because 64-bit MIPS uses its own layout for
+    /// the `r_info` field.
+    pub fn parse_bytes(
+        bytes: &[u8],
+        with_addend: bool,
+        arch: Arch,
+        word_width: WordWidth,
+        endianness: Endianness,
+    ) -> Result<Relocation> {
+        let size = Relocation::entry_size(with_addend, word_width);
+        if bytes.len() < size {
+            return Err(ParseError::InsufficientRelocationLength(bytes.len()));
+        }
+        let word_size = word_width.size();
+        let offset = Word::parse_bytes(bytes, word_width, endianness)?;
+        let info = u64::from(Word::parse_bytes(
+            &bytes[word_size..],
+            word_width,
+            endianness,
+        )?);
+        let (symbol_index, typ) = match (word_width, arch, endianness) {
+            (WordWidth::Width32, _, _) => ((info >> 8) as u32, (info & 0xFF) as u32),
+            // r_sym is followed by the single byte fields r_ssym, r_type3, r_type2 and r_type.
+            // Only the primary type is decoded.
+            (WordWidth::Width64, Arch::Mips, Endianness::Little) => {
+                ((info & 0xFFFFFFFF) as u32, (info >> 56) as u32)
+            }
+            (WordWidth::Width64, Arch::Mips, Endianness::Big) => {
+                ((info >> 32) as u32, (info & 0xFF) as u32)
+            }
+            (WordWidth::Width64, _, _) => ((info >> 32) as u32, (info & 0xFFFFFFFF) as u32),
+        };
+        let addend = if with_addend {
+            let raw = Word::parse_bytes(&bytes[2 * word_size..], word_width, endianness)?;
+            Some(match raw {
+                Word::Word32(u) => u as i32 as i64,
+                Word::Word64(u) => u as i64,
+            })
+        } else {
+            None
+        };
+        Ok(Relocation {
+            offset,
+            typ,
+            symbol_index,
+            addend,
+        })
+    }
+
+    /// The size of a relocation entry in bytes
+    pub const fn entry_size(with_addend: bool, word_width: WordWidth) -> usize {
+        let words = if with_addend { 3 } else { 2 };
+        match word_width {
+            WordWidth::Width32 => words * 4,
+            WordWidth::Width64 => words * 8,
+        }
+    }
+
+    pub const fn offset(&self) -> Word {
+        self.offset
+    }
+
+    /// The architecture specific relocation type
+    pub const fn typ(&self) -> u32 {
+        self.typ
+    }
+
+    /// The index of the referenced symbol in the linked symbol table. 0 means no symbol.
+    pub const fn symbol_index(&self) -> u32 {
+        self.symbol_index
+    }
+
+    /// The explicit addend of RELA entries, REL entries store the addend in the relocated field.
+    pub const fn addend(&self) -> Option<i64> {
+        self.addend
+    }
+
+    /// Returns the name of the relocation type, e.g. `R_X86_64_JUMP_SLOT`.
+    pub fn type_name(&self, arch: Arch) -> Option<&'static str> {
+        relocation_type_name(arch, self.typ)
+    }
+}
+
+impl<'data> Relocations<'data> {
+    pub(crate) fn new(
+        table: &'data [u8],
+        entry_size: Word,
+        with_addends: bool,
+        arch: Arch,
+        word_width: WordWidth,
+        endianness: Endianness,
+    ) -> Relocations<'data> {
+        let entry_size = match usize::try_from(u64::from(entry_size)) {
+            Ok(0) | Err(_) => Relocation::entry_size(with_addends, word_width),
+            Ok(size) => size,
+        };
+        // a trailing partial entry is ignored
+        let table = &table[..table.len() - table.len() % entry_size];
+        Relocations {
+            table,
+            entry_size,
+            with_addends,
+            arch,
+            word_width,
+            endianness,
+        }
+    }
+}
+
+impl<'data> Iterator for Relocations<'data> {
+    type Item = Result<Relocation>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.table.is_empty() {
+            return None;
+        }
+        let (bytes, rest) = self.table.split_at(self.entry_size);
+        self.table = rest;
+        Some(Relocation::parse_bytes(
+            bytes,
+            self.with_addends,
+            self.arch,
+            self.word_width,
+            self.endianness,
+        ))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.table.len() / self.entry_size;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<'data> ExactSizeIterator for Relocations<'data> {}
+
+impl<'data> RelocationTable<'data> {
+    pub(crate) fn new(
+        section: SectionHeader,
+        target_section: Option<SectionHeader>,
+        symbols: Option<Symbols<'data>>,
+        entries: Relocations<'data>,
+    ) -> RelocationTable<'data> {
+        RelocationTable {
+            section,
+            target_section,
+            symbols,
+            entries,
+        }
+    }
+
+    /// The relocation section itself
+    pub fn section(&self) -> &SectionHeader {
+        &self.section
+    }
+
+    /// The section the relocations apply to. Dynamic relocations don't have a target section.
+    pub fn target_section(&self) -> Option<&SectionHeader> {
+        self.target_section.as_ref()
+    }
+
+    /// The symbol table the relocations refer to
+    pub fn symbols(&self) -> Option<&Symbols<'data>> {
+        self.symbols.as_ref()
+    }
+
+    pub fn entries(&self) -> Relocations<'data> {
+        self.entries.clone()
+    }
+
+    /// Looks up the symbol referenced by a relocation. Returns `None` for relocations without a
+    /// symbol.
+    pub fn symbol(&self, relocation: &Relocation) -> Option<Result<Symbol>> {
+        if relocation.symbol_index() == 0 {
+            return None;
+        }
+        let symbols = self.symbols.as_ref()?;
+        Some(
+            symbols
+                .get(relocation.symbol_index() as usize)
+                .unwrap_or(Err(ParseError::InvalidSymbolIndex(
+                    relocation.symbol_index(),
+                ))),
+        )
+    }
+}
+
+/// Returns the name of a relocation type for the given architecture.
+pub fn relocation_type_name(arch: Arch, typ: u32) -> Option<&'static str> {
+    let names: &[(u32, &str)] = match arch {
+        Arch::X86_64 => X86_64_RELOCATIONS,
+        Arch::X86 => X86_RELOCATIONS,
+        Arch::AArch64 => AARCH64_RELOCATIONS,
+        Arch::Arm => ARM_RELOCATIONS,
+        Arch::RiscV => RISCV_RELOCATIONS,
+        Arch::PowerPC64 => PPC64_RELOCATIONS,
+        Arch::Mips => MIPS_RELOCATIONS,
+        Arch::S390 => S390_RELOCATIONS,
+        _ => return None,
+    };
+    names
+        .iter()
+        .find(|(value, _)| *value == typ)
+        .map(|(_, name)| *name)
+}
+
+static X86_64_RELOCATIONS: &[(u32, &str)] = &[
+    (0, "R_X86_64_NONE"),
+    (1, "R_X86_64_64"),
+    (2, "R_X86_64_PC32"),
+    (3, "R_X86_64_GOT32"),
+    (4, "R_X86_64_PLT32"),
+    (5, "R_X86_64_COPY"),
+    (6, "R_X86_64_GLOB_DAT"),
+    (7, "R_X86_64_JUMP_SLOT"),
+    (8, "R_X86_64_RELATIVE"),
+    (9, "R_X86_64_GOTPCREL"),
+    (10, "R_X86_64_32"),
+    (11, "R_X86_64_32S"),
+    (12, "R_X86_64_16"),
+    (13, "R_X86_64_PC16"),
+    (14, "R_X86_64_8"),
+    (15, "R_X86_64_PC8"),
+    (16, "R_X86_64_DTPMOD64"),
+    (17, "R_X86_64_DTPOFF64"),
+    (18, "R_X86_64_TPOFF64"),
+    (19, "R_X86_64_TLSGD"),
+    (20, "R_X86_64_TLSLD"),
+    (21, "R_X86_64_DTPOFF32"),
+    (22, "R_X86_64_GOTTPOFF"),
+    (23, "R_X86_64_TPOFF32"),
+    (24, "R_X86_64_PC64"),
+    (25, "R_X86_64_GOTOFF64"),
+    (26, "R_X86_64_GOTPC32"),
+    (27, "R_X86_64_GOT64"),
+    (28, "R_X86_64_GOTPCREL64"),
+    (29, "R_X86_64_GOTPC64"),
+    (30, "R_X86_64_GOTPLT64"),
+    (31, "R_X86_64_PLTOFF64"),
+    (32, "R_X86_64_SIZE32"),
+    (33, "R_X86_64_SIZE64"),
+    (34, "R_X86_64_GOTPC32_TLSDESC"),
+    (35, "R_X86_64_TLSDESC_CALL"),
+    (36, "R_X86_64_TLSDESC"),
+    (37, "R_X86_64_IRELATIVE"),
+    (38, "R_X86_64_RELATIVE64"),
+    (41, "R_X86_64_GOTPCRELX"),
+    (42, "R_X86_64_REX_GOTPCRELX"),
+];
+
+static X86_RELOCATIONS: &[(u32, &str)] = &[
+    (0, "R_386_NONE"),
+    (1, "R_386_32"),
+    (2, "R_386_PC32"),
+    (3, "R_386_GOT32"),
+    (4, "R_386_PLT32"),
+    (5, "R_386_COPY"),
+    (6, "R_386_GLOB_DAT"),
+    (7, "R_386_JUMP_SLOT"),
+    (8, "R_386_RELATIVE"),
+    (9, "R_386_GOTOFF"),
+    (10, "R_386_GOTPC"),
+    (11, "R_386_32PLT"),
+    (14, "R_386_TLS_TPOFF"),
+    (15, "R_386_TLS_IE"),
+    (16, "R_386_TLS_GOTIE"),
+    (17, "R_386_TLS_LE"),
+    (18, "R_386_TLS_GD"),
+    (19, "R_386_TLS_LDM"),
+    (20, "R_386_16"),
+    (21, "R_386_PC16"),
+    (22, "R_386_8"),
+    (23, "R_386_PC8"),
+    (24, "R_386_TLS_GD_32"),
+    (25, "R_386_TLS_GD_PUSH"),
+    (26, "R_386_TLS_GD_CALL"),
+    (27, "R_386_TLS_GD_POP"),
+    (28, "R_386_TLS_LDM_32"),
+    (29, "R_386_TLS_LDM_PUSH"),
+    (30, "R_386_TLS_LDM_CALL"),
+    (31, "R_386_TLS_LDM_POP"),
+    (32, "R_386_TLS_LDO_32"),
+    (33, "R_386_TLS_IE_32"),
+    (34, "R_386_TLS_LE_32"),
+    (35, "R_386_TLS_DTPMOD32"),
+    (36, "R_386_TLS_DTPOFF32"),
+    (37, "R_386_TLS_TPOFF32"),
+    (38, "R_386_SIZE32"),
+    (39, "R_386_TLS_GOTDESC"),
+    (40, "R_386_TLS_DESC_CALL"),
+    (41, "R_386_TLS_DESC"),
+    (42, "R_386_IRELATIVE"),
+    (43, "R_386_GOT32X"),
+];
+
+static AARCH64_RELOCATIONS: &[(u32, &str)] = &[
+    (0, "R_AARCH64_NONE"),
+    (257, "R_AARCH64_ABS64"),
+    (258, "R_AARCH64_ABS32"),
+    (259, "R_AARCH64_ABS16"),
+    (260, "R_AARCH64_PREL64"),
+    (261, "R_AARCH64_PREL32"),
+    (262, "R_AARCH64_PREL16"),
+    (263, "R_AARCH64_MOVW_UABS_G0"),
+    (264, "R_AARCH64_MOVW_UABS_G0_NC"),
+    (265, "R_AARCH64_MOVW_UABS_G1"),
+    (266, "R_AARCH64_MOVW_UABS_G1_NC"),
+    (267, "R_AARCH64_MOVW_UABS_G2"),
+    (268, "R_AARCH64_MOVW_UABS_G2_NC"),
+    (269, "R_AARCH64_MOVW_UABS_G3"),
+    (270, "R_AARCH64_MOVW_SABS_G0"),
+    (271, "R_AARCH64_MOVW_SABS_G1"),
+    (272, "R_AARCH64_MOVW_SABS_G2"),
+    (273, "R_AARCH64_LD_PREL_LO19"),
+    (274, "R_AARCH64_ADR_PREL_LO21"),
+    (275, "R_AARCH64_ADR_PREL_PG_HI21"),
+    (276, "R_AARCH64_ADR_PREL_PG_HI21_NC"),
+    (277, "R_AARCH64_ADD_ABS_LO12_NC"),
+    (278, "R_AARCH64_LDST8_ABS_LO12_NC"),
+    (279, "R_AARCH64_TSTBR14"),
+    (280, "R_AARCH64_CONDBR19"),
+    (282, "R_AARCH64_JUMP26"),
+    (283, "R_AARCH64_CALL26"),
+    (284, "R_AARCH64_LDST16_ABS_LO12_NC"),
+    (285, "R_AARCH64_LDST32_ABS_LO12_NC"),
+    (286, "R_AARCH64_LDST64_ABS_LO12_NC"),
+    (299, "R_AARCH64_LDST128_ABS_LO12_NC"),
+    (311, "R_AARCH64_ADR_GOT_PAGE"),
+    (312, "R_AARCH64_LD64_GOT_LO12_NC"),
+    (512, "R_AARCH64_TLSGD_ADR_PREL21"),
+    (513, "R_AARCH64_TLSGD_ADR_PAGE21"),
+    (514, "R_AARCH64_TLSGD_ADD_LO12_NC"),
+    (541, "R_AARCH64_TLSIE_ADR_GOTTPREL_PAGE21"),
+    (542, "R_AARCH64_TLSIE_LD64_GOTTPREL_LO12_NC"),
+    (549, "R_AARCH64_TLSLE_ADD_TPREL_HI12"),
+    (551, "R_AARCH64_TLSLE_ADD_TPREL_LO12_NC"),
+    (560, "R_AARCH64_TLSDESC_LD_PREL19"),
+    (562, "R_AARCH64_TLSDESC_ADR_PAGE21"),
+    (563, "R_AARCH64_TLSDESC_LD64_LO12"),
+    (564, "R_AARCH64_TLSDESC_ADD_LO12"),
+    (569, "R_AARCH64_TLSDESC_CALL"),
+    (1024, "R_AARCH64_COPY"),
+    (1025, "R_AARCH64_GLOB_DAT"),
+    (1026, "R_AARCH64_JUMP_SLOT"),
+    (1027, "R_AARCH64_RELATIVE"),
+    (1028, "R_AARCH64_TLS_DTPMOD64"),
+    (1029, "R_AARCH64_TLS_DTPREL64"),
+    (1030, "R_AARCH64_TLS_TPREL64"),
+    (1031, "R_AARCH64_TLSDESC"),
+    (1032, "R_AARCH64_IRELATIVE"),
+];
+
+static ARM_RELOCATIONS: &[(u32, &str)] = &[
+    (0, "R_ARM_NONE"),
+    (1, "R_ARM_PC24"),
+    (2, "R_ARM_ABS32"),
+    (3, "R_ARM_REL32"),
+    (4, "R_ARM_LDR_PC_G0"),
+    (5, "R_ARM_ABS16"),
+    (6, "R_ARM_ABS12"),
+    (7, "R_ARM_THM_ABS5"),
+    (8, "R_ARM_ABS8"),
+    (9, "R_ARM_SBREL32"),
+    (10, "R_ARM_THM_CALL"),
+    (11, "R_ARM_THM_PC8"),
+    (12, "R_ARM_BREL_ADJ"),
+    (13, "R_ARM_TLS_DESC"),
+    (17, "R_ARM_TLS_DTPMOD32"),
+    (18, "R_ARM_TLS_DTPOFF32"),
+    (19, "R_ARM_TLS_TPOFF32"),
+    (20, "R_ARM_COPY"),
+    (21, "R_ARM_GLOB_DAT"),
+    (22, "R_ARM_JUMP_SLOT"),
+    (23, "R_ARM_RELATIVE"),
+    (24, "R_ARM_GOTOFF32"),
+    (25, "R_ARM_BASE_PREL"),
+    (26, "R_ARM_GOT_BREL"),
+    (27, "R_ARM_PLT32"),
+    (28, "R_ARM_CALL"),
+    (29, "R_ARM_JUMP24"),
+    (30, "R_ARM_THM_JUMP24"),
+    (31, "R_ARM_BASE_ABS"),
+    (38, "R_ARM_TARGET1"),
+    (40, "R_ARM_V4BX"),
+    (41, "R_ARM_TARGET2"),
+    (42, "R_ARM_PREL31"),
+    (43, "R_ARM_MOVW_ABS_NC"),
+    (44, "R_ARM_MOVT_ABS"),
+    (45, "R_ARM_MOVW_PREL_NC"),
+    (46, "R_ARM_MOVT_PREL"),
+    (47, "R_ARM_THM_MOVW_ABS_NC"),
+    (48, "R_ARM_THM_MOVT_ABS"),
+    (102, "R_ARM_THM_JUMP11"),
+    (103, "R_ARM_THM_JUMP8"),
+    (104, "R_ARM_TLS_GD32"),
+    (105, "R_ARM_TLS_LDM32"),
+    (106, "R_ARM_TLS_LDO32"),
+    (107, "R_ARM_TLS_IE32"),
+    (108, "R_ARM_TLS_LE32"),
+    (160, "R_ARM_IRELATIVE"),
+];
+
+static RISCV_RELOCATIONS: &[(u32, &str)] = &[
+    (0, "R_RISCV_NONE"),
+    (1, "R_RISCV_32"),
+    (2, "R_RISCV_64"),
+    (3, "R_RISCV_RELATIVE"),
+    (4, "R_RISCV_COPY"),
+    (5, "R_RISCV_JUMP_SLOT"),
+    (6, "R_RISCV_TLS_DTPMOD32"),
+    (7, "R_RISCV_TLS_DTPMOD64"),
+    (8, "R_RISCV_TLS_DTPREL32"),
+    (9, "R_RISCV_TLS_DTPREL64"),
+    (10, "R_RISCV_TLS_TPREL32"),
+    (11, "R_RISCV_TLS_TPREL64"),
+    (12, "R_RISCV_TLSDESC"),
+    (16, "R_RISCV_BRANCH"),
+    (17, "R_RISCV_JAL"),
+    (18, "R_RISCV_CALL"),
+    (19, "R_RISCV_CALL_PLT"),
+    (20, "R_RISCV_GOT_HI20"),
+    (21, "R_RISCV_TLS_GOT_HI20"),
+    (22, "R_RISCV_TLS_GD_HI20"),
+    (23, "R_RISCV_PCREL_HI20"),
+    (24, "R_RISCV_PCREL_LO12_I"),
+    (25, "R_RISCV_PCREL_LO12_S"),
+    (26, "R_RISCV_HI20"),
+    (27, "R_RISCV_LO12_I"),
+    (28, "R_RISCV_LO12_S"),
+    (29, "R_RISCV_TPREL_HI20"),
+    (30, "R_RISCV_TPREL_LO12_I"),
+    (31, "R_RISCV_TPREL_LO12_S"),
+    (32, "R_RISCV_TPREL_ADD"),
+    (33, "R_RISCV_ADD8"),
+    (34, "R_RISCV_ADD16"),
+    (35, "R_RISCV_ADD32"),
+    (36, "R_RISCV_ADD64"),
+    (37, "R_RISCV_SUB8"),
+    (38, "R_RISCV_SUB16"),
+    (39, "R_RISCV_SUB32"),
+    (40, "R_RISCV_SUB64"),
+    (43, "R_RISCV_ALIGN"),
+    (44, "R_RISCV_RVC_BRANCH"),
+    (45, "R_RISCV_RVC_JUMP"),
+    (51, "R_RISCV_RELAX"),
+    (52, "R_RISCV_SUB6"),
+    (53, "R_RISCV_SET6"),
+    (54, "R_RISCV_SET8"),
+    (55, "R_RISCV_SET16"),
+    (56, "R_RISCV_SET32"),
+    (57, "R_RISCV_32_PCREL"),
+    (58, "R_RISCV_IRELATIVE"),
+    (59, "R_RISCV_PLT32"),
+    (60, "R_RISCV_SET_ULEB128"),
+    (61, "R_RISCV_SUB_ULEB128"),
+];
+
+static PPC64_RELOCATIONS: &[(u32, &str)] = &[
+    (0, "R_PPC64_NONE"),
+    (1, "R_PPC64_ADDR32"),
+    (2, "R_PPC64_ADDR24"),
+    (3, "R_PPC64_ADDR16"),
+    (4, "R_PPC64_ADDR16_LO"),
+    (5, "R_PPC64_ADDR16_HI"),
+    (6, "R_PPC64_ADDR16_HA"),
+    (7, "R_PPC64_ADDR14"),
+    (10, "R_PPC64_REL24"),
+    (11, "R_PPC64_REL14"),
+    (14, "R_PPC64_GOT16"),
+    (15, "R_PPC64_GOT16_LO"),
+    (16, "R_PPC64_GOT16_HI"),
+    (17, "R_PPC64_GOT16_HA"),
+    (19, "R_PPC64_COPY"),
+    (20, "R_PPC64_GLOB_DAT"),
+    (21, "R_PPC64_JMP_SLOT"),
+    (22, "R_PPC64_RELATIVE"),
+    (24, "R_PPC64_UADDR32"),
+    (25, "R_PPC64_UADDR16"),
+    (26, "R_PPC64_REL32"),
+    (38, "R_PPC64_ADDR64"),
+    (39, "R_PPC64_ADDR16_HIGHER"),
+    (40, "R_PPC64_ADDR16_HIGHERA"),
+    (41, "R_PPC64_ADDR16_HIGHEST"),
+    (42, "R_PPC64_ADDR16_HIGHESTA"),
+    (43, "R_PPC64_UADDR64"),
+    (44, "R_PPC64_REL64"),
+    (47, "R_PPC64_TOC16"),
+    (48, "R_PPC64_TOC16_LO"),
+    (49, "R_PPC64_TOC16_HI"),
+    (50, "R_PPC64_TOC16_HA"),
+    (51, "R_PPC64_TOC"),
+    (56, "R_PPC64_ADDR16_DS"),
+    (57, "R_PPC64_ADDR16_LO_DS"),
+    (58, "R_PPC64_GOT16_DS"),
+    (59, "R_PPC64_GOT16_LO_DS"),
+    (63, "R_PPC64_TOC16_DS"),
+    (64, "R_PPC64_TOC16_LO_DS"),
+    (67, "R_PPC64_TLS"),
+    (68, "R_PPC64_DTPMOD64"),
+    (69, "R_PPC64_TPREL16"),
+    (73, "R_PPC64_TPREL64"),
+    (78, "R_PPC64_DTPREL64"),
+    (79, "R_PPC64_GOT_TLSGD16"),
+    (87, "R_PPC64_GOT_TPREL16_DS"),
+    (107, "R_PPC64_TLSGD"),
+    (108, "R_PPC64_TLSLD"),
+    (109, "R_PPC64_TOCSAVE"),
+    (110, "R_PPC64_ADDR16_HIGH"),
+    (111, "R_PPC64_ADDR16_HIGHA"),
+    (116, "R_PPC64_REL24_NOTOC"),
+    (118, "R_PPC64_ENTRY"),
+    (247, "R_PPC64_JMP_IREL"),
+    (248, "R_PPC64_IRELATIVE"),
+    (249, "R_PPC64_REL16"),
+    (250, "R_PPC64_REL16_LO"),
+    (251, "R_PPC64_REL16_HI"),
+    (252, "R_PPC64_REL16_HA"),
+];
+
+static MIPS_RELOCATIONS: &[(u32, &str)] = &[
+    (0, "R_MIPS_NONE"),
+    (1, "R_MIPS_16"),
+    (2, "R_MIPS_32"),
+    (3, "R_MIPS_REL32"),
+    (4, "R_MIPS_26"),
+    (5, "R_MIPS_HI16"),
+    (6, "R_MIPS_LO16"),
+    (7, "R_MIPS_GPREL16"),
+    (8, "R_MIPS_LITERAL"),
+    (9, "R_MIPS_GOT16"),
+    (10, "R_MIPS_PC16"),
+    (11, "R_MIPS_CALL16"),
+    (12, "R_MIPS_GPREL32"),
+    (16, "R_MIPS_SHIFT5"),
+    (17, "R_MIPS_SHIFT6"),
+    (18, "R_MIPS_64"),
+    (19, "R_MIPS_GOT_DISP"),
+    (20, "R_MIPS_GOT_PAGE"),
+    (21, "R_MIPS_GOT_OFST"),
+    (22, "R_MIPS_GOT_HI16"),
+    (23, "R_MIPS_GOT_LO16"),
+    (24, "R_MIPS_SUB"),
+    (25, "R_MIPS_INSERT_A"),
+    (26, "R_MIPS_INSERT_B"),
+    (27, "R_MIPS_DELETE"),
+    (28, "R_MIPS_HIGHER"),
+    (29, "R_MIPS_HIGHEST"),
+    (30, "R_MIPS_CALL_HI16"),
+    (31, "R_MIPS_CALL_LO16"),
+    (32, "R_MIPS_SCN_DISP"),
+    (33, "R_MIPS_REL16"),
+    (34, "R_MIPS_ADD_IMMEDIATE"),
+    (35, "R_MIPS_PJUMP"),
+    (36, "R_MIPS_RELGOT"),
+    (37, "R_MIPS_JALR"),
+    (38, "R_MIPS_TLS_DTPMOD32"),
+    (39, "R_MIPS_TLS_DTPREL32"),
+    (40, "R_MIPS_TLS_DTPMOD64"),
+    (41, "R_MIPS_TLS_DTPREL64"),
+    (42, "R_MIPS_TLS_GD"),
+    (43, "R_MIPS_TLS_LDM"),
+    (44, "R_MIPS_TLS_DTPREL_HI16"),
+    (45, "R_MIPS_TLS_DTPREL_LO16"),
+    (46, "R_MIPS_TLS_GOTTPREL"),
+    (47, "R_MIPS_TLS_TPREL32"),
+    (48, "R_MIPS_TLS_TPREL64"),
+    (49, "R_MIPS_TLS_TPREL_HI16"),
+    (50, "R_MIPS_TLS_TPREL_LO16"),
+    (51, "R_MIPS_GLOB_DAT"),
+    (126, "R_MIPS_COPY"),
+    (127, "R_MIPS_JUMP_SLOT"),
+];
+
+static S390_RELOCATIONS: &[(u32, &str)] = &[
+    (0, "R_390_NONE"),
+    (1, "R_390_8"),
+    (2, "R_390_12"),
+    (3, "R_390_16"),
+    (4, "R_390_32"),
+    (5, "R_390_PC32"),
+    (6, "R_390_GOT12"),
+    (7, "R_390_GOT32"),
+    (8, "R_390_PLT32"),
+    (9, "R_390_COPY"),
+    (10, "R_390_GLOB_DAT"),
+    (11, "R_390_JMP_SLOT"),
+    (12, "R_390_RELATIVE"),
+    (13, "R_390_GOTOFF32"),
+    (14, "R_390_GOTPC"),
+    (15, "R_390_GOT16"),
+    (16, "R_390_PC16"),
+    (17, "R_390_PC16DBL"),
+    (18, "R_390_PLT16DBL"),
+    (19, "R_390_PC32DBL"),
+    (20, "R_390_PLT32DBL"),
+    (21, "R_390_GOTPCDBL"),
+    (22, "R_390_64"),
+    (23, "R_390_PC64"),
+    (24, "R_390_GOT64"),
+    (25, "R_390_PLT64"),
+    (26, "R_390_GOTENT"),
+    (27, "R_390_GOTOFF16"),
+    (28, "R_390_GOTOFF64"),
+    (29, "R_390_GOTPLT12"),
+    (30, "R_390_GOTPLT16"),
+    (31, "R_390_GOTPLT32"),
+    (32, "R_390_GOTPLT64"),
+    (33, "R_390_GOTPLTENT"),
+    (34, "R_390_PLTOFF16"),
+    (35, "R_390_PLTOFF32"),
+    (36, "R_390_PLTOFF64"),
+    (37, "R_390_TLS_LOAD"),
+    (38, "R_390_TLS_GDCALL"),
+    (39, "R_390_TLS_LDCALL"),
+    (40, "R_390_TLS_GD32"),
+    (41, "R_390_TLS_GD64"),
+    (42, "R_390_TLS_GOTIE12"),
+    (43, "R_390_TLS_GOTIE32"),
+    (44, "R_390_TLS_GOTIE64"),
+    (45, "R_390_TLS_LDM32"),
+    (46, "R_390_TLS_LDM64"),
+    (47, "R_390_TLS_IE32"),
+    (48, "R_390_TLS_IE64"),
+    (49, "R_390_TLS_IEENT"),
+    (50, "R_390_TLS_LE32"),
+    (51, "R_390_TLS_LE64"),
+    (52, "R_390_TLS_LDO32"),
+    (53, "R_390_TLS_LDO64"),
+    (54, "R_390_TLS_DTPMOD"),
+    (55, "R_390_TLS_DTPOFF"),
+    (56, "R_390_TLS_TPOFF"),
+    (57, "R_390_20"),
+    (58, "R_390_GOT20"),
+    (59, "R_390_GOTPLT20"),
+    (60, "R_390_TLS_GOTIE20"),
+    (61, "R_390_IRELATIVE"),
+    (62, "R_390_PC12DBL"),
+    (63, "R_390_PLT12DBL"),
+    (64, "R_390_PC24DBL"),
+    (65, "R_390_PLT24DBL"),
+];
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::elf::testing::{string_table, Fixture, FixtureSection, Writer};
+
+    #[test]
+    fn test_relocation_layouts() {
+        let test_data = [
+            (
+                WordWidth::Width32,
+                Endianness::Little,
+                Arch::X86,
+                0x0502_u64,
+            ),
+            (WordWidth::Width32, Endianness::Big, Arch::PowerPC, 0x0502),
+            (
+                WordWidth::Width64,
+                Endianness::Little,
+                Arch::X86_64,
+                0x5_0000_0002,
+            ),
+            (
+                WordWidth::Width64,
+                Endianness::Big,
+                Arch::S390,
+                0x5_0000_0002,
+            ),
+        ];
+        for (word_width, endianness, arch, info) in test_data.iter() {
+            let mut w = Writer::new(*word_width, *endianness);
+            w.word(0x1018).word(*info).word(-8_i64 as u64);
+            let rela = Relocation::parse_bytes(&w.bytes, true, *arch, *word_width, *endianness)
+                .expect("valid relocation");
+            assert_eq!(u64::from(rela.offset()), 0x1018);
+            assert_eq!(rela.symbol_index(), 5);
+            assert_eq!(rela.typ(), 2);
+            assert_eq!(rela.addend(), Some(-8));
+            let rel = Relocation::parse_bytes(&w.bytes, false, *arch, *word_width, *endianness)
+                .expect("valid relocation");
+            assert_eq!(rel.addend(), None);
+            assert_eq!(
+                Relocation::parse_bytes(&w.bytes[..5], false, *arch, *word_width, *endianness),
+                Err(ParseError::InsufficientRelocationLength(5))
+            );
+        }
+    }
+
+    #[test]
+    fn test_relocation_mips64() {
+        // r_sym = 7, r_ssym = 0, r_type3 = 0, r_type2 = 0, r_type = R_MIPS_REL32
+        let mut w = Writer::new(WordWidth::Width64, Endianness::Little);
+        w.u64(0x20).u32(7).bytes(&[0, 0, 0, 3]);
+        let rel = Relocation::parse_bytes(
+            &w.bytes,
+            false,
+            Arch::Mips,
+            WordWidth::Width64,
+            Endianness::Little,
+        )
+        .expect("valid relocation");
+        assert_eq!((rel.symbol_index(), rel.typ()), (7, 3));
+        assert_eq!(rel.type_name(Arch::Mips), Some("R_MIPS_REL32"));
+    }
+
+    #[test]
+    fn test_relocation_type_names() {
+        let test_data = [
+            (Arch::X86_64, 7, Some("R_X86_64_JUMP_SLOT")),
+            (Arch::X86, 8, Some("R_386_RELATIVE")),
+            (Arch::AArch64, 1026, Some("R_AARCH64_JUMP_SLOT")),
+            (Arch::Arm, 22, Some("R_ARM_JUMP_SLOT")),
+            (Arch::RiscV, 5, Some("R_RISCV_JUMP_SLOT")),
+            (Arch::PowerPC64, 21, Some("R_PPC64_JMP_SLOT")),
+            (Arch::Mips, 127, Some("R_MIPS_JUMP_SLOT")),
+            (Arch::S390, 11, Some("R_390_JMP_SLOT")),
+            (Arch::X86_64, 1000, None),
+            (Arch::Sparc, 1, None),
+        ];
+        for (arch, typ, expected) in test_data.iter() {
+            assert_eq!(relocation_type_name(*arch, *typ), *expected);
+        }
+    }
+
+    #[test]
+    fn test_relocation_table() {
+        let mut fixture = Fixture::new(WordWidth::Width64, Endianness::Little);
+        let text =
+            fixture.section(FixtureSection::new(".text", 1, vec![0xE8, 0, 0, 0, 0]).flags(0x6));
+        let (names, indices) = string_table(&["puts"]);
+        let strtab = fixture.section(FixtureSection::new(".strtab", 3, names));
+        let mut w = fixture.writer();
+        w.bytes(&[0; 24]);
+        w.u32(indices[0]).u8(0x10).u8(0).u16(0).u64(0).u64(0);
+        let symtab = fixture.section(
+            FixtureSection::new(".symtab", 2, w.bytes)
+                .link(strtab as u32)
+                .info(1)
+                .entry_size(24),
+        );
+        let mut w = fixture.writer();
+        w.u64(1).u64((1 << 32) | 4).u64(-4_i64 as u64);
+        fixture.section(
+            FixtureSection::new(".rela.text", 4, w.bytes)
+                .flags(0x40)
+                .link(symtab as u32)
+                .info(text as u32)
+                .entry_size(24),
+        );
+        let bytes = fixture.build();
+        let elf = ElfFile::parse(&bytes).expect("valid ELF");
+        let tables = elf.relocation_tables().expect("valid relocation tables");
+        assert_eq!(tables.len(), 1);
+        let table = &tables[0];
+        assert_eq!(table.section().name(), ".rela.text");
+        assert_eq!(table.target_section().map(|s| s.name()), Some(".text"));
+        let relocations: Vec<_> = table
+            .entries()
+            .collect::<Result<_>>()
+            .expect("valid relocations");
+        assert_eq!(relocations.len(), 1);
+        assert_eq!(
+            relocations[0].type_name(Arch::X86_64),
+            Some("R_X86_64_PLT32")
+        );
+        assert_eq!(relocations[0].addend(), Some(-4));
+        let symbol = table
+            .symbol(&relocations[0])
+            .expect("has a symbol")
+            .expect("valid symbol");
+        assert_eq!(symbol.name(), "puts");
+    }
+}
@@ -1,6 +1,8 @@
 use std::ffi::CString;
 
-use super::{Endianness, FromBytesEndianned, ParseError, Result, Word, WordWidth};
+use super::{
+    Endianness, FromBytesEndianned, ParseError, Result, ToBytesEndianned, Warnings, Word, WordWidth,
+};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SectionHeaderType {
@@ -23,6 +25,8 @@ pub enum SectionHeaderType {
     SectionIndices,
     Num,
     OsSpecific(u32),
+    /// A value outside of the defined and the reserved ranges, only produced by lenient parsing
+    Unknown(u32),
 }
 
 bitflags::bitflags! {
@@ -37,6 +41,7 @@ bitflags::bitflags! {
         const OS_NONCONFORMING = 0x100;
         const GROUP = 0x200;
         const THREAD_LOCAL = 0x400;
+        const COMPRESSED = 0x800;
         const MASK_OS = 0x0FF00000;
         const MASK_PROCESSOR = 0xF0000000;
         const ORDERED = 0x4000000;
@@ -49,6 +54,7 @@ pub struct UnnamedSectionHeader {
     name_index: u32,
     typ: SectionHeaderType,
     flags: SectionHeaderFlags,
+    raw_flags: u64,
     address: Word,
     offset: Word,
     size: Word,
@@ -61,8 +67,10 @@ pub struct UnnamedSectionHeader {
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct SectionHeader {
     name: String,
+    name_index: u32,
     typ: SectionHeaderType,
     flags: SectionHeaderFlags,
+    raw_flags: u64,
     address: Word,
     offset: Word,
     size: Word,
@@ -75,7 +83,7 @@ pub struct SectionHeader {
 impl SectionHeaderType {
     pub fn parse_bytes(bytes: &[u8], endianness: Endianness) -> Result<Self> {
         SectionHeaderType::check_length(4, bytes.len())?;
-        SectionHeaderType::parse_u32(u32::from_bytes(bytes, endianness))
+        SectionHeaderType::parse_u32(u32::from_bytes(bytes, endianness)?)
     }
 
     pub fn parse_u32(raw: u32) -> Result<Self> {
@@ -103,6 +111,30 @@ impl SectionHeaderType {
         }
     }
 
+    pub fn to_u32(self) -> u32 {
+        match self {
+            SectionHeaderType::Null => 0x0,
+            SectionHeaderType::ProgramBits => 0x1,
+            SectionHeaderType::SymbolTable => 0x2,
+            SectionHeaderType::StringTable => 0x3,
+            SectionHeaderType::RelocationWithAddends => 0x4,
+            SectionHeaderType::Hash => 0x5,
+            SectionHeaderType::Dynamic => 0x6,
+            SectionHeaderType::Note => 0x7,
+            SectionHeaderType::NoData => 0x8,
+            SectionHeaderType::Relocation => 0x9,
+            SectionHeaderType::SharedLib => 0xA,
+            SectionHeaderType::DynamicSymbolTable => 0xB,
+            SectionHeaderType::ConstructorArray => 0xE,
+            SectionHeaderType::DestructorArray => 0xF,
+            SectionHeaderType::PreConstructorArray => 0x10,
+            SectionHeaderType::Group => 0x11,
+            SectionHeaderType::SectionIndices => 0x12,
+            SectionHeaderType::Num => 0x13,
+            SectionHeaderType::OsSpecific(raw) | SectionHeaderType::Unknown(raw) => raw,
+        }
+    }
+
     fn check_length(expected: usize, actual: usize) -> Result<()> {
         if actual < expected {
             Err(ParseError::InsufficientPartLength(actual))
@@ -120,8 +152,8 @@ impl SectionHeaderFlags {
     ) -> Result<Self> {
         SectionHeaderFlags::check_length(word_width.size(), bytes.len())?;
         let raw = match word_width {
-            WordWidth::Width32 => u32::from_bytes(bytes, endianness) as u64,
-            WordWidth::Width64 => u64::from_bytes(bytes, endianness),
+            WordWidth::Width32 => u32::from_bytes(bytes, endianness)? as u64,
+            WordWidth::Width64 => u64::from_bytes(bytes, endianness)?,
         };
         SectionHeaderFlags::parse_u64(raw)
     }
@@ -140,6 +172,55 @@ impl SectionHeaderFlags {
 }
 
 impl UnnamedSectionHeader {
+    #[allow(clippy::too_many_arguments)]
+    pub const fn new(
+        name_index: u32,
+        typ: SectionHeaderType,
+        flags: SectionHeaderFlags,
+        address: Word,
+        offset: Word,
+        size: Word,
+        link: u32,
+        info: u32,
+        align: Word,
+        entry_size: Word,
+    ) -> UnnamedSectionHeader {
+        UnnamedSectionHeader {
+            name_index,
+            typ,
+            flags,
+            raw_flags: flags.bits(),
+            address,
+            offset,
+            size,
+            link,
+            info,
+            align,
+            entry_size,
+        }
+    }
+
+    pub fn name_index(&self) -> u32 {
+        self.name_index
+    }
+
+    pub fn typ(&self) -> SectionHeaderType {
+        self.typ
+    }
+
+    pub fn flags(&self) -> SectionHeaderFlags {
+        self.flags
+    }
+
+    /// The raw `sh_flags` field, including bits which don't correspond to a known flag
+    pub fn raw_flags(&self) -> u64 {
+        self.raw_flags
+    }
+
+    pub fn address(&self) -> Word {
+        self.address
+    }
+
     pub fn offset(&self) -> Word {
         self.offset
     }
@@ -148,14 +229,43 @@ impl UnnamedSectionHeader {
         self.size
     }
 
-    pub fn typ(&self) -> SectionHeaderType {
-        self.typ
+    pub fn link(&self) -> u32 {
+        self.link
+    }
+
+    pub fn info(&self) -> u32 {
+        self.info
+    }
+
+    pub fn align(&self) -> Word {
+        self.align
+    }
+
+    pub fn entry_size(&self) -> Word {
+        self.entry_size
     }
 
     pub fn parse_bytes(
         bytes: &[u8],
         word_width: WordWidth,
         endianness: Endianness,
+    ) -> Result<Self> {
+        UnnamedSectionHeader::parse_bytes_with_warnings(
+            bytes,
+            word_width,
+            endianness,
+            &mut Warnings::strict(),
+        )
+    }
+
+    /// Parses the section header like [`UnnamedSectionHeader::parse_bytes`], but reports an
+    /// unknown type, unknown flags and an invalid alignment to `warnings`. Unknown values are
+    /// kept as they are.
+    pub(crate) fn parse_bytes_with_warnings(
+        bytes: &[u8],
+        word_width: WordWidth,
+        endianness: Endianness,
+        warnings: &mut Warnings,
     ) -> Result<Self> {
         let expected_length = match word_width {
             WordWidth::Width32 => 40,
@@ -169,24 +279,53 @@ impl UnnamedSectionHeader {
             WordWidth::Width64 => [0, 4, 8, 16, 24, 32, 40, 44, 48, 56],
         };
 
-        let name_index = u32::from_bytes(&bytes[offsets[0]..], endianness);
-        let typ = SectionHeaderType::parse_bytes(&bytes[offsets[1]..], endianness)?;
-        let flags = SectionHeaderFlags::parse_bytes(&bytes[offsets[2]..], word_width, endianness)?;
+        let name_index = u32::from_bytes(&bytes[offsets[0]..], endianness)?;
+        let typ = match SectionHeaderType::parse_bytes(&bytes[offsets[1]..], endianness) {
+            Err(ParseError::InvalidSectionHeaderType(raw)) => {
+                warnings.report(
+                    offsets[1],
+                    Some("sh_type"),
+                    ParseError::InvalidSectionHeaderType(raw),
+                )?;
+                SectionHeaderType::Unknown(raw)
+            }
+            result => result?,
+        };
+        let (flags, raw_flags) =
+            match SectionHeaderFlags::parse_bytes(&bytes[offsets[2]..], word_width, endianness) {
+                Err(ParseError::InvalidSectionHeaderFlags(raw)) => {
+                    warnings.report(
+                        offsets[2],
+                        Some("sh_flags"),
+                        ParseError::InvalidSectionHeaderFlags(raw),
+                    )?;
+                    (SectionHeaderFlags::from_bits_truncate(raw), raw)
+                }
+                result => {
+                    let flags = result?;
+                    (flags, flags.bits())
+                }
+            };
         let address = Word::parse_bytes(&bytes[offsets[3]..], word_width, endianness)?;
         let offset = Word::parse_bytes(&bytes[offsets[4]..], word_width, endianness)?;
         let size = Word::parse_bytes(&bytes[offsets[5]..], word_width, endianness)?;
-        let link = u32::from_bytes(&bytes[offsets[6]..], endianness);
-        let info = u32::from_bytes(&bytes[offsets[7]..], endianness);
+        let link = u32::from_bytes(&bytes[offsets[6]..], endianness)?;
+        let info = u32::from_bytes(&bytes[offsets[7]..], endianness)?;
         let align = Word::parse_bytes(&bytes[offsets[8]..], word_width, endianness)?;
         let align_num = u64::from(align);
         if align_num != 0 && !align_num.is_power_of_two() {
-            return Err(ParseError::InvalidAlignment(align.into()));
+            warnings.report(
+                offsets[8],
+                Some("sh_addralign"),
+                ParseError::InvalidAlignment(align.into()),
+            )?;
         }
         let entry_size = Word::parse_bytes(&bytes[offsets[9]..], word_width, endianness)?;
         Ok(UnnamedSectionHeader {
             name_index,
             typ,
             flags,
+            raw_flags,
             address,
             offset,
             size,
@@ -205,9 +344,26 @@ impl UnnamedSectionHeader {
         }
     }
 
+    /// Serializes the section header, the inverse of [`UnnamedSectionHeader::parse_bytes`].
+    pub fn to_bytes(&self, word_width: WordWidth, endianness: Endianness) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(64);
+        self.name_index.write_bytes(&mut bytes, endianness);
+        self.typ.to_u32().write_bytes(&mut bytes, endianness);
+        Word::Word64(self.raw_flags).write_bytes(&mut bytes, word_width, endianness);
+        self.address.write_bytes(&mut bytes, word_width, endianness);
+        self.offset.write_bytes(&mut bytes, word_width, endianness);
+        self.size.write_bytes(&mut bytes, word_width, endianness);
+        self.link.write_bytes(&mut bytes, endianness);
+        self.info.write_bytes(&mut bytes, endianness);
+        self.align.write_bytes(&mut bytes, word_width, endianness);
+        self.entry_size
+            .write_bytes(&mut bytes, word_width, endianness);
+        bytes
+    }
+
     pub fn to_named(self, names_table: &[u8]) -> Result<SectionHeader> {
         let index = self.name_index as usize;
-        let name_bytes = &names_table[index..];
+        let name_bytes = names_table.get(index..).unwrap_or(&[]);
         let null_index = name_bytes
             .iter()
             .position(|byte| *byte == 0)
@@ -216,10 +372,17 @@ impl UnnamedSectionHeader {
             .expect("checked for null byte")
             .into_string()
             .map_err(ParseError::InvalidSectionName)?;
-        Ok(SectionHeader {
+        Ok(self.with_name(name))
+    }
+
+    /// Names the section header without looking the name up in the section name table
+    pub(crate) fn with_name(self, name: String) -> SectionHeader {
+        SectionHeader {
             name,
+            name_index: self.name_index,
             typ: self.typ,
             flags: self.flags,
+            raw_flags: self.raw_flags,
             address: self.address,
             offset: self.offset,
             size: self.size,
@@ -227,7 +390,149 @@ impl UnnamedSectionHeader {
             info: self.info,
             align: self.align,
             entry_size: self.entry_size,
-        })
+        }
+    }
+}
+
+impl SectionHeader {
+    pub fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    /// The offset of the name in the section name table
+    pub fn name_index(&self) -> u32 {
+        self.name_index
+    }
+
+    pub fn typ(&self) -> SectionHeaderType {
+        self.typ
+    }
+
+    pub fn flags(&self) -> SectionHeaderFlags {
+        self.flags
+    }
+
+    /// The raw `sh_flags` field, including bits which don't correspond to a known flag
+    pub fn raw_flags(&self) -> u64 {
+        self.raw_flags
+    }
+
+    pub fn address(&self) -> Word {
+        self.address
+    }
+
+    pub fn offset(&self) -> Word {
+        self.offset
+    }
+
+    pub fn size(&self) -> Word {
+        self.size
+    }
+
+    pub fn link(&self) -> u32 {
+        self.link
+    }
+
+    pub fn info(&self) -> u32 {
+        self.info
+    }
+
+    pub fn align(&self) -> Word {
+        self.align
+    }
+
+    pub fn entry_size(&self) -> Word {
+        self.entry_size
+    }
+
+    pub fn with_typ(mut self, typ: SectionHeaderType) -> SectionHeader {
+        self.typ = typ;
+        self
+    }
+
+    pub fn with_address(mut self, address: Word) -> SectionHeader {
+        self.address = address;
+        self
+    }
+
+    pub fn with_offset(mut self, offset: Word) -> SectionHeader {
+        self.offset = offset;
+        self
+    }
+
+    pub fn with_size(mut self, size: Word) -> SectionHeader {
+        self.size = size;
+        self
+    }
+
+    pub fn with_link(mut self, link: u32) -> SectionHeader {
+        self.link = link;
+        self
+    }
+
+    pub fn with_info(mut self, info: u32) -> SectionHeader {
+        self.info = info;
+        self
+    }
+
+    /// Drops the resolved name, keeping its offset in the section name table
+    pub fn to_unnamed(&self) -> UnnamedSectionHeader {
+        UnnamedSectionHeader {
+            name_index: self.name_index,
+            typ: self.typ,
+            flags: self.flags,
+            raw_flags: self.raw_flags,
+            address: self.address,
+            offset: self.offset,
+            size: self.size,
+            link: self.link,
+            info: self.info,
+            align: self.align,
+            entry_size: self.entry_size,
+        }
+    }
+
+    /// Serializes the section header, see [`UnnamedSectionHeader::to_bytes`].
+    pub fn to_bytes(&self, word_width: WordWidth, endianness: Endianness) -> Vec<u8> {
+        self.to_unnamed().to_bytes(word_width, endianness)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_section_header_round_trip() {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&0x1B_u32.to_be_bytes());
+        bytes.extend_from_slice(&0x1_u32.to_be_bytes());
+        bytes.extend_from_slice(&0x6_u32.to_be_bytes());
+        bytes.extend_from_slice(&0x10074_u32.to_be_bytes());
+        bytes.extend_from_slice(&0x74_u32.to_be_bytes());
+        bytes.extend_from_slice(&0x1C_u32.to_be_bytes());
+        bytes.extend_from_slice(&0_u32.to_be_bytes());
+        bytes.extend_from_slice(&0_u32.to_be_bytes());
+        bytes.extend_from_slice(&4_u32.to_be_bytes());
+        bytes.extend_from_slice(&0_u32.to_be_bytes());
+        let sheader =
+            UnnamedSectionHeader::parse_bytes(&bytes, WordWidth::Width32, Endianness::Big)
+                .expect("valid section header");
+        assert_eq!(sheader.to_bytes(WordWidth::Width32, Endianness::Big), bytes);
+
+        let mut names = vec![0; 0x1B];
+        names.extend_from_slice(b".text\0");
+        let named = sheader.to_named(&names).expect("valid name");
+        assert_eq!(named.name(), ".text");
+        assert_eq!(named.to_unnamed(), sheader);
+        let wide = named.to_bytes(WordWidth::Width64, Endianness::Little);
+        assert_eq!(
+            UnnamedSectionHeader::parse_bytes(&wide, WordWidth::Width64, Endianness::Little)
+                .map(|wide| (wide.flags(), u64::from(wide.address()))),
+            Ok((
+                SectionHeaderFlags::ALLOC | SectionHeaderFlags::EXEC,
+                0x10074
+            ))
+        );
     }
 }
-//TODO: add tests
@@ -0,0 +1,622 @@
+use std::io::Cursor;
+
+use super::*;
+
+/// Removes sections from executables, shared objects and object files, similar to `strip`.
+///
+/// The contents of sections which are part of a segment stay in place, so every segment keeps
+/// its contents and only the section headers of removed sections are dropped. The contents of
+/// the other remaining sections are packed behind the segments, followed by the new section
+/// header table. The `link` and `info` fields of the remaining sections, the section indices of
+/// symbols and section groups and the index of the section name table are adjusted to the new
+/// section numbering. Relocation sections are removed together with the section they apply to,
+/// and symbols defined in a removed section become undefined.
+///
+/// ```no_run
+/// use elfreader::elf::ElfStripper;
+///
+/// let data = std::fs::read("app").unwrap();
+/// let mut stripper = ElfStripper::new();
+/// stripper.strip_debug();
+/// let debug = stripper.debug_file(&data).unwrap();
+/// stripper.set_debuglink("app.debug", &debug);
+/// std::fs::write("app.debug", debug).unwrap();
+/// std::fs::write("app", stripper.apply(&data).unwrap()).unwrap();
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ElfStripper {
+    debug: bool,
+    symbols: bool,
+    sections: Vec<String>,
+    debuglink: Option<(String, u32)>,
+}
+
+/// What happens to a section when the file is rewritten
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Disposition {
+    Keep,
+    Remove,
+    /// Keep the section header, but turn it into a `NoData` section without contents
+    Empty,
+}
+
+impl ElfStripper {
+    pub fn new() -> ElfStripper {
+        ElfStripper::default()
+    }
+
+    /// Removes the `.debug_*` and `.zdebug_*` sections
+    pub fn strip_debug(&mut self) -> &mut ElfStripper {
+        self.debug = true;
+        self
+    }
+
+    /// Removes the symbol table `.symtab` with its string table `.strtab`. The dynamic symbol
+    /// table is kept.
+    pub fn strip_symbols(&mut self) -> &mut ElfStripper {
+        self.symbols = true;
+        self
+    }
+
+    /// Removes the sections with the given name
+    pub fn remove_section(&mut self, name: &str) -> &mut ElfStripper {
+        self.sections.push(name.to_string());
+        self
+    }
+
+    /// Adds a `.gnu_debuglink` section to the stripped file, which refers debuggers to the
+    /// separate debug file `file_name` with the contents `debug_file`.
+    pub fn set_debuglink(&mut self, file_name: &str, debug_file: &[u8]) -> &mut ElfStripper {
+        let mut crc = flate2::Crc::new();
+        crc.update(debug_file);
+        self.debuglink = Some((file_name.to_string(), crc.sum()));
+        self
+    }
+
+    /// Removes the selected sections from the file contents in `data` and returns the stripped
+    /// file.
+    pub fn apply(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let elf = ElfFile::parse(data)?;
+        let sheaders = elf.section_headers()?.collect::<Result<Vec<_>>>()?;
+        let names_index = elf.counts()?.section_names_index;
+        let removed = self.removed_sections(&sheaders, names_index);
+        let dispositions: Vec<_> = removed
+            .iter()
+            .map(|&removed| {
+                if removed {
+                    Disposition::Remove
+                } else {
+                    Disposition::Keep
+                }
+            })
+            .collect();
+        let debuglink = self.debuglink.as_ref().map(|(file_name, crc)| {
+            let mut contents = file_name.as_bytes().to_vec();
+            contents.push(0);
+            contents.resize(align_up(contents.len() as u64, 4) as usize, 0);
+            crc.write_bytes(&mut contents, elf.header().endianness());
+            contents
+        });
+        Rewrite::new(&elf, sheaders, dispositions, true)?.finish(debuglink)
+    }
+
+    /// Returns a file containing the sections [`ElfStripper::apply`] removes, the symbol table
+    /// and the notes, to be used as a separate debug file. The other sections become `NoData`
+    /// sections, so that the section numbering and addresses stay the same, and the program
+    /// headers are dropped.
+    pub fn debug_file(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let elf = ElfFile::parse(data)?;
+        let sheaders = elf.section_headers()?.collect::<Result<Vec<_>>>()?;
+        let names_index = elf.counts()?.section_names_index;
+        let removed = self.removed_sections(&sheaders, names_index);
+        // like `objcopy --only-keep-debug`, the symbol table and the notes with the build ID are
+        // kept as well
+        let mut kept = removed;
+        for &index in [0, names_index].iter() {
+            if let Some(kept) = kept.get_mut(index) {
+                *kept = true;
+            }
+        }
+        for (i, sheader) in sheaders.iter().enumerate() {
+            if sheader.typ() == SectionHeaderType::SymbolTable {
+                kept[i] = true;
+                if let Some(strings) = kept.get_mut(sheader.link() as usize) {
+                    *strings = true;
+                }
+            }
+            if sheader.typ() == SectionHeaderType::Note
+                || sheader.typ() == SectionHeaderType::NoData
+            {
+                kept[i] = true;
+            }
+        }
+        let dispositions: Vec<_> = kept
+            .iter()
+            .map(|&kept| {
+                if kept {
+                    Disposition::Keep
+                } else {
+                    Disposition::Empty
+                }
+            })
+            .collect();
+        Rewrite::new(&elf, sheaders, dispositions, false)?.finish(None)
+    }
+
+    fn is_selected(&self, sheader: &SectionHeader) -> bool {
+        let name = sheader.name();
+        (self.debug && (name.starts_with(".debug") || name.starts_with(".zdebug")))
+            || (self.symbols
+                && (sheader.typ() == SectionHeaderType::SymbolTable || name == ".strtab"))
+            || self.sections.iter().any(|section| section == name)
+    }
+
+    /// Selects the removed sections. The null section and the section name table are always
+    /// kept, relocation sections are removed with the section they apply to.
+    fn removed_sections(&self, sheaders: &[SectionHeader], names_index: usize) -> Vec<bool> {
+        let mut removed: Vec<_> = sheaders
+            .iter()
+            .enumerate()
+            .map(|(i, sheader)| i != 0 && i != names_index && self.is_selected(sheader))
+            .collect();
+        for (i, sheader) in sheaders.iter().enumerate() {
+            let applies_to = sheader.info() as usize;
+            if applies_to != 0
+                && is_relocation(sheader.typ())
+                && removed.get(applies_to) == Some(&true)
+            {
+                removed[i] = true;
+            }
+        }
+        removed
+    }
+}
+
+/// The state of a rewrite in progress
+struct Rewrite<'data> {
+    elf: &'data ElfFile<'data>,
+    sheaders: Vec<SectionHeader>,
+    dispositions: Vec<Disposition>,
+    /// The new index of every section, if it is kept
+    indices: Vec<Option<u32>>,
+    keep_segments: bool,
+    contents: Vec<u8>,
+}
+
+impl<'data> Rewrite<'data> {
+    fn new(
+        elf: &'data ElfFile<'data>,
+        sheaders: Vec<SectionHeader>,
+        dispositions: Vec<Disposition>,
+        keep_segments: bool,
+    ) -> Result<Rewrite<'data>> {
+        let mut next = 0;
+        let indices = dispositions
+            .iter()
+            .map(|disposition| {
+                if *disposition == Disposition::Remove {
+                    return None;
+                }
+                next += 1;
+                Some(next - 1)
+            })
+            .collect();
+        // the headers and the segments are copied as they are
+        let header = elf.header();
+        let mut fixed_end = match header.word_width() {
+            WordWidth::Width32 => 52,
+            WordWidth::Width64 => 64,
+        };
+        if keep_segments {
+            let counts = elf.counts()?;
+            let table_size =
+                counts.program_headers as u64 * u64::from(header.program_header_entry_size());
+            fixed_end =
+                fixed_end.max(u64::from(header.program_header_start()).saturating_add(table_size));
+            for pheader in elf.program_headers()? {
+                let pheader = pheader?;
+                // the segment is inside of the file, so its end can't overflow
+                elf.segment_data(&pheader)?;
+                fixed_end =
+                    fixed_end.max(u64::from(pheader.offset()) + u64::from(pheader.filesize()));
+            }
+        }
+        let fixed_end = fixed_end.min(elf.data().len() as u64) as usize;
+        let contents = if keep_segments {
+            elf.data()[..fixed_end].to_vec()
+        } else {
+            vec![0; fixed_end]
+        };
+        Ok(Rewrite {
+            elf,
+            sheaders,
+            dispositions,
+            indices,
+            keep_segments,
+            contents,
+        })
+    }
+
+    /// The new index of a section referenced by the `link` or `info` field of `referrer`
+    fn new_index(&self, index: u32, referrer: usize) -> Result<u32> {
+        match self.indices.get(index as usize) {
+            Some(Some(index)) => Ok(*index),
+            // a removed section can't be referenced by a section which is kept
+            Some(None) => Err(ParseError::InvalidSectionLink(index)),
+            None => Err(ParseError::InvalidSectionLink(referrer as u32)),
+        }
+    }
+
+    fn finish(mut self, debuglink: Option<Vec<u8>>) -> Result<Vec<u8>> {
+        let header = self.elf.header().clone();
+        let word_width = header.word_width();
+        let names_index = self.elf.counts()?.section_names_index;
+        let fixed_end = self.contents.len() as u64;
+        let names_header = self
+            .sheaders
+            .get(names_index)
+            .ok_or(ParseError::InvalidSectionLink(names_index as u32))?;
+        let mut names = self.elf.section_data(names_header)?.to_vec();
+        let debuglink_name = debuglink.as_ref().map(|_| {
+            let name = b".gnu_debuglink\0";
+            match names.windows(name.len()).position(|window| window == name) {
+                Some(index) => index as u32,
+                None => {
+                    names.extend_from_slice(name);
+                    (names.len() - name.len()) as u32
+                }
+            }
+        });
+
+        // sections outside of the copied part are packed in the order of their offsets
+        let mut order: Vec<_> = (0..self.sheaders.len()).collect();
+        order.sort_by_key(|&i| u64::from(self.sheaders[i].offset()));
+        let mut new_sheaders: Vec<Option<SectionHeader>> = vec![None; self.sheaders.len()];
+        for i in order {
+            let sheader = &self.sheaders[i];
+            let disposition = self.dispositions[i];
+            if disposition == Disposition::Remove {
+                continue;
+            }
+            let offset = u64::from(sheader.offset());
+            let size = u64::from(sheader.size());
+            let mut new_sheader = sheader.clone();
+            let has_contents = disposition == Disposition::Keep
+                && sheader.typ() != SectionHeaderType::NoData
+                && sheader.typ() != SectionHeaderType::Null;
+            if !has_contents {
+                if disposition == Disposition::Empty {
+                    new_sheader = new_sheader.with_typ(SectionHeaderType::NoData);
+                }
+                if i != 0 && (!self.keep_segments || offset > fixed_end) {
+                    let end = Word::new(word_width, self.contents.len() as u64);
+                    new_sheader = new_sheader.with_offset(end);
+                }
+            } else if i == names_index {
+                new_sheader = self.append(new_sheader, &names)?;
+            } else if offset.saturating_add(size) <= fixed_end && self.keep_segments {
+                // part of a segment, the contents are already copied
+            } else {
+                let data = self.section_contents(i)?;
+                new_sheader = self.append(new_sheader, &data)?;
+            }
+            new_sheaders[i] = Some(new_sheader);
+        }
+
+        let mut sheaders = Vec::new();
+        for (i, sheader) in new_sheaders.into_iter().enumerate() {
+            let mut sheader = match sheader {
+                None => continue,
+                Some(sheader) => sheader,
+            };
+            if i != 0 {
+                if sheader.link() != 0 {
+                    let link = self.new_index(sheader.link(), i)?;
+                    sheader = sheader.with_link(link);
+                }
+                let info_is_index = is_relocation(sheader.typ())
+                    || sheader.flags().contains(SectionHeaderFlags::INFO_LINK);
+                if info_is_index && sheader.info() != 0 {
+                    let info = self.new_index(sheader.info(), i)?;
+                    sheader = sheader.with_info(info);
+                }
+                if sheader.typ() == SectionHeaderType::SymbolTable
+                    || sheader.typ() == SectionHeaderType::DynamicSymbolTable
+                {
+                    self.renumber_symbols(&sheader)?;
+                }
+            }
+            sheaders.push(sheader);
+        }
+        if let (Some(contents), Some(name)) = (debuglink, debuglink_name) {
+            let sheader = UnnamedSectionHeader::new(
+                name,
+                SectionHeaderType::ProgramBits,
+                SectionHeaderFlags::empty(),
+                Word::zero(word_width),
+                Word::zero(word_width),
+                Word::new(word_width, contents.len() as u64),
+                0,
+                0,
+                Word::new(word_width, 4),
+                Word::zero(word_width),
+            )
+            .to_named(&names)?;
+            let sheader = self.append(sheader, &contents)?;
+            sheaders.push(sheader);
+        }
+
+        let count = sheaders.len();
+        let new_names_index = self.indices[names_index].unwrap_or(0) as usize;
+        // counts that don't fit into the header are stored in the first section header
+        sheaders[0] = sheaders[0]
+            .clone()
+            .with_size(Word::new(
+                word_width,
+                if count >= 0xFF00 { count as u64 } else { 0 },
+            ))
+            .with_link(if new_names_index >= 0xFF00 {
+                new_names_index as u32
+            } else {
+                0
+            });
+        let sheader_start = align_up(self.contents.len() as u64, word_width.size() as u64);
+        self.contents.resize(sheader_start as usize, 0);
+        let mut header = header
+            .with_section_header_start(Word::new(word_width, sheader_start))
+            .with_section_header_entry_count(if count >= 0xFF00 { 0 } else { count as u16 })
+            .with_section_names_index(if new_names_index >= 0xFF00 {
+                Header::SHN_XINDEX
+            } else {
+                new_names_index as u16
+            });
+        let pheaders = if self.keep_segments {
+            self.elf.program_headers()?.collect::<Result<Vec<_>>>()?
+        } else {
+            header = header
+                .with_program_header_start(Word::zero(word_width))
+                .with_program_header_entry_count(0);
+            sheaders[0] = sheaders[0].clone().with_info(0);
+            Vec::new()
+        };
+        let metadata = Metadata::new(header, pheaders, sheaders);
+        let mut writer = Cursor::new(Vec::new());
+        metadata
+            .write_to(&self.contents, &mut writer)
+            .expect("writing to memory does not fail");
+        Ok(writer.into_inner())
+    }
+
+    /// Appends the contents of a section and points its header to them
+    fn append(&mut self, sheader: SectionHeader, data: &[u8]) -> Result<SectionHeader> {
+        let end = self.contents.len() as u64;
+        let align = u64::from(sheader.align());
+        let offset = align_up(end, align);
+        // the padding of a bogus alignment could exhaust the memory, while a valid one never
+        // needs more padding than the size of the input file
+        if offset.saturating_sub(end) > self.elf.data().len() as u64 {
+            return Err(ParseError::InvalidAlignment(align));
+        }
+        self.contents.resize(offset as usize, 0);
+        self.contents.extend_from_slice(data);
+        let word_width = self.elf.header().word_width();
+        Ok(sheader
+            .with_offset(Word::new(word_width, offset))
+            .with_size(Word::new(word_width, data.len() as u64)))
+    }
+
+    /// The contents of a section, where the members of section groups are renumbered
+    fn section_contents(&self, index: usize) -> Result<Vec<u8>> {
+        let sheader = &self.sheaders[index];
+        let data = self.elf.section_data(sheader)?;
+        if sheader.typ() != SectionHeaderType::Group {
+            return Ok(data.to_vec());
+        }
+        let endianness = self.elf.header().endianness();
+        let mut words = data.chunks_exact(4);
+        let mut contents = Vec::with_capacity(data.len());
+        if let Some(flags) = words.next() {
+            contents.extend_from_slice(flags);
+        }
+        for member in words {
+            let member = u32::from_bytes(member, endianness)?;
+            if let Some(Some(member)) = self.indices.get(member as usize) {
+                member.write_bytes(&mut contents, endianness);
+            }
+        }
+        Ok(contents)
+    }
+
+    /// Adjusts the section indices of the symbols in an already written symbol table
+    fn renumber_symbols(&mut self, sheader: &SectionHeader) -> Result<()> {
+        let word_width = self.elf.header().word_width();
+        let endianness = self.elf.header().endianness();
+        let entry_size = match u64::from(sheader.entry_size()) as usize {
+            0 => Symbol::entry_size(word_width),
+            size => size,
+        };
+        // the offset of `st_shndx` in a symbol
+        let index_offset = match word_width {
+            WordWidth::Width32 => 14,
+            WordWidth::Width64 => 6,
+        };
+        let start = u64::from(sheader.offset()) as usize;
+        let end = start + u64::from(sheader.size()) as usize;
+        let table = self
+            .contents
+            .get_mut(start..end)
+            .ok_or(ParseError::InvalidDataRange(
+                start as u64,
+                (end - start) as u64,
+            ))?;
+        for symbol in table.chunks_exact_mut(entry_size) {
+            let field = symbol
+                .get_mut(index_offset..index_offset + 2)
+                .ok_or(ParseError::InsufficientSymbolLength(entry_size))?;
+            let index = u16::from_bytes(field, endianness)?;
+            if let SymbolSectionIndex::Index(index) = SymbolSectionIndex::from_u16(index) {
+                let new_index = match self.indices.get(index as usize) {
+                    Some(Some(new_index)) => *new_index as u16,
+                    _ => 0,
+                };
+                let mut bytes = Vec::with_capacity(2);
+                new_index.write_bytes(&mut bytes, endianness);
+                field.copy_from_slice(&bytes);
+            }
+        }
+        Ok(())
+    }
+}
+
+fn is_relocation(typ: SectionHeaderType) -> bool {
+    typ == SectionHeaderType::Relocation || typ == SectionHeaderType::RelocationWithAddends
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::elf::testing::{string_table, Fixture, FixtureSection};
+
+    /// An executable with debug information and a symbol table after its only segment
+    fn debug_fixture(word_width: WordWidth, endianness: Endianness) -> Vec<u8> {
+        let mut fixture = Fixture::new(word_width, endianness);
+        let text = fixture.section(FixtureSection::new(".text", 1, vec![0xC3; 16]).flags(0x6));
+        let debug_info = fixture.section(FixtureSection::new(".debug_info", 1, vec![1; 24]));
+        let relocation_size = match word_width {
+            WordWidth::Width32 => 12,
+            WordWidth::Width64 => 24,
+        };
+        fixture.section(
+            FixtureSection::new(".rela.debug_info", 4, vec![0; relocation_size])
+                .link(debug_info as u32 + 3)
+                .info(debug_info as u32)
+                .entry_size(relocation_size as u64)
+                .align(8),
+        );
+        let (strings, indices) = string_table(&["main", "info"]);
+        let mut symbols = fixture.writer();
+        let mut symbol = |name: u32, value: u64, index: u16| match word_width {
+            WordWidth::Width32 => {
+                symbols
+                    .u32(name)
+                    .u32(value as u32)
+                    .u32(0)
+                    .u8(0x12)
+                    .u8(0)
+                    .u16(index);
+            }
+            WordWidth::Width64 => {
+                symbols
+                    .u32(name)
+                    .u8(0x12)
+                    .u8(0)
+                    .u16(index)
+                    .u64(value)
+                    .u64(0);
+            }
+        };
+        symbol(0, 0, 0);
+        symbol(indices[0], fixture.address_of(text), text as u16);
+        symbol(indices[1], 0, debug_info as u16);
+        let symbol_size = match word_width {
+            WordWidth::Width32 => 16,
+            WordWidth::Width64 => 24,
+        };
+        let symtab = fixture.section(
+            FixtureSection::new(".symtab", 2, symbols.bytes)
+                .link(debug_info as u32 + 3)
+                .info(1)
+                .entry_size(symbol_size)
+                .align(8),
+        );
+        fixture.section(FixtureSection::new(".strtab", 3, strings));
+        assert_eq!(symtab, debug_info + 2);
+        fixture.segment(1, 0x5, text..text + 1, 0x1000);
+        fixture.build()
+    }
+
+    fn section_names(elf: &ElfFile) -> Vec<String> {
+        elf.section_headers()
+            .unwrap()
+            .map(|sheader| sheader.unwrap().name().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_strip_debug() {
+        for &word_width in [WordWidth::Width32, WordWidth::Width64].iter() {
+            for &endianness in [Endianness::Little, Endianness::Big].iter() {
+                let data = debug_fixture(word_width, endianness);
+                let stripped = ElfStripper::new().strip_debug().apply(&data).unwrap();
+                assert!(stripped.len() < data.len());
+                let elf = ElfFile::parse(&stripped).unwrap();
+                assert_eq!(
+                    section_names(&elf),
+                    ["", ".text", ".symtab", ".strtab", ".shstrtab"]
+                );
+                assert_eq!(elf.counts().unwrap().section_names_index, 4);
+                let symtab = elf.section_header(2).unwrap().unwrap();
+                assert_eq!(symtab.link(), 3);
+                let symbols = elf.symbol_table().unwrap().unwrap();
+                let symbols: Vec<_> = symbols.map(|symbol| symbol.unwrap()).collect();
+                assert_eq!(symbols[1].name(), "main");
+                assert_eq!(symbols[1].section_index(), SymbolSectionIndex::Index(1));
+                assert_eq!(symbols[2].section_index(), SymbolSectionIndex::Undefined);
+
+                // the segment is unchanged
+                let text = elf.section_header(1).unwrap().unwrap();
+                assert_eq!(elf.section_data(&text).unwrap(), &[0xC3; 16]);
+                let original = ElfFile::parse(&data).unwrap();
+                let pheader = original.program_headers().unwrap().next().unwrap().unwrap();
+                let segment = original.segment_data(&pheader).unwrap();
+                let pheader = elf.program_headers().unwrap().next().unwrap().unwrap();
+                assert_eq!(elf.segment_data(&pheader).unwrap(), segment);
+            }
+        }
+    }
+
+    #[test]
+    fn test_strip_all_and_debug_file() {
+        let data = debug_fixture(WordWidth::Width64, Endianness::Little);
+        let mut stripper = ElfStripper::new();
+        stripper.strip_debug().strip_symbols();
+        let debug = stripper.debug_file(&data).unwrap();
+        stripper.set_debuglink("app.debug", &debug);
+        let stripped = stripper.apply(&data).unwrap();
+
+        let elf = ElfFile::parse(&stripped).unwrap();
+        assert_eq!(
+            section_names(&elf),
+            ["", ".text", ".shstrtab", ".gnu_debuglink"]
+        );
+        assert!(elf.symbol_table().unwrap().is_none());
+        let debuglink = elf
+            .section_header_by_name(".gnu_debuglink")
+            .unwrap()
+            .unwrap();
+        let contents = elf.section_data(&debuglink).unwrap();
+        assert_eq!(&contents[..12], b"app.debug\0\0\0");
+        let mut crc = flate2::Crc::new();
+        crc.update(&debug);
+        assert_eq!(contents[12..], crc.sum().to_le_bytes());
+
+        // the debug file keeps the numbering, with the contents of the stripped sections only
+        let elf = ElfFile::parse(&debug).unwrap();
+        assert_eq!(elf.program_headers().unwrap().len(), 0);
+        let sheaders: Vec<_> = elf.section_headers().unwrap().map(|s| s.unwrap()).collect();
+        assert_eq!(sheaders.len(), 7);
+        assert_eq!(sheaders[0].typ(), SectionHeaderType::Null);
+        assert_eq!(sheaders[1].typ(), SectionHeaderType::NoData);
+        assert_eq!(elf.section_data(&sheaders[2]).unwrap(), &[1; 24]);
+        assert_eq!(sheaders[3].info(), 2);
+        assert_eq!(sheaders[4].link(), 5);
+        assert_eq!(elf.symbol_table().unwrap().unwrap().count(), 3);
+    }
+
+    #[test]
+    fn test_strip_referenced_section() {
+        let data = debug_fixture(WordWidth::Width64, Endianness::Little);
+        let result = ElfStripper::new().remove_section(".strtab").apply(&data);
+        assert_eq!(result, Err(ParseError::InvalidSectionLink(5)));
+    }
+}
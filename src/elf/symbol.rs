@@ -0,0 +1,522 @@
+use std::convert::TryFrom;
+use std::fmt::{Display, Formatter};
+
+use super::*;
+
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum SymbolBinding {
+    Local,
+    Global,
+    Weak,
+    OsSpecific(u8),
+    ProcessorSpecific(u8),
+}
+
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum SymbolType {
+    NoType,
+    Object,
+    Function,
+    Section,
+    File,
+    Common,
+    ThreadLocalStorage,
+    OsSpecific(u8),
+    ProcessorSpecific(u8),
+}
+
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum SymbolVisibility {
+    Default,
+    Internal,
+    Hidden,
+    Protected,
+}
+
+/// The section a symbol is defined in, or one of the reserved section indices.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum SymbolSectionIndex {
+    Undefined,
+    Absolute,
+    Common,
+    /// The real index is stored in the `SHT_SYMTAB_SHNDX` section. Symbols read through
+    /// [`Symbols`] with such a section carry the real index instead.
+    ExtendedIndex,
+    Reserved(u16),
+    Index(u32),
+}
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Symbol {
+    name: String,
+    value: Word,
+    size: Word,
+    binding: SymbolBinding,
+    typ: SymbolType,
+    visibility: SymbolVisibility,
+    section_index: SymbolSectionIndex,
+}
+
+/// Lazily parses the entries of a symbol table section.
+#[derive(Debug, Clone)]
+pub struct Symbols<'data> {
+    table: &'data [u8],
+    names: &'data [u8],
+    /// The contents of the `SHT_SYMTAB_SHNDX` section of the table, if there is one
+    section_indices: &'data [u8],
+    entry_size: usize,
+    word_width: WordWidth,
+    endianness: Endianness,
+}
+
+impl SymbolBinding {
+    fn parse_u8(b: u8) -> Result<SymbolBinding> {
+        use SymbolBinding::*;
+        match b {
+            0 => Ok(Local),
+            1 => Ok(Global),
+            2 => Ok(Weak),
+            10..=12 => Ok(OsSpecific(b)),
+            13..=15 => Ok(ProcessorSpecific(b)),
+            _ => Err(ParseError::InvalidSymbolBinding(b)),
+        }
+    }
+
+    pub(crate) fn to_u8(self) -> u8 {
+        use SymbolBinding::*;
+        match self {
+            Local => 0,
+            Global => 1,
+            Weak => 2,
+            OsSpecific(b) | ProcessorSpecific(b) => b,
+        }
+    }
+}
+
+impl SymbolType {
+    fn parse_u8(b: u8) -> Result<SymbolType> {
+        use SymbolType::*;
+        match b {
+            0 => Ok(NoType),
+            1 => Ok(Object),
+            2 => Ok(Function),
+            3 => Ok(Section),
+            4 => Ok(File),
+            5 => Ok(Common),
+            6 => Ok(ThreadLocalStorage),
+            10..=12 => Ok(OsSpecific(b)),
+            13..=15 => Ok(ProcessorSpecific(b)),
+            _ => Err(ParseError::InvalidSymbolType(b)),
+        }
+    }
+
+    pub(crate) fn to_u8(self) -> u8 {
+        use SymbolType::*;
+        match self {
+            NoType => 0,
+            Object => 1,
+            Function => 2,
+            Section => 3,
+            File => 4,
+            Common => 5,
+            ThreadLocalStorage => 6,
+            OsSpecific(b) | ProcessorSpecific(b) => b,
+        }
+    }
+}
+
+impl SymbolVisibility {
+    fn from_byte(b: u8) -> SymbolVisibility {
+        use SymbolVisibility::*;
+        match b & 0x3 {
+            0 => Default,
+            1 => Internal,
+            2 => Hidden,
+            _ => Protected,
+        }
+    }
+
+    pub(crate) fn to_u8(self) -> u8 {
+        use SymbolVisibility::*;
+        match self {
+            Default => 0,
+            Internal => 1,
+            Hidden => 2,
+            Protected => 3,
+        }
+    }
+}
+
+impl SymbolSectionIndex {
+    pub(crate) fn from_u16(i: u16) -> SymbolSectionIndex {
+        use SymbolSectionIndex::*;
+        match i {
+            0x0000 => Undefined,
+            0xFFF1 => Absolute,
+            0xFFF2 => Common,
+            0xFFFF => ExtendedIndex,
+            _ if i >= 0xFF00 => Reserved(i),
+            _ => Index(u32::from(i)),
+        }
+    }
+
+    pub(crate) fn to_u16(self) -> u16 {
+        use SymbolSectionIndex::*;
+        match self {
+            Undefined => 0x0000,
+            Absolute => 0xFFF1,
+            Common => 0xFFF2,
+            // larger indices have to be stored in the `SHT_SYMTAB_SHNDX` section
+            ExtendedIndex => 0xFFFF,
+            Index(i) if i >= 0xFF00 => 0xFFFF,
+            Reserved(i) => i,
+            Index(i) => i as u16,
+        }
+    }
+}
+
+impl Symbol {
+    /// Parses a single symbol table entry and looks up its name in `names`, the string table
+    /// linked to the symbol table.
+    pub fn parse_bytes(
+        bytes: &[u8],
+        names: &[u8],
+        word_width: WordWidth,
+        endianness: Endianness,
+    ) -> Result<Symbol> {
+        let size = Symbol::entry_size(word_width);
+        if bytes.len() < size {
+            return Err(ParseError::InsufficientSymbolLength(bytes.len()));
+        }
+        // these are the word width dependent offsets of the fields:
+        // [name, value, size, info, other, section_index]
+        let offsets = match word_width {
+            WordWidth::Width32 => [0, 4, 8, 12, 13, 14],
+            WordWidth::Width64 => [0, 8, 16, 4, 5, 6],
+        };
+        let name_index = u32::from_bytes(&bytes[offsets[0]..], endianness)?;
+        let value = Word::parse_bytes(&bytes[offsets[1]..], word_width, endianness)?;
+        let size = Word::parse_bytes(&bytes[offsets[2]..], word_width, endianness)?;
+        let info = bytes[offsets[3]];
+        let other = bytes[offsets[4]];
+        let section_index = u16::from_bytes(&bytes[offsets[5]..], endianness)?;
+        let name = parse_string(names, name_index as usize)?.to_string();
+        Ok(Symbol {
+            name,
+            value,
+            size,
+            binding: SymbolBinding::parse_u8(info >> 4)?,
+            typ: SymbolType::parse_u8(info & 0xF)?,
+            visibility: SymbolVisibility::from_byte(other),
+            section_index: SymbolSectionIndex::from_u16(section_index),
+        })
+    }
+
+    /// The size of a symbol table entry in bytes
+    pub const fn entry_size(word_width: WordWidth) -> usize {
+        match word_width {
+            WordWidth::Width32 => 16,
+            WordWidth::Width64 => 24,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    pub const fn value(&self) -> Word {
+        self.value
+    }
+
+    pub const fn size(&self) -> Word {
+        self.size
+    }
+
+    pub const fn binding(&self) -> SymbolBinding {
+        self.binding
+    }
+
+    pub const fn typ(&self) -> SymbolType {
+        self.typ
+    }
+
+    pub const fn visibility(&self) -> SymbolVisibility {
+        self.visibility
+    }
+
+    pub const fn section_index(&self) -> SymbolSectionIndex {
+        self.section_index
+    }
+}
+
+impl<'data> Symbols<'data> {
+    pub(crate) fn new(
+        table: &'data [u8],
+        names: &'data [u8],
+        entry_size: Word,
+        word_width: WordWidth,
+        endianness: Endianness,
+    ) -> Symbols<'data> {
+        let entry_size = match usize::try_from(u64::from(entry_size)) {
+            Ok(0) | Err(_) => Symbol::entry_size(word_width),
+            Ok(size) => size,
+        };
+        // a trailing partial entry is ignored
+        let table = &table[..table.len() - table.len() % entry_size];
+        Symbols {
+            table,
+            names,
+            section_indices: &[],
+            entry_size,
+            word_width,
+            endianness,
+        }
+    }
+
+    /// Resolves `ExtendedIndex` section indices through `section_indices`, the contents of the
+    /// `SHT_SYMTAB_SHNDX` section linked to the symbol table.
+    pub(crate) fn with_section_indices(mut self, section_indices: &'data [u8]) -> Symbols<'data> {
+        self.section_indices = section_indices;
+        self
+    }
+
+    /// Parses the symbol with the given index in the table.
+    pub fn get(&self, index: usize) -> Option<Result<Symbol>> {
+        let start = index.checked_mul(self.entry_size)?;
+        let bytes = self.table.get(start..start.checked_add(self.entry_size)?)?;
+        let symbol = Symbol::parse_bytes(bytes, self.names, self.word_width, self.endianness);
+        Some(symbol.and_then(|mut symbol| {
+            if symbol.section_index == SymbolSectionIndex::ExtendedIndex
+                && !self.section_indices.is_empty()
+            {
+                let start = index.saturating_mul(4);
+                let entry = self
+                    .section_indices
+                    .get(start..start.saturating_add(4))
+                    .ok_or(ParseError::InvalidSymbolIndex(index as u32))?;
+                symbol.section_index =
+                    SymbolSectionIndex::Index(u32::from_bytes(entry, self.endianness)?);
+            }
+            Ok(symbol)
+        }))
+    }
+}
+
+impl<'data> Iterator for Symbols<'data> {
+    type Item = Result<Symbol>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let symbol = self.get(0)?;
+        self.table = &self.table[self.entry_size..];
+        self.section_indices = self.section_indices.get(4..).unwrap_or(&[]);
+        Some(symbol)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.table.len() / self.entry_size;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<'data> ExactSizeIterator for Symbols<'data> {}
+
+impl Display for SymbolBinding {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            SymbolBinding::Local => write!(f, "LOCAL"),
+            SymbolBinding::Global => write!(f, "GLOBAL"),
+            SymbolBinding::Weak => write!(f, "WEAK"),
+            SymbolBinding::OsSpecific(10) => write!(f, "UNIQUE"),
+            SymbolBinding::OsSpecific(b) => write!(f, "<OS specific>: {}", b),
+            SymbolBinding::ProcessorSpecific(b) => write!(f, "<processor specific>: {}", b),
+        }
+    }
+}
+
+impl Display for SymbolType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            SymbolType::NoType => write!(f, "NOTYPE"),
+            SymbolType::Object => write!(f, "OBJECT"),
+            SymbolType::Function => write!(f, "FUNC"),
+            SymbolType::Section => write!(f, "SECTION"),
+            SymbolType::File => write!(f, "FILE"),
+            SymbolType::Common => write!(f, "COMMON"),
+            SymbolType::ThreadLocalStorage => write!(f, "TLS"),
+            SymbolType::OsSpecific(10) => write!(f, "IFUNC"),
+            SymbolType::OsSpecific(b) => write!(f, "<OS specific>: {}", b),
+            SymbolType::ProcessorSpecific(b) => write!(f, "<processor specific>: {}", b),
+        }
+    }
+}
+
+impl Display for SymbolVisibility {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            SymbolVisibility::Default => write!(f, "DEFAULT"),
+            SymbolVisibility::Internal => write!(f, "INTERNAL"),
+            SymbolVisibility::Hidden => write!(f, "HIDDEN"),
+            SymbolVisibility::Protected => write!(f, "PROTECTED"),
+        }
+    }
+}
+
+impl Display for SymbolSectionIndex {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            SymbolSectionIndex::Undefined => write!(f, "UND"),
+            SymbolSectionIndex::Absolute => write!(f, "ABS"),
+            SymbolSectionIndex::Common => write!(f, "COM"),
+            SymbolSectionIndex::ExtendedIndex => write!(f, "XINDEX"),
+            SymbolSectionIndex::Reserved(i) => write!(f, "RSV[{:#06x}]", i),
+            SymbolSectionIndex::Index(i) => write!(f, "{}", i),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::elf::testing::{string_table, Fixture, FixtureSection, Writer};
+
+    #[allow(clippy::too_many_arguments)]
+    fn symbol_bytes(
+        word_width: WordWidth,
+        endianness: Endianness,
+        name: u32,
+        value: u64,
+        size: u64,
+        info: u8,
+        other: u8,
+        section_index: u16,
+    ) -> Vec<u8> {
+        let mut w = Writer::new(word_width, endianness);
+        match word_width {
+            WordWidth::Width32 => {
+                w.u32(name).word(value).word(size).u8(info).u8(other);
+            }
+            WordWidth::Width64 => {
+                w.u32(name).u8(info).u8(other).u16(section_index);
+            }
+        }
+        match word_width {
+            WordWidth::Width32 => w.u16(section_index),
+            WordWidth::Width64 => w.word(value).word(size),
+        };
+        w.bytes
+    }
+
+    #[test]
+    fn test_symbol_all_layouts() {
+        let (names, indices) = string_table(&["main"]);
+        for &word_width in [WordWidth::Width32, WordWidth::Width64].iter() {
+            for &endianness in [Endianness::Little, Endianness::Big].iter() {
+                let bytes = symbol_bytes(
+                    word_width, endianness, indices[0], 0x1130, 0x2A, 0x12, 0x02, 14,
+                );
+                assert_eq!(bytes.len(), Symbol::entry_size(word_width));
+                let symbol = Symbol::parse_bytes(&bytes, &names, word_width, endianness)
+                    .expect("valid symbol");
+                assert_eq!(symbol.name(), "main");
+                assert_eq!(u64::from(symbol.value()), 0x1130);
+                assert_eq!(u64::from(symbol.size()), 0x2A);
+                assert_eq!(symbol.binding(), SymbolBinding::Global);
+                assert_eq!(symbol.typ(), SymbolType::Function);
+                assert_eq!(symbol.visibility(), SymbolVisibility::Hidden);
+                assert_eq!(symbol.section_index(), SymbolSectionIndex::Index(14));
+            }
+        }
+    }
+
+    #[test]
+    fn test_symbol_section_index() {
+        use SymbolSectionIndex::*;
+        let test_data = [
+            (0x0000, Undefined),
+            (0x0001, Index(1)),
+            (0xFEFF, Index(0xFEFF)),
+            (0xFF00, Reserved(0xFF00)),
+            (0xFFF1, Absolute),
+            (0xFFF2, Common),
+            (0xFFFF, ExtendedIndex),
+        ];
+        for (raw, expected) in test_data.iter() {
+            assert_eq!(SymbolSectionIndex::from_u16(*raw), *expected);
+        }
+    }
+
+    #[test]
+    fn test_symbol_err() {
+        let names = [0];
+        let bytes = symbol_bytes(WordWidth::Width64, Endianness::Little, 0, 0, 0, 0x30, 0, 0);
+        assert_eq!(
+            Symbol::parse_bytes(&bytes, &names, WordWidth::Width64, Endianness::Little),
+            Err(ParseError::InvalidSymbolBinding(3))
+        );
+        assert_eq!(
+            Symbol::parse_bytes(&bytes[..20], &names, WordWidth::Width64, Endianness::Little),
+            Err(ParseError::InsufficientSymbolLength(20))
+        );
+        let bytes = symbol_bytes(WordWidth::Width32, Endianness::Big, 5, 0, 0, 0, 0, 0);
+        assert_eq!(
+            Symbol::parse_bytes(&bytes, &names, WordWidth::Width32, Endianness::Big),
+            Err(ParseError::UnterminatedString)
+        );
+    }
+
+    #[test]
+    fn test_symbol_table_section() {
+        let mut fixture = Fixture::new(WordWidth::Width32, Endianness::Big);
+        let (names, indices) = string_table(&["a.c", "counter"]);
+        let mut table = symbol_bytes(fixture.word_width, fixture.endianness, 0, 0, 0, 0, 0, 0);
+        table.extend(symbol_bytes(
+            fixture.word_width,
+            fixture.endianness,
+            indices[0],
+            0,
+            0,
+            0x04,
+            0,
+            0xFFF1,
+        ));
+        table.extend(symbol_bytes(
+            fixture.word_width,
+            fixture.endianness,
+            indices[1],
+            0x2000,
+            4,
+            0x11,
+            0,
+            0,
+        ));
+        let strtab = fixture.section(FixtureSection::new(".strtab", 3, names));
+        fixture.section(
+            FixtureSection::new(".symtab", 2, table)
+                .link(strtab as u32)
+                .info(2)
+                .align(4)
+                .entry_size(16),
+        );
+        let bytes = fixture.build();
+        let elf = ElfFile::parse(&bytes).expect("valid ELF");
+        let symbols: Vec<_> = elf
+            .symbol_table()
+            .expect("valid symbol table")
+            .expect("has a symbol table")
+            .collect::<Result<_>>()
+            .expect("valid symbols");
+        assert_eq!(symbols.len(), 3);
+        assert_eq!(symbols[0].name(), "");
+        assert_eq!(symbols[1].name(), "a.c");
+        assert_eq!(symbols[1].typ(), SymbolType::File);
+        assert_eq!(symbols[1].section_index(), SymbolSectionIndex::Absolute);
+        assert_eq!(symbols[2].name(), "counter");
+        assert_eq!(symbols[2].value(), Word::Word32(0x2000));
+        assert_eq!(symbols[2].typ(), SymbolType::Object);
+        assert_eq!(symbols[2].section_index(), SymbolSectionIndex::Undefined);
+        assert!(elf
+            .dynamic_symbol_table()
+            .expect("valid section headers")
+            .is_none());
+    }
+}
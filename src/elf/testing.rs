@@ -0,0 +1,369 @@
+//! Helpers for assembling small ELF files in unit tests.
+#![allow(dead_code)]
+
+use std::ops::Range;
+
+use super::{align_up, Endianness, WordWidth};
+
+const BASE_ADDRESS: u64 = 0x400000;
+const DATA_START: u64 = 0x200;
+
+/// Serializes integers with a fixed word width and endianness.
+pub(crate) struct Writer {
+    pub word_width: WordWidth,
+    pub endianness: Endianness,
+    pub bytes: Vec<u8>,
+}
+
+impl Writer {
+    pub fn new(word_width: WordWidth, endianness: Endianness) -> Self {
+        Writer {
+            word_width,
+            endianness,
+            bytes: Vec::new(),
+        }
+    }
+
+    pub fn u8(&mut self, value: u8) -> &mut Self {
+        self.bytes.push(value);
+        self
+    }
+
+    pub fn u16(&mut self, value: u16) -> &mut Self {
+        match self.endianness {
+            Endianness::Little => self.bytes.extend_from_slice(&value.to_le_bytes()),
+            Endianness::Big => self.bytes.extend_from_slice(&value.to_be_bytes()),
+        }
+        self
+    }
+
+    pub fn u32(&mut self, value: u32) -> &mut Self {
+        match self.endianness {
+            Endianness::Little => self.bytes.extend_from_slice(&value.to_le_bytes()),
+            Endianness::Big => self.bytes.extend_from_slice(&value.to_be_bytes()),
+        }
+        self
+    }
+
+    pub fn u64(&mut self, value: u64) -> &mut Self {
+        match self.endianness {
+            Endianness::Little => self.bytes.extend_from_slice(&value.to_le_bytes()),
+            Endianness::Big => self.bytes.extend_from_slice(&value.to_be_bytes()),
+        }
+        self
+    }
+
+    pub fn word(&mut self, value: u64) -> &mut Self {
+        match self.word_width {
+            WordWidth::Width32 => self.u32(value as u32),
+            WordWidth::Width64 => self.u64(value),
+        }
+    }
+
+    pub fn bytes(&mut self, bytes: &[u8]) -> &mut Self {
+        self.bytes.extend_from_slice(bytes);
+        self
+    }
+
+    pub fn align(&mut self, align: u64) -> &mut Self {
+        let len = self.bytes.len() as u64;
+        let align = align.max(1);
+        self.bytes.resize(align_up(len, align) as usize, 0);
+        self
+    }
+}
+
+pub(crate) struct FixtureSection {
+    pub name: String,
+    pub typ: u32,
+    pub flags: u64,
+    pub data: Vec<u8>,
+    pub link: u32,
+    pub info: u32,
+    pub align: u64,
+    pub entry_size: u64,
+    /// size of `NoData` sections, which have no data in the file
+    pub nobits_size: u64,
+}
+
+pub(crate) struct FixtureSegment {
+    pub typ: u32,
+    pub flags: u32,
+    /// indices of the sections covered by the segment (the null section is index 0)
+    pub sections: Range<usize>,
+    pub align: u64,
+}
+
+/// Assembles an ELF file from sections and segments. Offsets are laid out automatically and
+/// allocated sections get the address `0x400000 + offset`. A `.shstrtab` is appended as the last
+/// section.
+pub(crate) struct Fixture {
+    pub word_width: WordWidth,
+    pub endianness: Endianness,
+    pub file_type: u16,
+    pub arch: u16,
+    pub flags: u32,
+    pub sections: Vec<FixtureSection>,
+    pub segments: Vec<FixtureSegment>,
+}
+
+impl FixtureSection {
+    pub fn new(name: &str, typ: u32, data: Vec<u8>) -> Self {
+        FixtureSection {
+            name: name.to_string(),
+            typ,
+            flags: 0,
+            data,
+            link: 0,
+            info: 0,
+            align: 1,
+            entry_size: 0,
+            nobits_size: 0,
+        }
+    }
+
+    pub fn flags(mut self, flags: u64) -> Self {
+        self.flags = flags;
+        self
+    }
+
+    pub fn link(mut self, link: u32) -> Self {
+        self.link = link;
+        self
+    }
+
+    pub fn info(mut self, info: u32) -> Self {
+        self.info = info;
+        self
+    }
+
+    pub fn align(mut self, align: u64) -> Self {
+        self.align = align;
+        self
+    }
+
+    pub fn entry_size(mut self, entry_size: u64) -> Self {
+        self.entry_size = entry_size;
+        self
+    }
+
+    pub fn nobits_size(mut self, size: u64) -> Self {
+        self.nobits_size = size;
+        self
+    }
+}
+
+impl Fixture {
+    pub fn new(word_width: WordWidth, endianness: Endianness) -> Self {
+        Fixture {
+            word_width,
+            endianness,
+            file_type: 2,
+            arch: 0x3E,
+            flags: 0,
+            sections: Vec::new(),
+            segments: Vec::new(),
+        }
+    }
+
+    pub fn writer(&self) -> Writer {
+        Writer::new(self.word_width, self.endianness)
+    }
+
+    /// Adds a section and returns its index in the section header table.
+    pub fn section(&mut self, section: FixtureSection) -> usize {
+        self.sections.push(section);
+        self.sections.len()
+    }
+
+    pub fn segment(&mut self, typ: u32, flags: u32, sections: Range<usize>, align: u64) {
+        self.segments.push(FixtureSegment {
+            typ,
+            flags,
+            sections,
+            align,
+        });
+    }
+
+    /// Returns the address the section with the given index will be placed at.
+    pub fn address_of(&self, index: usize) -> u64 {
+        let (_, addresses) = self.layout();
+        addresses[index].1
+    }
+
+    fn sizes(&self) -> (u64, u64, u64) {
+        match self.word_width {
+            WordWidth::Width32 => (52, 32, 40),
+            WordWidth::Width64 => (64, 56, 64),
+        }
+    }
+
+    fn names(&self) -> (Vec<u8>, Vec<u32>) {
+        let mut table = vec![0];
+        let mut indices = Vec::new();
+        for section in self.sections.iter() {
+            indices.push(table.len() as u32);
+            table.extend_from_slice(section.name.as_bytes());
+            table.push(0);
+        }
+        indices.push(table.len() as u32);
+        table.extend_from_slice(b".shstrtab\0");
+        (table, indices)
+    }
+
+    /// Returns the end of the section data and the (offset, address) of every section.
+    fn layout(&self) -> (u64, Vec<(u64, u64)>) {
+        // leave room for up to 8 program headers, so addresses don't change when segments are added
+        assert!(self.segments.len() <= 8);
+        let mut offset = DATA_START;
+        let mut layout = vec![(0, 0)];
+        for section in self.sections.iter() {
+            let align = section.align.max(1);
+            offset = align_up(offset, align);
+            let address = if section.flags & 0x2 != 0 {
+                BASE_ADDRESS + offset
+            } else {
+                0
+            };
+            layout.push((offset, address));
+            offset += section.data.len() as u64;
+        }
+        (offset, layout)
+    }
+
+    pub fn build(&self) -> Vec<u8> {
+        let (header_size, pheader_size, sheader_size) = self.sizes();
+        let (names, name_indices) = self.names();
+        let (data_end, layout) = self.layout();
+        let names_offset = data_end;
+        let sheader_offset = align_up(names_offset + names.len() as u64, 8);
+        let section_count = self.sections.len() as u16 + 2;
+        let section_size = |index: usize| {
+            let section = &self.sections[index - 1];
+            if section.typ == 8 {
+                section.nobits_size
+            } else {
+                section.data.len() as u64
+            }
+        };
+
+        let mut w = self.writer();
+        w.bytes(&[0x7F, 0x45, 0x4C, 0x46]);
+        w.u8(match self.word_width {
+            WordWidth::Width32 => 1,
+            WordWidth::Width64 => 2,
+        });
+        w.u8(match self.endianness {
+            Endianness::Little => 1,
+            Endianness::Big => 2,
+        });
+        w.u8(1).u8(0).u8(0).bytes(&[0; 7]);
+        w.u16(self.file_type).u16(self.arch).u32(1);
+        w.word(BASE_ADDRESS)
+            .word(header_size)
+            .word(sheader_offset)
+            .u32(self.flags);
+        w.u16(header_size as u16)
+            .u16(pheader_size as u16)
+            .u16(self.segments.len() as u16)
+            .u16(sheader_size as u16)
+            .u16(section_count)
+            .u16(section_count - 1);
+
+        for segment in self.segments.iter() {
+            let (offset, address) = layout[segment.sections.start];
+            let file_end = segment
+                .sections
+                .clone()
+                .filter(|&i| self.sections[i - 1].typ != 8)
+                .map(|i| layout[i].0 + section_size(i))
+                .max()
+                .unwrap_or(offset);
+            let mem_end = segment
+                .sections
+                .clone()
+                .map(|i| layout[i].0 + section_size(i))
+                .max()
+                .unwrap_or(offset);
+            w.u32(segment.typ);
+            if self.word_width == WordWidth::Width64 {
+                w.u32(segment.flags);
+            }
+            w.word(offset)
+                .word(address)
+                .word(address)
+                .word(file_end - offset)
+                .word(mem_end - offset);
+            if self.word_width == WordWidth::Width32 {
+                w.u32(segment.flags);
+            }
+            w.word(segment.align);
+        }
+
+        for (section, &(offset, _)) in self.sections.iter().zip(layout[1..].iter()) {
+            w.bytes.resize(offset as usize, 0);
+            w.bytes(&section.data);
+        }
+        w.bytes.resize(names_offset as usize, 0);
+        w.bytes(&names);
+        w.bytes.resize(sheader_offset as usize, 0);
+
+        let sheader = |w: &mut Writer, fields: [u64; 10]| {
+            w.u32(fields[0] as u32).u32(fields[1] as u32);
+            w.word(fields[2])
+                .word(fields[3])
+                .word(fields[4])
+                .word(fields[5]);
+            w.u32(fields[6] as u32).u32(fields[7] as u32);
+            w.word(fields[8]).word(fields[9]);
+        };
+        sheader(&mut w, [0; 10]);
+        for (i, section) in self.sections.iter().enumerate() {
+            let (offset, address) = layout[i + 1];
+            sheader(
+                &mut w,
+                [
+                    name_indices[i] as u64,
+                    section.typ as u64,
+                    section.flags,
+                    address,
+                    offset,
+                    section_size(i + 1),
+                    section.link as u64,
+                    section.info as u64,
+                    section.align,
+                    section.entry_size,
+                ],
+            );
+        }
+        sheader(
+            &mut w,
+            [
+                name_indices[self.sections.len()] as u64,
+                3,
+                0,
+                0,
+                names_offset,
+                names.len() as u64,
+                0,
+                0,
+                1,
+                0,
+            ],
+        );
+        w.bytes
+    }
+}
+
+/// Builds a string table from the given strings and returns it together with the index of every
+/// string.
+pub(crate) fn string_table(strings: &[&str]) -> (Vec<u8>, Vec<u32>) {
+    let mut table = vec![0];
+    let mut indices = Vec::new();
+    for string in strings {
+        indices.push(table.len() as u32);
+        table.extend_from_slice(string.as_bytes());
+        table.push(0);
+    }
+    (table, indices)
+}
@@ -0,0 +1,457 @@
+use std::fmt::{Display, Formatter};
+
+use super::*;
+
+/// Section type of `.gnu.version_d`, the versions defined by the file
+pub(crate) const SHT_GNU_VERDEF: u32 = 0x6FFFFFFD;
+/// Section type of `.gnu.version_r`, the versions required from other files
+pub(crate) const SHT_GNU_VERNEED: u32 = 0x6FFFFFFE;
+/// Section type of `.gnu.version`, the version index of each dynamic symbol
+pub(crate) const SHT_GNU_VERSYM: u32 = 0x6FFFFFFF;
+
+bitflags::bitflags! {
+    pub struct VersionFlags: u16 {
+        /// The version definition of the file itself
+        const BASE = 0x1;
+        /// A weak version reference
+        const WEAK = 0x2;
+        const INFO = 0x4;
+    }
+}
+
+/// An entry of the `.gnu.version` section. The highest bit marks hidden symbols, the remaining
+/// bits are the index of a version definition or requirement.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct VersionIndex(u16);
+
+/// An entry of the `.gnu.version_d` section. The first name is the defined version, the
+/// following ones are its parents.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct VersionDefinition {
+    revision: u16,
+    flags: VersionFlags,
+    index: u16,
+    hash: u32,
+    names: Vec<String>,
+}
+
+/// An entry of the `.gnu.version_r` section, which lists the versions required from one file.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct VersionRequirement {
+    revision: u16,
+    file: String,
+    entries: Vec<VersionRequirementEntry>,
+}
+
+/// A single version required from the file of a `VersionRequirement`.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct VersionRequirementEntry {
+    hash: u32,
+    flags: VersionFlags,
+    index: u16,
+    name: String,
+}
+
+/// The version information of a dynamic symbol table.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct SymbolVersions {
+    indices: Vec<VersionIndex>,
+    definitions: Vec<VersionDefinition>,
+    requirements: Vec<VersionRequirement>,
+}
+
+/// The version of a single symbol.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum SymbolVersion<'a> {
+    /// The symbol is not visible outside of the file
+    Local,
+    /// The symbol is unversioned
+    Global,
+    Defined {
+        name: &'a str,
+        hidden: bool,
+    },
+    Required {
+        name: &'a str,
+        file: &'a str,
+    },
+}
+
+fn entry(data: &[u8], offset: usize, size: usize) -> Result<&[u8]> {
+    offset
+        .checked_add(size)
+        .and_then(|end| data.get(offset..end))
+        .ok_or_else(|| ParseError::InsufficientVersionLength(data.len().saturating_sub(offset)))
+}
+
+fn advance(offset: usize, next: u32) -> Result<usize> {
+    offset
+        .checked_add(next as usize)
+        .ok_or(ParseError::InsufficientVersionLength(0))
+}
+
+impl VersionIndex {
+    /// Index of symbols that are local to the file
+    pub const LOCAL: u16 = 0;
+    /// Index of unversioned global symbols
+    pub const GLOBAL: u16 = 1;
+
+    /// Parses the contents of a `.gnu.version` section, a trailing odd byte is ignored.
+    pub fn parse_table(bytes: &[u8], endianness: Endianness) -> Vec<VersionIndex> {
+        bytes
+            .chunks_exact(2)
+            .filter_map(|chunk| u16::from_bytes(chunk, endianness).ok())
+            .map(VersionIndex)
+            .collect()
+    }
+
+    pub const fn raw(&self) -> u16 {
+        self.0
+    }
+
+    pub const fn index(&self) -> u16 {
+        self.0 & 0x7FFF
+    }
+
+    pub const fn is_hidden(&self) -> bool {
+        self.0 & 0x8000 != 0
+    }
+}
+
+impl VersionDefinition {
+    /// Parses `count` version definitions, which is the `info` field of the section.
+    pub fn parse_table(
+        bytes: &[u8],
+        count: u32,
+        strings: &[u8],
+        endianness: Endianness,
+    ) -> Result<Vec<VersionDefinition>> {
+        let mut definitions = Vec::new();
+        let mut offset = 0;
+        for _ in 0..count {
+            let raw = entry(bytes, offset, 20)?;
+            let aux_count = u16::from_bytes(&raw[6..], endianness)?;
+            let mut aux_offset = advance(offset, u32::from_bytes(&raw[12..], endianness)?)?;
+            let mut names = Vec::new();
+            for _ in 0..aux_count {
+                let aux = entry(bytes, aux_offset, 8)?;
+                let name = u32::from_bytes(aux, endianness)?;
+                names.push(parse_string(strings, name as usize)?.to_string());
+                let next = u32::from_bytes(&aux[4..], endianness)?;
+                if next == 0 {
+                    break;
+                }
+                aux_offset = advance(aux_offset, next)?;
+            }
+            definitions.push(VersionDefinition {
+                revision: u16::from_bytes(raw, endianness)?,
+                flags: VersionFlags::from_bits_truncate(u16::from_bytes(&raw[2..], endianness)?),
+                index: u16::from_bytes(&raw[4..], endianness)?,
+                hash: u32::from_bytes(&raw[8..], endianness)?,
+                names,
+            });
+            let next = u32::from_bytes(&raw[16..], endianness)?;
+            if next == 0 {
+                break;
+            }
+            offset = advance(offset, next)?;
+        }
+        Ok(definitions)
+    }
+
+    pub const fn revision(&self) -> u16 {
+        self.revision
+    }
+
+    pub const fn flags(&self) -> VersionFlags {
+        self.flags
+    }
+
+    /// The version index used by the `.gnu.version` entries
+    pub const fn index(&self) -> u16 {
+        self.index
+    }
+
+    pub const fn hash(&self) -> u32 {
+        self.hash
+    }
+
+    /// The name of the defined version
+    pub fn name(&self) -> Option<&str> {
+        self.names.first().map(String::as_str)
+    }
+
+    /// The names of the versions this version inherits from
+    pub fn parents(&self) -> &[String] {
+        self.names.get(1..).unwrap_or(&[])
+    }
+}
+
+impl VersionRequirement {
+    /// Parses `count` version requirements, which is the `info` field of the section.
+    pub fn parse_table(
+        bytes: &[u8],
+        count: u32,
+        strings: &[u8],
+        endianness: Endianness,
+    ) -> Result<Vec<VersionRequirement>> {
+        let mut requirements = Vec::new();
+        let mut offset = 0;
+        for _ in 0..count {
+            let raw = entry(bytes, offset, 16)?;
+            let aux_count = u16::from_bytes(&raw[2..], endianness)?;
+            let file = u32::from_bytes(&raw[4..], endianness)?;
+            let mut aux_offset = advance(offset, u32::from_bytes(&raw[8..], endianness)?)?;
+            let mut entries = Vec::new();
+            for _ in 0..aux_count {
+                let aux = entry(bytes, aux_offset, 16)?;
+                let name = u32::from_bytes(&aux[8..], endianness)?;
+                entries.push(VersionRequirementEntry {
+                    hash: u32::from_bytes(aux, endianness)?,
+                    flags: VersionFlags::from_bits_truncate(u16::from_bytes(
+                        &aux[4..],
+                        endianness,
+                    )?),
+                    index: u16::from_bytes(&aux[6..], endianness)?,
+                    name: parse_string(strings, name as usize)?.to_string(),
+                });
+                let next = u32::from_bytes(&aux[12..], endianness)?;
+                if next == 0 {
+                    break;
+                }
+                aux_offset = advance(aux_offset, next)?;
+            }
+            requirements.push(VersionRequirement {
+                revision: u16::from_bytes(raw, endianness)?,
+                file: parse_string(strings, file as usize)?.to_string(),
+                entries,
+            });
+            let next = u32::from_bytes(&raw[12..], endianness)?;
+            if next == 0 {
+                break;
+            }
+            offset = advance(offset, next)?;
+        }
+        Ok(requirements)
+    }
+
+    pub const fn revision(&self) -> u16 {
+        self.revision
+    }
+
+    /// The name of the file the versions are required from, e.g. `libc.so.6`
+    pub fn file(&self) -> &str {
+        self.file.as_str()
+    }
+
+    pub fn entries(&self) -> &[VersionRequirementEntry] {
+        self.entries.as_slice()
+    }
+}
+
+impl VersionRequirementEntry {
+    pub const fn hash(&self) -> u32 {
+        self.hash
+    }
+
+    pub const fn flags(&self) -> VersionFlags {
+        self.flags
+    }
+
+    /// The version index used by the `.gnu.version` entries
+    pub const fn index(&self) -> u16 {
+        self.index
+    }
+
+    /// The name of the required version, e.g. `GLIBC_2.14`
+    pub fn name(&self) -> &str {
+        self.name.as_str()
+    }
+}
+
+impl SymbolVersions {
+    pub fn new(
+        indices: Vec<VersionIndex>,
+        definitions: Vec<VersionDefinition>,
+        requirements: Vec<VersionRequirement>,
+    ) -> SymbolVersions {
+        SymbolVersions {
+            indices,
+            definitions,
+            requirements,
+        }
+    }
+
+    /// The version indices of the symbols, in symbol table order
+    pub fn indices(&self) -> &[VersionIndex] {
+        self.indices.as_slice()
+    }
+
+    pub fn definitions(&self) -> &[VersionDefinition] {
+        self.definitions.as_slice()
+    }
+
+    pub fn requirements(&self) -> &[VersionRequirement] {
+        self.requirements.as_slice()
+    }
+
+    /// Resolves a version index to the name of the defined or required version.
+    pub fn resolve(&self, index: VersionIndex) -> Option<SymbolVersion<'_>> {
+        match index.index() {
+            VersionIndex::LOCAL => return Some(SymbolVersion::Local),
+            VersionIndex::GLOBAL => return Some(SymbolVersion::Global),
+            _ => {}
+        }
+        let defined = self
+            .definitions
+            .iter()
+            .find(|definition| definition.index == index.index())
+            .and_then(|definition| definition.name());
+        if let Some(name) = defined {
+            return Some(SymbolVersion::Defined {
+                name,
+                hidden: index.is_hidden(),
+            });
+        }
+        self.requirements.iter().find_map(|requirement| {
+            requirement
+                .entries
+                .iter()
+                .find(|entry| entry.index == index.index())
+                .map(|entry| SymbolVersion::Required {
+                    name: entry.name(),
+                    file: requirement.file(),
+                })
+        })
+    }
+
+    /// The version of the symbol with the given index in the dynamic symbol table
+    pub fn version(&self, symbol_index: usize) -> Option<SymbolVersion<'_>> {
+        self.resolve(*self.indices.get(symbol_index)?)
+    }
+
+    /// The name of the symbol with its version appended like the GNU tools do, e.g.
+    /// `memcpy@GLIBC_2.14` for required and hidden versions and `foo@@VERS_1` for the default
+    /// version of a defined symbol.
+    pub fn versioned_name(&self, symbol_index: usize, symbol: &Symbol) -> String {
+        match self.version(symbol_index) {
+            Some(SymbolVersion::Defined { name, hidden }) => {
+                let separator = if hidden || symbol.section_index() == SymbolSectionIndex::Undefined
+                {
+                    "@"
+                } else {
+                    "@@"
+                };
+                format!("{}{}{}", symbol.name(), separator, name)
+            }
+            Some(SymbolVersion::Required { name, .. }) => format!("{}@{}", symbol.name(), name),
+            _ => symbol.name().to_string(),
+        }
+    }
+}
+
+impl Display for VersionFlags {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.is_empty() {
+            return write!(f, "none");
+        }
+        let names: Vec<_> = [
+            (VersionFlags::BASE, "BASE"),
+            (VersionFlags::WEAK, "WEAK"),
+            (VersionFlags::INFO, "INFO"),
+        ]
+        .iter()
+        .filter(|(flag, _)| self.contains(*flag))
+        .map(|(_, name)| *name)
+        .collect();
+        write!(f, "{}", names.join(" | "))
+    }
+}
+
+impl<'a> Display for SymbolVersion<'a> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SymbolVersion::Local => write!(f, "*local*"),
+            SymbolVersion::Global => write!(f, "*global*"),
+            SymbolVersion::Defined { name, hidden: true } => write!(f, "{}h", name),
+            SymbolVersion::Defined { name, .. } | SymbolVersion::Required { name, .. } => {
+                write!(f, "{}", name)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::elf::testing::{string_table, Writer};
+
+    #[test]
+    fn test_version_requirements() {
+        let (strings, names) = string_table(&["libc.so.6", "GLIBC_2.14", "GLIBC_2.2.5"]);
+        let mut w = Writer::new(WordWidth::Width64, Endianness::Little);
+        w.u16(1).u16(2).u32(names[0]).u32(16).u32(0);
+        w.u32(0x06969194).u16(0).u16(3).u32(names[1]).u32(16);
+        w.u32(0x09691A75).u16(0).u16(2).u32(names[2]).u32(0);
+        let requirements =
+            VersionRequirement::parse_table(&w.bytes, 1, &strings, Endianness::Little)
+                .expect("valid requirements");
+        assert_eq!(requirements.len(), 1);
+        assert_eq!(requirements[0].file(), "libc.so.6");
+        let entries: Vec<_> = requirements[0]
+            .entries()
+            .iter()
+            .map(|entry| (entry.index(), entry.name()))
+            .collect();
+        assert_eq!(entries, vec![(3, "GLIBC_2.14"), (2, "GLIBC_2.2.5")]);
+
+        let indices = VersionIndex::parse_table(&[0, 0, 3, 0, 1, 0], Endianness::Little);
+        let versions = SymbolVersions::new(indices, Vec::new(), requirements);
+        assert_eq!(versions.version(0), Some(SymbolVersion::Local));
+        assert_eq!(
+            versions.version(1),
+            Some(SymbolVersion::Required {
+                name: "GLIBC_2.14",
+                file: "libc.so.6"
+            })
+        );
+        assert_eq!(versions.version(2), Some(SymbolVersion::Global));
+        assert_eq!(versions.version(3), None);
+    }
+
+    #[test]
+    fn test_version_definitions() {
+        let (strings, names) = string_table(&["libfoo.so.1", "FOO_1", "FOO_2"]);
+        let mut w = Writer::new(WordWidth::Width64, Endianness::Little);
+        w.u16(1).u16(1).u16(1).u16(1).u32(0).u32(20).u32(28);
+        w.u32(names[0]).u32(0);
+        w.u16(1).u16(0).u16(2).u16(1).u32(0).u32(20).u32(28);
+        w.u32(names[1]).u32(0);
+        w.u16(1).u16(0).u16(3).u16(2).u32(0).u32(20).u32(0);
+        w.u32(names[2]).u32(8).u32(names[1]).u32(0);
+        let definitions = VersionDefinition::parse_table(&w.bytes, 3, &strings, Endianness::Little)
+            .expect("valid definitions");
+        assert_eq!(definitions.len(), 3);
+        assert_eq!(definitions[0].flags(), VersionFlags::BASE);
+        assert_eq!(definitions[2].name(), Some("FOO_2"));
+        assert_eq!(definitions[2].parents(), &["FOO_1".to_string()]);
+
+        let versions = SymbolVersions::new(
+            VersionIndex::parse_table(&[0, 0, 3, 0, 2, 0x80], Endianness::Little),
+            definitions,
+            Vec::new(),
+        );
+        let mut w = Writer::new(WordWidth::Width64, Endianness::Little);
+        w.u32(1).u8(0x12).u8(0).u16(5).u64(0x1000).u64(0);
+        let symbol =
+            Symbol::parse_bytes(&w.bytes, b"\0foo\0", WordWidth::Width64, Endianness::Little)
+                .expect("valid symbol");
+        assert_eq!(versions.versioned_name(0, &symbol), "foo");
+        assert_eq!(versions.versioned_name(1, &symbol), "foo@@FOO_2");
+        assert_eq!(versions.versioned_name(2, &symbol), "foo@FOO_1");
+        assert_eq!(
+            VersionDefinition::parse_table(&w.bytes[..10], 1, &strings, Endianness::Little),
+            Err(ParseError::InsufficientVersionLength(10))
+        );
+    }
+}
@@ -0,0 +1,8 @@
+#![deny(clippy::all)]
+
+//! Library for reading the metadata of binary files in the ELF format.
+//!
+//! The parsing code lives in the [`elf`] module. The `elfreader` binary is a thin
+//! command line frontend on top of it.
+
+pub mod elf;
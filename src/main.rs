@@ -1,12 +1,17 @@
 #![deny(clippy::all)]
 
 mod cli;
-mod elf;
 use clap::Parser;
 
-use crate::elf::Metadata;
+use elfreader::elf::{
+    signal_name, AddressMapping, Archive, CoreNote, ElfEditor, ElfFile, ElfStripper, FileType,
+    Metadata, ProgramHeaderSegmentType, SectionHeaderFlags, SectionHeaderType, SymbolSectionIndex,
+    SymbolType,
+};
+use memmap2::Mmap;
 
 use std::fs::File;
+use std::path::Path;
 
 fn main() -> Result<(), i32> {
     let arguments = cli::Arguments::parse();
@@ -14,9 +19,18 @@ fn main() -> Result<(), i32> {
         println!("{}", cli::VERSION);
         return Ok(());
     }
-    let filename = arguments.path;
+    match &arguments.command {
+        Some(cli::Command::Edit(edit)) => return edit_file(edit),
+        Some(cli::Command::Strip(strip)) => return strip_file(strip),
+        Some(cli::Command::Addr(addr)) => return print_address(addr),
+        None => {}
+    }
+    let filename = match &arguments.path {
+        Some(path) => path,
+        None => return Err(1),
+    };
     println!("Parsing ELF header of file {:?}", filename);
-    let mut file = match File::open(filename.as_path()) {
+    let file = match File::open(filename.as_path()) {
         Ok(f) => f,
         Err(e) => {
             eprintln!("Can not open file {:?} Reason:", filename);
@@ -24,24 +38,331 @@ fn main() -> Result<(), i32> {
             return Err(1);
         }
     };
-    let metadata = match Metadata::parse_file(&mut file) {
+    // SAFETY: the mapping is only read and the file is not modified while elfreader runs
+    let mmap = match unsafe { Mmap::map(&file) } {
+        Ok(mmap) => mmap,
+        Err(e) => {
+            eprintln!("Can not map file {:?} Reason:", filename);
+            eprintln!("{}", e);
+            return Err(1);
+        }
+    };
+    if Archive::is_archive(&mmap) {
+        return print_archive(&arguments, &mmap);
+    }
+    print_file(&arguments, &mmap)
+}
+
+fn edit_file(arguments: &cli::EditArguments) -> Result<(), i32> {
+    let data = read_file(&arguments.path)?;
+    let mut editor = ElfEditor::new();
+    if let Some(interpreter) = arguments.set_interpreter.as_deref() {
+        editor.set_interpreter(interpreter);
+    }
+    if let Some(rpath) = arguments.set_rpath.as_deref() {
+        editor.set_rpath(rpath);
+    }
+    if let Some(soname) = arguments.set_soname.as_deref() {
+        editor.set_soname(soname);
+    }
+    for library in arguments.add_needed.iter() {
+        editor.add_needed(library);
+    }
+    for library in arguments.remove_needed.iter() {
+        editor.remove_needed(library);
+    }
+    let edited = match editor.apply(&data) {
+        Ok(edited) => edited,
+        Err(error) => {
+            eprintln!("Error editing the ELF file:");
+            eprintln!("{}", error);
+            return Err(1);
+        }
+    };
+    let output = arguments.output.as_ref().unwrap_or(&arguments.path);
+    write_file(output, &arguments.path, &edited)
+}
+
+fn strip_file(arguments: &cli::StripArguments) -> Result<(), i32> {
+    let data = read_file(&arguments.path)?;
+    let mut stripper = ElfStripper::new();
+    let selected = arguments.strip_debug || !arguments.remove_section.is_empty();
+    if arguments.strip_all || !selected {
+        stripper.strip_debug().strip_symbols();
+    } else if arguments.strip_debug {
+        stripper.strip_debug();
+    }
+    for name in arguments.remove_section.iter() {
+        stripper.remove_section(name);
+    }
+    let mut debug_file = None;
+    if let Some(debug_path) = arguments.debug_file.as_ref() {
+        let debug = match stripper.debug_file(&data) {
+            Ok(debug) => debug,
+            Err(error) => {
+                eprintln!("Error extracting the debug sections:");
+                eprintln!("{}", error);
+                return Err(1);
+            }
+        };
+        let file_name = debug_path.file_name().unwrap_or_default();
+        stripper.set_debuglink(&file_name.to_string_lossy(), &debug);
+        debug_file = Some((debug_path, debug));
+    }
+    let stripped = match stripper.apply(&data) {
+        Ok(stripped) => stripped,
+        Err(error) => {
+            eprintln!("Error stripping the ELF file:");
+            eprintln!("{}", error);
+            return Err(1);
+        }
+    };
+    // only write anything once both files were produced, so a failure leaves no stray debug file
+    if let Some((debug_path, debug)) = debug_file {
+        write_file(debug_path, &arguments.path, &debug)?;
+    }
+    let output = arguments.output.as_ref().unwrap_or(&arguments.path);
+    write_file(output, &arguments.path, &stripped)
+}
+
+fn print_address(arguments: &cli::AddrArguments) -> Result<(), i32> {
+    let data = read_file(&arguments.path)?;
+    let metadata = match Metadata::parse_bytes(&data) {
         Ok(metadata) => metadata,
         Err(error) => {
             eprintln!("Error parsing the ELF metadata:");
-            eprintln!("{:#x?}", error);
+            eprintln!("{}", error);
+            return Err(1);
+        }
+    };
+    let value = arguments.address;
+    let (vaddr, offset) = if arguments.offset {
+        println!("File offset {:#x}", value);
+        match metadata.offset_to_vaddr(value) {
+            AddressMapping::Mapped(segment, vaddr) => {
+                print_segment(&metadata, segment);
+                println!("Virtual address {:#x}", vaddr);
+                (Some(vaddr), Some(value))
+            }
+            mapping => {
+                print_unmapped(&metadata, mapping, "offset");
+                (None, Some(value))
+            }
+        }
+    } else {
+        println!("Virtual address {:#x}", value);
+        match metadata.vaddr_to_offset(value) {
+            AddressMapping::Mapped(segment, offset) => {
+                print_segment(&metadata, segment);
+                println!("File offset {:#x}", offset);
+                (Some(value), Some(offset))
+            }
+            AddressMapping::ZeroFilled(segment) => {
+                print_segment(&metadata, segment);
+                println!("Not backed by the file, zero filled at load time");
+                (Some(value), None)
+            }
+            mapping => {
+                print_unmapped(&metadata, mapping, "address");
+                (None, None)
+            }
+        }
+    };
+
+    // sections are looked up by address if possible, unloaded ones like .debug_info by offset
+    let (value, in_memory) = match (vaddr, offset) {
+        (Some(vaddr), _) => (vaddr, true),
+        (None, Some(offset)) => (offset, false),
+        (None, None) => return Ok(()),
+    };
+    let mut found = false;
+    for (i, sheader) in metadata.section_headers().iter().enumerate() {
+        let no_data = sheader.typ() == SectionHeaderType::NoData;
+        let start = if in_memory {
+            let flags = sheader.flags();
+            // .tbss only exists in the TLS template and overlaps the following sections
+            if !flags.contains(SectionHeaderFlags::ALLOC)
+                || (no_data && flags.contains(SectionHeaderFlags::THREAD_LOCAL))
+            {
+                continue;
+            }
+            u64::from(sheader.address())
+        } else if no_data {
+            continue;
+        } else {
+            u64::from(sheader.offset())
+        };
+        if value >= start && value - start < u64::from(sheader.size()) {
+            println!("Section [{}] {} + {:#x}", i, sheader.name(), value - start);
+            found = true;
+        }
+    }
+    if !found {
+        println!("Not inside any section");
+    }
+    Ok(())
+}
+
+fn print_segment(metadata: &Metadata, index: usize) {
+    let pheader = &metadata.program_headers()[index];
+    let vaddress = u64::from(pheader.vaddress());
+    println!(
+        "Segment [{}] {:?} {:#x}..{:#x}, file offset {:#x}..{:#x}",
+        index,
+        pheader.typ(),
+        vaddress,
+        vaddress.saturating_add(u64::from(pheader.memsize())),
+        u64::from(pheader.offset()),
+        u64::from(pheader.offset()).saturating_add(u64::from(pheader.filesize()))
+    );
+}
+
+fn print_unmapped(metadata: &Metadata, mapping: AddressMapping, kind: &str) {
+    match mapping {
+        AddressMapping::Unmapped(None, None) => {
+            println!("The file has no Load segments, the {} is not mapped", kind);
+        }
+        AddressMapping::Unmapped(below, above) => {
+            println!("The {} is not inside a Load segment", kind);
+            if let Some(below) = below {
+                print!("After ");
+                print_segment(metadata, below);
+            }
+            if let Some(above) = above {
+                print!("Before ");
+                print_segment(metadata, above);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn read_file(path: &Path) -> Result<Vec<u8>, i32> {
+    std::fs::read(path).map_err(|e| {
+        eprintln!("Can not read file {:?} Reason:", path);
+        eprintln!("{}", e);
+        1
+    })
+}
+
+/// Writes `data` to `output`, which gets the permissions of the `input` file
+fn write_file(output: &Path, input: &Path, data: &[u8]) -> Result<(), i32> {
+    if let Err(e) = std::fs::write(output, data) {
+        eprintln!("Can not write file {:?} Reason:", output);
+        eprintln!("{}", e);
+        return Err(1);
+    }
+    // a new output file gets the permissions of the input, so that executables stay executable
+    if let Ok(metadata) = std::fs::metadata(input) {
+        let _ = std::fs::set_permissions(output, metadata.permissions());
+    }
+    println!("Wrote {:?}", output);
+    Ok(())
+}
+
+fn print_archive(arguments: &cli::Arguments, data: &[u8]) -> Result<(), i32> {
+    let archive = match Archive::parse(data) {
+        Ok(archive) => archive,
+        Err(error) => {
+            eprintln!("Error parsing the archive:");
+            eprintln!("{}", error);
+            return Err(1);
+        }
+    };
+    if arguments.archive_index {
+        if let Err(error) = print_archive_index(&archive) {
+            eprintln!("Error parsing the archive index:");
+            eprintln!("{}", error);
+            return Err(1);
+        }
+    }
+    for member in archive.members() {
+        let member = match member {
+            Ok(member) => member,
+            Err(error) => {
+                eprintln!("Error parsing the archive members:");
+                eprintln!("{}", error);
+                return Err(1);
+            }
+        };
+        println!();
+        let path = arguments.path.as_deref().unwrap_or_else(|| Path::new(""));
+        println!("File: {}({})", path.display(), member.name());
+        if !ElfFile::is_elf(member.data()) {
+            println!("Not an ELF file, skipping");
+            continue;
+        }
+        print_file(arguments, member.data())?;
+    }
+    Ok(())
+}
+
+fn print_archive_index(archive: &Archive) -> elfreader::elf::Result<()> {
+    println!();
+    println!("Index of archive with {} entries:", archive.symbols().len());
+    let mut member_offset = None;
+    for symbol in archive.symbols() {
+        if member_offset != Some(symbol.member_offset) {
+            member_offset = Some(symbol.member_offset);
+            let member = archive.member_at(symbol.member_offset)?;
+            println!("Binary {} contains:", member.name());
+        }
+        println!("\t{}", symbol.name);
+    }
+    Ok(())
+}
+
+fn print_file(arguments: &cli::Arguments, data: &[u8]) -> Result<(), i32> {
+    let parsed = if arguments.lenient {
+        Metadata::parse_bytes_lenient(data)
+    } else {
+        Metadata::parse_bytes(data).map(|metadata| (metadata, Vec::new()))
+    };
+    let (metadata, warnings) = match parsed {
+        Ok(parsed) => parsed,
+        Err(error) => {
+            eprintln!("Error parsing the ELF metadata:");
+            eprintln!("{}", error);
             return Err(1);
         }
     };
     println!("Successfully parsed ELF metadata");
+    for warning in warnings.iter() {
+        println!("Warning: {}", warning.error());
+    }
     if arguments.header {
         println!("Content of the header:");
         println!("{:#x?}", metadata.header());
+        let arch_flags = metadata.header().arch_flags().to_string();
+        if arch_flags.is_empty() {
+            println!("Flags: {:#x}", metadata.header().flags());
+        } else {
+            println!("Flags: {:#x}, {}", metadata.header().flags(), arch_flags);
+        }
+        println!(
+            "Number of program headers: {}",
+            metadata.program_header_count()
+        );
+        println!(
+            "Number of section headers: {}",
+            metadata.section_header_count()
+        );
+        println!(
+            "Section header string table index: {}",
+            metadata.section_names_index()
+        );
     }
     if arguments.program_header {
         println!("Content of the program headers:");
         metadata.program_headers().iter().for_each(|header| {
             println!("{:#018x?}", header);
+            if header.typ() == ProgramHeaderSegmentType::Interp {
+                if let Some(interpreter) = metadata.interpreter() {
+                    println!("[Requesting program interpreter: {}]", interpreter);
+                }
+            }
         });
+        print_segment_sections(&metadata);
     }
     if arguments.section_header {
         println!("Content of the section headers:");
@@ -49,5 +370,518 @@ fn main() -> Result<(), i32> {
             println!("{:#018x?}", header);
         });
     }
+    let elf = if arguments.lenient {
+        ElfFile::parse_lenient(data)
+    } else {
+        ElfFile::parse(data)
+    };
+    let elf = match elf {
+        Ok(elf) => elf,
+        Err(error) => {
+            eprintln!("Error parsing the ELF file:");
+            eprintln!("{}", error);
+            return Err(1);
+        }
+    };
+    if arguments.symbols {
+        if let Err(error) = print_symbols(&elf) {
+            eprintln!("Error parsing the symbol tables:");
+            eprintln!("{}", error);
+            return Err(1);
+        }
+    }
+    if arguments.dynamic {
+        if let Err(error) = print_dynamic(&elf) {
+            eprintln!("Error parsing the dynamic section:");
+            eprintln!("{}", error);
+            return Err(1);
+        }
+    }
+    if arguments.relocations {
+        if let Err(error) = print_relocations(&elf) {
+            eprintln!("Error parsing the relocation sections:");
+            eprintln!("{}", error);
+            return Err(1);
+        }
+    }
+    if arguments.notes {
+        if let Err(error) = print_notes(&elf) {
+            eprintln!("Error parsing the notes:");
+            eprintln!("{}", error);
+            return Err(1);
+        }
+    }
+    if arguments.version_info {
+        if let Err(error) = print_version_info(&elf) {
+            eprintln!("Error parsing the symbol versions:");
+            eprintln!("{}", error);
+            return Err(1);
+        }
+    }
+    if arguments.histogram {
+        if let Err(error) = print_histogram(&elf) {
+            eprintln!("Error parsing the hash tables:");
+            eprintln!("{}", error);
+            return Err(1);
+        }
+    }
+    if let Some(name) = arguments.lookup.as_deref() {
+        if let Err(error) = print_lookup(&elf, name) {
+            eprintln!("Error looking up the symbol:");
+            eprintln!("{}", error);
+            return Err(1);
+        }
+    }
+    if arguments.core {
+        if let Err(error) = print_core(&elf) {
+            eprintln!("Error parsing the core dump notes:");
+            eprintln!("{}", error);
+            return Err(1);
+        }
+    }
+    if arguments.section_groups {
+        if let Err(error) = print_section_groups(&elf) {
+            eprintln!("Error parsing the section groups:");
+            eprintln!("{}", error);
+            return Err(1);
+        }
+    }
+    Ok(())
+}
+
+fn print_segment_sections(metadata: &Metadata) {
+    if metadata.program_headers().is_empty() {
+        return;
+    }
+    println!();
+    println!(" Section to Segment mapping:");
+    println!("  Segment Sections...");
+    for (i, sections) in metadata.segment_sections().iter().enumerate() {
+        print!("   {:02}     ", i);
+        for &section in sections {
+            print!("{} ", metadata.section_headers()[section].name());
+        }
+        println!();
+    }
+}
+
+fn print_version_info(elf: &ElfFile) -> elfreader::elf::Result<()> {
+    let versions = match elf.symbol_versions()? {
+        None => {
+            println!();
+            println!("No version information found in this file.");
+            return Ok(());
+        }
+        Some(versions) => versions,
+    };
+    println!();
+    println!(
+        "Version symbols section contains {} entries:",
+        versions.indices().len()
+    );
+    for (row, indices) in versions.indices().chunks(4).enumerate() {
+        print!("  {:03x}:", row * 4);
+        for index in indices {
+            let name = match versions.resolve(*index) {
+                Some(version) => format!("({})", version),
+                None => "(*invalid*)".to_string(),
+            };
+            print!(" {:>3x} {:<13}", index.index(), name);
+        }
+        println!();
+    }
+    if !versions.definitions().is_empty() {
+        println!();
+        println!(
+            "Version definition section contains {} entries:",
+            versions.definitions().len()
+        );
+        for definition in versions.definitions() {
+            println!(
+                "  Rev: {}  Flags: {}  Index: {}  Cnt: {}  Name: {}",
+                definition.revision(),
+                definition.flags(),
+                definition.index(),
+                definition.parents().len() + 1,
+                definition.name().unwrap_or("")
+            );
+            for (i, parent) in definition.parents().iter().enumerate() {
+                println!("    Parent {}: {}", i + 1, parent);
+            }
+        }
+    }
+    if !versions.requirements().is_empty() {
+        println!();
+        println!(
+            "Version needs section contains {} entries:",
+            versions.requirements().len()
+        );
+        for requirement in versions.requirements() {
+            println!(
+                "  Version: {}  File: {}  Cnt: {}",
+                requirement.revision(),
+                requirement.file(),
+                requirement.entries().len()
+            );
+            for entry in requirement.entries() {
+                println!(
+                    "    Name: {}  Flags: {}  Version: {}",
+                    entry.name(),
+                    entry.flags(),
+                    entry.index()
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+fn print_histogram(elf: &ElfFile) -> elfreader::elf::Result<()> {
+    let tables = elf.hash_tables()?;
+    if tables.is_empty() {
+        println!();
+        println!("There are no hash tables in this file.");
+    }
+    for (sheader, table) in tables.iter() {
+        let histogram = table.histogram()?;
+        let buckets = table.bucket_count();
+        let symbols: usize = histogram
+            .iter()
+            .enumerate()
+            .map(|(length, count)| length * count)
+            .sum();
+        println!();
+        println!(
+            "Histogram for `{}' bucket list length (total of {} buckets):",
+            sheader.name(),
+            buckets
+        );
+        println!(" Length  Number     % of total  Coverage");
+        let mut covered = 0;
+        for (length, count) in histogram.iter().enumerate() {
+            let percentage = (*count * 100) as f64 / buckets.max(1) as f64;
+            if length == 0 {
+                println!("{:>7}  {:<10} ({:>5.1}%)", length, count, percentage);
+                continue;
+            }
+            covered += length * count;
+            let coverage = (covered * 100) as f64 / symbols.max(1) as f64;
+            println!(
+                "{:>7}  {:<10} ({:>5.1}%)    {:>5.1}%",
+                length, count, percentage, coverage
+            );
+        }
+    }
+    Ok(())
+}
+
+fn print_lookup(elf: &ElfFile, name: &str) -> elfreader::elf::Result<()> {
+    match elf.lookup_dynamic_symbol(name)? {
+        None => println!("Symbol '{}' not found in the hash tables", name),
+        Some((index, symbol)) => println!(
+            "Symbol '{}' found at index {}: value {:#x}, size {}, {} {}, section {}",
+            name,
+            index,
+            symbol.value(),
+            u64::from(symbol.size()),
+            symbol.binding(),
+            symbol.typ(),
+            symbol.section_index()
+        ),
+    }
+    Ok(())
+}
+
+fn print_core(elf: &ElfFile) -> elfreader::elf::Result<()> {
+    if elf.header().file_type() != FileType::Core {
+        println!();
+        println!("This file is not a core dump.");
+        return Ok(());
+    }
+    let width = elf.header().word_width().size() * 2;
+    for core_note in elf.core_notes()? {
+        println!();
+        match core_note {
+            CoreNote::PrPsInfo(info) => {
+                println!("Process: {}", info.name);
+                println!("  Arguments: {}", info.arguments);
+                println!(
+                    "  PID: {}  PPID: {}  PGRP: {}  SID: {}  UID: {}  GID: {}",
+                    info.pid, info.ppid, info.pgrp, info.sid, info.uid, info.gid
+                );
+                println!("  State: {} ({})", info.state, info.state_name);
+            }
+            CoreNote::SigInfo(info) => {
+                println!(
+                    "Signal: {} ({})  Code: {}  Errno: {}",
+                    info.signal,
+                    signal_name(info.signal).unwrap_or("unknown"),
+                    info.code,
+                    info.errno
+                );
+                if let Some(address) = info.address {
+                    println!("  Fault address: {:#0width$x}", address, width = width + 2);
+                }
+            }
+            CoreNote::PrStatus(status) => {
+                println!(
+                    "Thread {}: signal {} ({})",
+                    status.pid,
+                    status.signal,
+                    signal_name(status.signal as i32).unwrap_or("none")
+                );
+                for registers in status.registers.chunks(3) {
+                    let line: Vec<_> = registers
+                        .iter()
+                        .map(|(name, value)| {
+                            format!("{:>8}: {:0width$x}", name, value, width = width)
+                        })
+                        .collect();
+                    println!("  {}", line.join(" "));
+                }
+            }
+            CoreNote::Auxv(entries) => {
+                println!("Auxiliary vector:");
+                for entry in entries {
+                    let name = match entry.type_name() {
+                        Some(name) => name.to_string(),
+                        None => format!("<type {}>", entry.typ),
+                    };
+                    println!("  {:<20} {:#x}", name, entry.value);
+                }
+            }
+            CoreNote::File(mappings) => {
+                println!("Mapped files (page size: {}):", mappings.page_size);
+                println!(
+                    "  {:<width$} {:<width$} {:<width$} Path",
+                    "Start",
+                    "End",
+                    "Page Offset",
+                    width = width + 2
+                );
+                for file in mappings.files {
+                    println!(
+                        "  {:#0width$x} {:#0width$x} {:#0width$x} {}",
+                        file.start,
+                        file.end,
+                        file.page_offset,
+                        file.path,
+                        width = width + 2
+                    );
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn print_section_groups(elf: &ElfFile) -> elfreader::elf::Result<()> {
+    let sheaders = elf.section_headers()?.collect::<Result<Vec<_>, _>>()?;
+    // the groups are in the order of their sections
+    let indices = sheaders
+        .iter()
+        .enumerate()
+        .filter(|(_, sheader)| sheader.typ() == SectionHeaderType::Group)
+        .map(|(index, _)| index);
+    let groups = elf.section_groups()?;
+    for (index, group) in indices.zip(groups.iter()) {
+        let sheader = group.section();
+        println!();
+        println!(
+            "{}group section [{:5}] `{}' [{}] contains {} sections:",
+            if group.is_comdat() { "COMDAT " } else { "" },
+            index,
+            sheader.name(),
+            group.signature(),
+            group.members().len()
+        );
+        println!("   [Index]    Name");
+        for (member_index, member) in group.members() {
+            println!("   [{:5}]   {}", member_index, member.name());
+        }
+    }
+    if groups.is_empty() {
+        println!();
+        println!("There are no section groups in this file.");
+    }
+    Ok(())
+}
+
+fn print_notes(elf: &ElfFile) -> elfreader::elf::Result<()> {
+    for (location, notes) in elf.notes()? {
+        println!();
+        println!("Displaying notes found in: {}", location);
+        println!("  {:<20} {:<10} Description", "Owner", "Data size");
+        for note in notes {
+            let owner = String::from_utf8_lossy(note.name());
+            let typ = match note.type_name() {
+                Some(name) => name.to_string(),
+                None => format!("Unknown note type: ({:#010x})", note.typ()),
+            };
+            println!("  {:<20} {:#010x} {}", owner, note.descriptor().len(), typ);
+            match CoreNote::decode(&note, elf.header().arch())? {
+                Some(core_note) => println!("    {}", core_note),
+                None => println!("    {}", note.decode()?),
+            }
+        }
+    }
+    Ok(())
+}
+
+fn print_relocations(elf: &ElfFile) -> elfreader::elf::Result<()> {
+    let arch = elf.header().arch();
+    let width = elf.header().word_width().size() * 2;
+    let tables = elf.relocation_tables()?;
+    // relocations against section symbols are named after the section
+    let sheaders = elf.section_headers()?.collect::<Result<Vec<_>, _>>()?;
+    if tables.is_empty() {
+        println!();
+        println!("There are no relocations in this file.");
+    }
+    for table in tables.iter() {
+        let entries = table.entries();
+        println!();
+        println!(
+            "Relocation section '{}' at offset {:#x} contains {} {}:",
+            table.section().name(),
+            table.section().offset(),
+            entries.len(),
+            if entries.len() == 1 {
+                "entry"
+            } else {
+                "entries"
+            }
+        );
+        println!(
+            "  {:<width$} {:<24} {:<width$} Sym. Name + Addend",
+            "Offset",
+            "Type",
+            "Sym. Value",
+            width = width
+        );
+        for relocation in entries {
+            let relocation = relocation?;
+            let typ = match relocation.type_name(arch) {
+                Some(name) => name.to_string(),
+                None => format!("<unknown>: {:#x}", relocation.typ()),
+            };
+            let (value, name) = match table.symbol(&relocation) {
+                None => (String::new(), String::new()),
+                Some(symbol) => {
+                    let symbol = symbol?;
+                    let name = match (symbol.typ(), symbol.section_index()) {
+                        (SymbolType::Section, SymbolSectionIndex::Index(index))
+                            if symbol.name().is_empty() =>
+                        {
+                            sheaders
+                                .get(index as usize)
+                                .map_or("", |sheader| sheader.name())
+                        }
+                        _ => symbol.name(),
+                    };
+                    (
+                        format!("{:0width$x}", symbol.value(), width = width),
+                        name.to_string(),
+                    )
+                }
+            };
+            let addend = match relocation.addend() {
+                None => String::new(),
+                Some(addend) if addend < 0 => format!(" - {:x}", -(addend as i128)),
+                Some(addend) => format!(" + {:x}", addend),
+            };
+            println!(
+                "  {:0width$x} {:<24} {:<width$} {}{}",
+                relocation.offset(),
+                typ,
+                value,
+                name,
+                addend,
+                width = width
+            );
+        }
+    }
+    Ok(())
+}
+
+fn print_dynamic(elf: &ElfFile) -> elfreader::elf::Result<()> {
+    let entries = match elf.dynamic_entries()? {
+        None => {
+            println!();
+            println!("There is no dynamic section in this file.");
+            return Ok(());
+        }
+        Some(entries) => entries,
+    };
+    let width = elf.header().word_width().size() * 2 + 2;
+    println!();
+    println!("Dynamic section contains {} entries:", entries.len());
+    println!(
+        "  {:<width$} {:<20} Name/Value",
+        "Tag",
+        "Type",
+        width = width
+    );
+    for entry in entries.iter() {
+        println!(
+            "  {:#0width$x} {:<20} {}",
+            entry.tag(),
+            format!("({})", entry.tag_name()),
+            entry,
+            width = width
+        );
+    }
+    Ok(())
+}
+
+fn print_symbols(elf: &ElfFile) -> elfreader::elf::Result<()> {
+    let width = elf.header().word_width().size() * 2;
+    let versions = elf.symbol_versions()?;
+    for sheader in elf.section_headers()? {
+        let sheader = sheader?;
+        match sheader.typ() {
+            SectionHeaderType::SymbolTable | SectionHeaderType::DynamicSymbolTable => {}
+            _ => continue,
+        }
+        let symbols = elf.symbols(&sheader)?;
+        println!();
+        println!(
+            "Symbol table '{}' contains {} entries:",
+            sheader.name(),
+            symbols.len()
+        );
+        println!(
+            "{:>6}: {:<width$} {:>5} {:<7} {:<6} {:<8} {:>3} Name",
+            "Num",
+            "Value",
+            "Size",
+            "Type",
+            "Bind",
+            "Vis",
+            "Ndx",
+            width = width
+        );
+        for (i, symbol) in symbols.enumerate() {
+            let symbol = symbol?;
+            let name = match (&versions, sheader.typ()) {
+                (Some(versions), SectionHeaderType::DynamicSymbolTable) => {
+                    versions.versioned_name(i, &symbol)
+                }
+                _ => symbol.name().to_string(),
+            };
+            println!(
+                "{:>6}: {:0width$x} {:>5} {:<7} {:<6} {:<8} {:>3} {}",
+                i,
+                symbol.value(),
+                u64::from(symbol.size()),
+                symbol.typ().to_string(),
+                symbol.binding().to_string(),
+                symbol.visibility().to_string(),
+                symbol.section_index().to_string(),
+                name,
+                width = width
+            );
+        }
+    }
     Ok(())
 }